@@ -1,31 +1,67 @@
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::elements::{atomic_number, element_symbol};
+
+/// The public Basis Set Exchange that `fetch_basis` and friends talk to.
+pub const BSE_BASE_URL: &str = "https://www.basissetexchange.org";
+/// Connect and overall request timeout for BSE requests.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Retries after a 5xx response or a failed connection before giving up.
+pub const DEFAULT_RETRIES: u32 = 3;
+/// Delay before the first retry; it doubles on each subsequent attempt.
+pub const DEFAULT_BACKOFF: Duration = Duration::from_millis(250);
+/// Built-in cache directory, relative to the CWD; see [`default_cache_root`].
+pub const DEFAULT_CACHE_ROOT: &str = "data/basis";
+/// Environment variable that overrides [`DEFAULT_CACHE_ROOT`].
+pub const CACHE_ROOT_ENV: &str = "QCHEM_BASIS_CACHE";
+
+/// Cache root used by `fetch_basis`, `load_basis` and `init_basis`: the
+/// `QCHEM_BASIS_CACHE` environment variable when set and non-empty, otherwise
+/// `data/basis`.
+pub fn default_cache_root() -> PathBuf {
+  cache_root_from(std::env::var(CACHE_ROOT_ENV).ok())
+}
+
+fn cache_root_from(env_value: Option<String>) -> PathBuf {
+  match env_value {
+    Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+    _ => PathBuf::from(DEFAULT_CACHE_ROOT),
+  }
+}
 
-/// All 118 known element symbols in title case, indexed by atomic number (1-based).
-const ELEMENTS: &[&str] = &[
-  "H",  "He", "Li", "Be", "B",  "C",  "N",  "O",  "F",  "Ne",
-  "Na", "Mg", "Al", "Si", "P",  "S",  "Cl", "Ar", "K",  "Ca",
-  "Sc", "Ti", "V",  "Cr", "Mn", "Fe", "Co", "Ni", "Cu", "Zn",
-  "Ga", "Ge", "As", "Se", "Br", "Kr", "Rb", "Sr", "Y",  "Zr",
-  "Nb", "Mo", "Tc", "Ru", "Rh", "Pd", "Ag", "Cd", "In", "Sn",
-  "Sb", "Te", "I",  "Xe", "Cs", "Ba", "La", "Ce", "Pr", "Nd",
-  "Pm", "Sm", "Eu", "Gd", "Tb", "Dy", "Ho", "Er", "Tm", "Yb",
-  "Lu", "Hf", "Ta", "W",  "Re", "Os", "Ir", "Pt", "Au", "Hg",
-  "Tl", "Pb", "Bi", "Po", "At", "Rn", "Fr", "Ra", "Ac", "Th",
-  "Pa", "U",  "Np", "Pu", "Am", "Cm", "Bk", "Cf", "Es", "Fm",
-  "Md", "No", "Lr", "Rf", "Db", "Sg", "Bh", "Hs", "Mt", "Ds",
-  "Rg", "Cn", "Nh", "Fl", "Mc", "Lv", "Ts", "Og",
-];
-
-const BSE_BASE_URL: &str = "https://www.basissetexchange.org";
-const DEFAULT_CACHE_ROOT: &str = "data/basis";
+/// How a single BSE request is bounded and retried; see
+/// [`fetch_basis_with_policy`]. The default uses [`DEFAULT_TIMEOUT`],
+/// [`DEFAULT_RETRIES`] and [`DEFAULT_BACKOFF`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RequestPolicy {
+  /// Connect and overall timeout of each attempt.
+  pub timeout: Duration,
+  /// Retries after a 5xx response or a failed connection; 0 disables them.
+  pub retries: u32,
+  /// Delay before the first retry; it doubles on each subsequent attempt.
+  pub backoff: Duration,
+}
+
+impl Default for RequestPolicy {
+  fn default() -> Self {
+    Self { timeout: DEFAULT_TIMEOUT, retries: DEFAULT_RETRIES, backoff: DEFAULT_BACKOFF }
+  }
+}
 
 #[derive(Debug, PartialEq)]
 pub enum BseError {
   InvalidElement(String),
   InvalidBasisSetName(String),
   ElementNotInBasisSet { element: String, basis_name: String },
-  UnknownBasisSet(String),
+  /// The BSE does not know `basis_name` (normalized); `url` is the request
+  /// that said so.
+  UnknownBasisSet { basis_name: String, url: String },
+  /// A failed connection or unexpected HTTP status. Status messages name the
+  /// request URL, which carries the basis and element symbols.
   NetworkError(String),
+  /// The BSE did not answer within the request timeout.
+  Timeout(Duration),
   IoError(String),
   InvalidResponse(String),
 }
@@ -39,10 +75,12 @@ impl std::fmt::Display for BseError {
         write!(f, "invalid basis set name: {:?}", s),
       BseError::ElementNotInBasisSet { element, basis_name } =>
         write!(f, "element {} not found in basis set {}", element, basis_name),
-      BseError::UnknownBasisSet(s) =>
-        write!(f, "unknown basis set: {:?}", s),
+      BseError::UnknownBasisSet { basis_name, url } =>
+        write!(f, "unknown basis set: {:?} (GET {})", basis_name, url),
       BseError::NetworkError(s) =>
         write!(f, "network error: {}", s),
+      BseError::Timeout(d) =>
+        write!(f, "request timed out after {:?}", d),
       BseError::IoError(s) =>
         write!(f, "I/O error: {}", s),
       BseError::InvalidResponse(s) =>
@@ -52,9 +90,162 @@ impl std::fmt::Display for BseError {
 }
 
 /// Downloads (if needed) and returns the path to the cached QCSchema JSON file
-/// for `element` in `basis_name`, using `data/basis` as the cache root.
+/// for `element` in `basis_name`, under [`default_cache_root`].
 pub fn fetch_basis(element: &str, basis_name: &str) -> Result<PathBuf, BseError> {
-  fetch_basis_impl(element, basis_name, BSE_BASE_URL, Path::new(DEFAULT_CACHE_ROOT))
+  fetch_basis_in(element, basis_name, &default_cache_root())
+}
+
+/// [`fetch_basis`] with the request timeout set to `timeout` instead of
+/// [`DEFAULT_TIMEOUT`]; exceeding it gives [`BseError::Timeout`].
+pub fn fetch_basis_with_timeout(
+  element: &str,
+  basis_name: &str,
+  timeout: Duration,
+) -> Result<PathBuf, BseError> {
+  fetch_basis_with_policy(element, basis_name, RequestPolicy { timeout, ..RequestPolicy::default() })
+}
+
+/// [`fetch_basis`] with the request timeout, retry count and backoff taken
+/// from `policy` instead of the defaults.
+pub fn fetch_basis_with_policy(
+  element: &str,
+  basis_name: &str,
+  policy: RequestPolicy,
+) -> Result<PathBuf, BseError> {
+  fetch_basis_timed(element, basis_name, BSE_BASE_URL, &default_cache_root(), policy)
+}
+
+/// [`fetch_basis`] with the cache rooted at `cache_root`; the file lives at
+/// `{cache_root}/{basis_name}/{element}.json`.
+pub fn fetch_basis_in(
+  element: &str,
+  basis_name: &str,
+  cache_root: &Path,
+) -> Result<PathBuf, BseError> {
+  fetch_basis_impl(element, basis_name, BSE_BASE_URL, cache_root)
+}
+
+/// [`fetch_basis_in`] against the BSE at `base_url` (scheme and host, no
+/// trailing slash) instead of [`BSE_BASE_URL`].
+pub fn fetch_basis_from(
+  element: &str,
+  basis_name: &str,
+  base_url: &str,
+  cache_root: &Path,
+) -> Result<PathBuf, BseError> {
+  fetch_basis_impl(element, basis_name, base_url, cache_root)
+}
+
+/// Downloads every requested element of `basis_name` that is not yet cached in
+/// one BSE request and returns the per-element cache paths, in request order,
+/// under [`default_cache_root`]. See [`fetch_basis_multi_in`].
+pub fn fetch_basis_multi(elements: &[&str], basis_name: &str) -> Result<Vec<PathBuf>, BseError> {
+  fetch_basis_multi_in(elements, basis_name, &default_cache_root())
+}
+
+/// [`fetch_basis_multi`] with the cache rooted at `cache_root`.
+///
+/// The combined response is kept as
+/// `{cache_root}/{basis_name}/_multi_{E1}_{E2}….json` (symbols sorted by
+/// atomic number) and split into the usual `{element}.json` files, so later
+/// single-element loads are cache hits. No request is made when every element
+/// is already cached.
+pub fn fetch_basis_multi_in(
+  elements: &[&str],
+  basis_name: &str,
+  cache_root: &Path,
+) -> Result<Vec<PathBuf>, BseError> {
+  fetch_basis_multi_impl(elements, basis_name, BSE_BASE_URL, cache_root)
+}
+
+/// [`fetch_basis_multi_in`] against the BSE at `base_url` instead of
+/// [`BSE_BASE_URL`].
+pub fn fetch_basis_multi_from(
+  elements: &[&str],
+  basis_name: &str,
+  base_url: &str,
+  cache_root: &Path,
+) -> Result<Vec<PathBuf>, BseError> {
+  fetch_basis_multi_impl(elements, basis_name, base_url, cache_root)
+}
+
+/// Returns the requested `elements` that have no valid cached file for
+/// `basis_name` under `cache_root`, in request order. Never touches the
+/// network; unrecognised symbols are reported as missing unchanged.
+pub fn cached_coverage(elements: &[&str], basis_name: &str, cache_root: &Path) -> Vec<String> {
+  let basis_norm = basis_name.to_lowercase();
+  elements
+    .iter()
+    .filter(|&&el| match normalize_element(el) {
+      Ok(norm) => {
+        !is_valid_cache(&cache_root.join(&basis_norm).join(format!("{}.json", norm)))
+      }
+      Err(_) => true,
+    })
+    .map(|el| el.to_string())
+    .collect()
+}
+
+/// Asks the BSE metadata endpoint which of `elements` the latest version of
+/// `basis_name` lacks, and returns those symbols (normalized, deduplicated, in
+/// request order). One request is made regardless of how many elements are
+/// asked for, and nothing is downloaded or cached.
+pub fn check_basis_covers(elements: &[&str], basis_name: &str) -> Result<Vec<String>, BseError> {
+  check_basis_covers_impl(elements, basis_name, BSE_BASE_URL)
+}
+
+/// [`check_basis_covers`] against the BSE at `base_url` instead of
+/// [`BSE_BASE_URL`].
+pub fn check_basis_covers_from(
+  elements: &[&str],
+  basis_name: &str,
+  base_url: &str,
+) -> Result<Vec<String>, BseError> {
+  check_basis_covers_impl(elements, basis_name, base_url)
+}
+
+fn check_basis_covers_impl(
+  elements: &[&str],
+  basis_name: &str,
+  base_url: &str,
+) -> Result<Vec<String>, BseError> {
+  if basis_name.is_empty() {
+    return Err(BseError::InvalidBasisSetName(basis_name.to_string()));
+  }
+  let basis_norm = basis_name.to_lowercase();
+  let mut normalized = Vec::with_capacity(elements.len());
+  for el in elements {
+    let norm = normalize_element(el)?;
+    if !normalized.contains(&norm) {
+      normalized.push(norm);
+    }
+  }
+
+  let url = format!("{}/api/metadata", base_url);
+  let (_, metadata) = get_json(&url, RequestPolicy::default(), || {
+    BseError::NetworkError(format!("unexpected HTTP status 404 for {}", url))
+  })?;
+  let entry = metadata.get(&basis_norm).ok_or_else(|| BseError::UnknownBasisSet {
+    basis_name: basis_norm.clone(),
+    url: url.clone(),
+  })?;
+  let covered = entry
+    .get("latest_version")
+    .and_then(|v| v.as_str())
+    .and_then(|latest| entry.get("versions")?.get(latest)?.get("elements")?.as_array())
+    .ok_or_else(|| {
+      BseError::InvalidResponse(format!("no element list for {} in metadata", basis_norm))
+    })?;
+
+  Ok(
+    normalized
+      .into_iter()
+      .filter(|el| {
+        let z = atomic_number(el).unwrap_or(0).to_string();
+        !covered.iter().any(|v| v.as_str() == Some(z.as_str()))
+      })
+      .collect(),
+  )
 }
 
 fn normalize_element(element: &str) -> Result<String, BseError> {
@@ -66,7 +257,7 @@ fn normalize_element(element: &str) -> Result<String, BseError> {
       upper + &chars.as_str().to_lowercase()
     }
   };
-  if ELEMENTS.contains(&normalized.as_str()) {
+  if atomic_number(&normalized).is_some() {
     Ok(normalized)
   } else {
     Err(BseError::InvalidElement(element.to_string()))
@@ -97,6 +288,16 @@ fn fetch_basis_impl(
   basis_name: &str,
   base_url: &str,
   cache_root: &Path,
+) -> Result<PathBuf, BseError> {
+  fetch_basis_timed(element, basis_name, base_url, cache_root, RequestPolicy::default())
+}
+
+fn fetch_basis_timed(
+  element: &str,
+  basis_name: &str,
+  base_url: &str,
+  cache_root: &Path,
+  policy: RequestPolicy,
 ) -> Result<PathBuf, BseError> {
   if basis_name.is_empty() {
     return Err(BseError::InvalidBasisSetName(basis_name.to_string()));
@@ -113,51 +314,203 @@ fn fetch_basis_impl(
     return Ok(path);
   }
 
+  let (body, json) = request_qcschema(base_url, &basis_norm, &element_norm, policy)?;
+
+  if elements_field_is_empty(&json) {
+    return Err(BseError::ElementNotInBasisSet {
+      element: element_norm,
+      basis_name: basis_norm,
+    });
+  }
+
+  write_cache(&path, &body)?;
+  Ok(path)
+}
+
+fn fetch_basis_multi_impl(
+  elements: &[&str],
+  basis_name: &str,
+  base_url: &str,
+  cache_root: &Path,
+) -> Result<Vec<PathBuf>, BseError> {
+  if basis_name.is_empty() {
+    return Err(BseError::InvalidBasisSetName(basis_name.to_string()));
+  }
+  let basis_norm = basis_name.to_lowercase();
+  let dir = cache_root.join(&basis_norm);
+
+  let normalized = elements
+    .iter()
+    .map(|el| normalize_element(el))
+    .collect::<Result<Vec<_>, _>>()?;
+  let paths: Vec<PathBuf> =
+    normalized.iter().map(|el| dir.join(format!("{}.json", el))).collect();
+
+  // Distinct uncached symbols, sorted by atomic number.
+  let mut missing: Vec<&str> = normalized
+    .iter()
+    .zip(&paths)
+    .filter(|(_, path)| !is_valid_cache(path))
+    .map(|(el, _)| el.as_str())
+    .collect();
+  missing.sort_by_key(|el| atomic_number(el).unwrap_or(0));
+  missing.dedup();
+  if missing.is_empty() {
+    return Ok(paths);
+  }
+
+  let (body, json) =
+    request_qcschema(base_url, &basis_norm, &missing.join(","), RequestPolicy::default())?;
+
+  // Split into one single-element document per symbol, keeping the other
+  // top-level fields (name, description, …) as the BSE would send them.
+  // Nothing is cached unless the response holds every requested element.
+  let Some(serde_json::Value::Object(by_z)) = json.get("elements") else {
+    return Err(BseError::InvalidResponse("missing elements object".to_string()));
+  };
+  let mut singles = Vec::with_capacity(missing.len());
+  for el in &missing {
+    let z = atomic_number(el).unwrap_or(0).to_string();
+    let Some(data) = by_z.get(&z) else {
+      return Err(BseError::ElementNotInBasisSet {
+        element: el.to_string(),
+        basis_name: basis_norm,
+      });
+    };
+    let mut single = json.clone();
+    single["elements"] = serde_json::json!({ z: data });
+    singles.push((el, single));
+  }
+
+  write_cache(&dir.join(format!("_multi_{}.json", missing.join("_"))), &body)?;
+  for (el, single) in singles {
+    write_cache(&dir.join(format!("{}.json", el)), &single.to_string())?;
+  }
+  Ok(paths)
+}
+
+/// GETs the QCSchema document for `elements_query` (one symbol, or several
+/// joined by commas) and returns the raw body with its parsed JSON; a 404
+/// means the basis set is unknown. See [`get_json`].
+fn request_qcschema(
+  base_url: &str,
+  basis_norm: &str,
+  elements_query: &str,
+  policy: RequestPolicy,
+) -> Result<(String, serde_json::Value), BseError> {
   let url = format!(
     "{}/api/basis/{}/format/qcschema?elements={}",
-    base_url, basis_norm, element_norm
+    base_url, basis_norm, elements_query
   );
+  get_json(&url, policy, || BseError::UnknownBasisSet {
+    basis_name: basis_norm.to_string(),
+    url: url.clone(),
+  })
+}
 
-  let response = reqwest::blocking::get(&url)
-    .map_err(|e| BseError::NetworkError(e.to_string()))?;
-
-  match response.status().as_u16() {
-    404 => return Err(BseError::UnknownBasisSet(basis_norm)),
-    200 => {}
-    code => return Err(BseError::NetworkError(format!("unexpected HTTP status {}", code))),
-  }
+/// GETs `url` and returns the raw body with its parsed JSON. Both connecting
+/// and the whole request are bounded by `policy.timeout`; a 404 is reported as
+/// `not_found()` and any other unexpected status as a `NetworkError` naming
+/// `url`.
+///
+/// Responses may be gzip-encoded; the body is decoded before it is returned,
+/// so callers (and the cache) only ever see JSON text.
+///
+/// A 5xx status or a failed connection is retried up to `policy.retries`
+/// times, sleeping `policy.backoff` before the first retry and doubling it
+/// each time. Timeouts, 404 and other statuses fail immediately.
+fn get_json(
+  url: &str,
+  policy: RequestPolicy,
+  not_found: impl FnOnce() -> BseError,
+) -> Result<(String, serde_json::Value), BseError> {
+  let network_error = |e: reqwest::Error| {
+    if e.is_timeout() {
+      BseError::Timeout(policy.timeout)
+    } else {
+      BseError::NetworkError(e.to_string())
+    }
+  };
+  let client = reqwest::blocking::Client::builder()
+    .gzip(true)
+    .connect_timeout(policy.timeout)
+    .timeout(policy.timeout)
+    .build()
+    .map_err(network_error)?;
+
+  let mut attempt = 0;
+  let response = loop {
+    let retryable = match client.get(url).send() {
+      Ok(response) => match response.status().as_u16() {
+        200 => break response,
+        404 => return Err(not_found()),
+        code @ 500..=599 => unexpected_status(code, url),
+        code => return Err(unexpected_status(code, url)),
+      },
+      Err(e) if e.is_connect() && !e.is_timeout() => network_error(e),
+      Err(e) => return Err(network_error(e)),
+    };
+    if attempt >= policy.retries {
+      return Err(retryable);
+    }
+    std::thread::sleep(policy.backoff * 2u32.pow(attempt));
+    attempt += 1;
+  };
 
-  let body = response.text()
-    .map_err(|e| BseError::NetworkError(e.to_string()))?;
+  let body = response.text().map_err(network_error)?;
 
   let json: serde_json::Value = serde_json::from_str(&body)
     .map_err(|e| BseError::InvalidResponse(e.to_string()))?;
+  Ok((body, json))
+}
 
-  if elements_field_is_empty(&json) {
-    return Err(BseError::ElementNotInBasisSet {
-      element: element_norm,
-      basis_name: basis_norm,
-    });
-  }
+fn unexpected_status(code: u16, url: &str) -> BseError {
+  BseError::NetworkError(format!("unexpected HTTP status {} for {}", code, url))
+}
 
+fn write_cache(path: &Path, content: &str) -> Result<(), BseError> {
   let dir = path.parent().expect("cache path always has a parent");
   std::fs::create_dir_all(dir)
     .map_err(|e| BseError::IoError(e.to_string()))?;
-  std::fs::write(&path, &body)
-    .map_err(|e| BseError::IoError(e.to_string()))?;
-
-  Ok(path)
+  std::fs::write(path, content)
+    .map_err(|e| BseError::IoError(e.to_string()))
 }
 
 // ============================================================================
 // Types for parsed basis sets
 // ============================================================================
 
+/// Significant digits written for exponents and coefficients by
+/// `to_qcschema_json`; 17 is enough for any f64 to round-trip exactly.
+const JSON_FLOAT_DIGITS: usize = 17;
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct ElectronShell {
   pub angular_momentum: u32,
   pub exponents: Vec<f64>,
   pub coefficients: Vec<f64>,
+  /// Index of the file shell this one was split from. Shells split from one
+  /// SP (or other general) contraction share the id and their exponents;
+  /// `None` for shells built by hand.
+  pub general_contraction_group: Option<usize>,
+}
+
+/// One angular-momentum channel of an effective core potential: the sum over
+/// `k` of `coefficients[k] * r^(r_exponents[k] - 2) * exp(-gaussian_exponents[k] * r²)`,
+/// following the QCSchema/BSE convention for `r_exponents`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct EcpShell {
+  pub angular_momentum: u32,
+  pub r_exponents: Vec<i32>,
+  pub gaussian_exponents: Vec<f64>,
+  pub coefficients: Vec<f64>,
+}
+
+/// A scalar effective core potential replacing `ecp_electrons` core electrons.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Ecp {
+  pub ecp_electrons: u32,
+  pub shells: Vec<EcpShell>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -165,6 +518,192 @@ pub struct BasisSet {
   pub element: String,
   pub atomic_number: u32,
   pub shells: Vec<ElectronShell>,
+  /// Present when the file carries `ecp_potentials` for this element.
+  pub ecp: Option<Ecp>,
+}
+
+/// A shell as written in the QCSchema file: one set of exponents shared by one
+/// coefficient vector per angular momentum (two for an SP shell).
+#[derive(Debug, PartialEq, Clone)]
+pub struct CombinedShell {
+  pub angular_momenta: Vec<u32>,
+  pub exponents: Vec<f64>,
+  /// `coefficients[k]` contracts `exponents` for `angular_momenta[k]`.
+  pub coefficients: Vec<Vec<f64>>,
+  /// Group id given to the shells [`CombinedBasisSet::split`] makes from this
+  /// one; `None` (as parsed) means this shell's own index.
+  pub general_contraction_group: Option<usize>,
+}
+
+/// A basis set whose shells may combine several angular momenta; see
+/// [`parse_basis_with`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct CombinedBasisSet {
+  pub element: String,
+  pub atomic_number: u32,
+  pub shells: Vec<CombinedShell>,
+  pub ecp: Option<Ecp>,
+}
+
+impl CombinedBasisSet {
+  /// Splits every shell into one `ElectronShell` per angular momentum, in file
+  /// order, each with its own copy of the shared exponents. The pieces of
+  /// shell `k` carry its `general_contraction_group`, or `Some(k)` if unset.
+  pub fn split(&self) -> BasisSet {
+    let shells = self
+      .shells
+      .iter()
+      .enumerate()
+      .flat_map(|(k, sh)| {
+        sh.angular_momenta.iter().zip(&sh.coefficients).map(move |(&l, c)| ElectronShell {
+          angular_momentum: l,
+          exponents: sh.exponents.clone(),
+          coefficients: c.clone(),
+          general_contraction_group: sh.general_contraction_group.or(Some(k)),
+        })
+      })
+      .collect();
+    BasisSet {
+      element: self.element.clone(),
+      atomic_number: self.atomic_number,
+      shells,
+      ecp: self.ecp.clone(),
+    }
+  }
+}
+
+/// Options for [`parse_basis_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseBasisOptions {
+  /// Split SP (and other multi-angular-momentum) shells into one shell per
+  /// angular momentum, as [`parse_basis`] does. When false they are kept
+  /// combined so consumers can share work across the common exponents.
+  pub split_sp: bool,
+}
+
+impl Default for ParseBasisOptions {
+  fn default() -> Self {
+    ParseBasisOptions { split_sp: true }
+  }
+}
+
+impl BasisSet {
+  /// Number of contracted shells (after SP splitting).
+  pub fn n_shells(&self) -> usize {
+    self.shells.len()
+  }
+
+  /// Total number of primitive Gaussians summed over all shells.
+  pub fn n_primitives(&self) -> usize {
+    self.shells.iter().map(|s| s.exponents.len()).sum()
+  }
+
+  /// Highest shell angular momentum; 0 when there are no shells.
+  pub fn max_angular_momentum(&self) -> u32 {
+    self.shells.iter().map(|s| s.angular_momentum).max().unwrap_or(0)
+  }
+
+  /// Number of Cartesian basis functions, (l+1)(l+2)/2 per shell, as the AO
+  /// basis built from this set will have per atom.
+  pub fn n_cartesian_functions(&self) -> usize {
+    self
+      .shells
+      .iter()
+      .map(|s| ((s.angular_momentum + 1) * (s.angular_momentum + 2) / 2) as usize)
+      .sum()
+  }
+
+  /// Serializes the basis set as a single-element QCSchema JSON document that
+  /// [`parse_basis_str`] reads back exactly. Consecutive shells of one
+  /// `general_contraction_group` are written as a single combined shell, so an
+  /// SP shell stays SP; every other shell is written separately.
+  pub fn to_qcschema_json(&self) -> String {
+    let fmt = |v: &f64| serde_json::Value::String(format!("{:.*e}", JSON_FLOAT_DIGITS - 1, v));
+    let shells: Vec<serde_json::Value> = self
+      .shells
+      .chunk_by(|a, b| {
+        a.general_contraction_group.is_some()
+          && a.general_contraction_group == b.general_contraction_group
+          && a.exponents == b.exponents
+      })
+      .map(|group| {
+        serde_json::json!({
+          "function_type": "gto",
+          "angular_momentum": group.iter().map(|sh| sh.angular_momentum).collect::<Vec<_>>(),
+          "exponents": group[0].exponents.iter().map(fmt).collect::<Vec<_>>(),
+          "coefficients": group
+            .iter()
+            .map(|sh| sh.coefficients.iter().map(fmt).collect::<Vec<_>>())
+            .collect::<Vec<_>>(),
+        })
+      })
+      .collect();
+    let mut element = serde_json::json!({ "electron_shells": shells });
+    if let Some(ecp) = &self.ecp {
+      let potentials: Vec<serde_json::Value> = ecp
+        .shells
+        .iter()
+        .map(|sh| {
+          serde_json::json!({
+            "ecp_type": "scalar_ecp",
+            "angular_momentum": [sh.angular_momentum],
+            "r_exponents": sh.r_exponents,
+            "gaussian_exponents": sh.gaussian_exponents.iter().map(fmt).collect::<Vec<_>>(),
+            "coefficients": [sh.coefficients.iter().map(fmt).collect::<Vec<_>>()],
+          })
+        })
+        .collect();
+      element["ecp_electrons"] = ecp.ecp_electrons.into();
+      element["ecp_potentials"] = potentials.into();
+    }
+    serde_json::json!({ "elements": { self.atomic_number.to_string(): element } }).to_string()
+  }
+
+  /// True if `other` describes the same element, shells (including their
+  /// grouping) and ECP, with every exponent and coefficient within `tol`.
+  /// Shells are compared in order.
+  pub fn approx_eq(&self, other: &BasisSet, tol: f64) -> bool {
+    let close = |a: &[f64], b: &[f64]| {
+      a.len() == b.len() && a.iter().zip(b).all(|(x, y)| (x - y).abs() <= tol)
+    };
+    self.element == other.element
+      && self.atomic_number == other.atomic_number
+      && self.shells.len() == other.shells.len()
+      && self.shells.iter().zip(&other.shells).all(|(a, b)| {
+        a.angular_momentum == b.angular_momentum
+          && a.general_contraction_group == b.general_contraction_group
+          && close(&a.exponents, &b.exponents)
+          && close(&a.coefficients, &b.coefficients)
+      })
+      && match (&self.ecp, &other.ecp) {
+        (None, None) => true,
+        (Some(a), Some(b)) => {
+          a.ecp_electrons == b.ecp_electrons
+            && a.shells.len() == b.shells.len()
+            && a.shells.iter().zip(&b.shells).all(|(x, y)| {
+              x.angular_momentum == y.angular_momentum
+                && x.r_exponents == y.r_exponents
+                && close(&x.gaussian_exponents, &y.gaussian_exponents)
+                && close(&x.coefficients, &y.coefficients)
+            })
+        }
+        _ => false,
+      }
+  }
+
+  /// Rescales every shell's coefficients so that the contracted function has
+  /// unit self-overlap. Shells with zero self-overlap are left unchanged.
+  pub fn renormalize(&mut self) {
+    for shell in &mut self.shells {
+      let overlap = shell_self_overlap(shell);
+      if overlap > 0.0 {
+        let scale = overlap.powf(-0.5);
+        for c in &mut shell.coefficients {
+          *c *= scale;
+        }
+      }
+    }
+  }
 }
 
 #[derive(Debug, PartialEq)]
@@ -176,6 +715,13 @@ pub enum ParseError {
   InvalidAtomicNumber(String),
   NoElectronShells,
   MalformedShell { index: usize, reason: String },
+  /// Entry `index` of `ecp_potentials` (or the ECP as a whole) is invalid.
+  MalformedEcp { index: usize, reason: String },
+  /// An element symbol in a Gaussian94 file (or the one requested) is not
+  /// a known element.
+  InvalidElement(String),
+  /// The Gaussian94 file has no block for the requested element.
+  ElementNotFound(String),
 }
 
 impl std::fmt::Display for ParseError {
@@ -191,6 +737,10 @@ impl std::fmt::Display for ParseError {
       ParseError::NoElectronShells => write!(f, "no electron shells found"),
       ParseError::MalformedShell { index, reason } =>
         write!(f, "shell {}: {}", index, reason),
+      ParseError::MalformedEcp { index, reason } =>
+        write!(f, "ECP potential {}: {}", index, reason),
+      ParseError::InvalidElement(s) => write!(f, "invalid element: {:?}", s),
+      ParseError::ElementNotFound(s) => write!(f, "no basis functions for element {}", s),
     }
   }
 }
@@ -199,6 +749,9 @@ impl std::fmt::Display for ParseError {
 pub enum LoadError {
   Fetch(BseError),
   Parse(ParseError),
+  /// The basis file parsed cleanly but describes a different element than the
+  /// one requested (e.g. a corrupted cache file).
+  ElementMismatch { requested: String, found: String },
 }
 
 impl std::fmt::Display for LoadError {
@@ -206,6 +759,9 @@ impl std::fmt::Display for LoadError {
     match self {
       LoadError::Fetch(e) => write!(f, "fetch error: {}", e),
       LoadError::Parse(e) => write!(f, "parse error: {}", e),
+      LoadError::ElementMismatch { requested, found } => write!(
+        f, "basis file for {} contains element {}", requested, found
+      ),
     }
   }
 }
@@ -222,28 +778,122 @@ impl std::fmt::Display for LoadError {
 pub fn parse_basis(path: &Path) -> Result<BasisSet, ParseError> {
   let content = std::fs::read_to_string(path)
     .map_err(|e| ParseError::IoError(e.to_string()))?;
+  parse_basis_str(&content)
+}
 
-  let json: serde_json::Value = serde_json::from_str(&content)
-    .map_err(|e| ParseError::InvalidJson(e.to_string()))?;
+/// Parses QCSchema basis set JSON text; see [`parse_basis`].
+pub fn parse_basis_str(json: &str) -> Result<BasisSet, ParseError> {
+  parse_single_element(json).map(|bs| bs.split())
+}
 
-  let elements_obj = json
-    .get("elements")
-    .and_then(|v| v.as_object())
-    .ok_or(ParseError::NoElements)?;
+/// [`parse_basis`] with SP-shell handling controlled by `options`. With the
+/// default options every returned shell has a single angular momentum.
+pub fn parse_basis_with(
+  path: &Path,
+  options: &ParseBasisOptions,
+) -> Result<CombinedBasisSet, ParseError> {
+  let content = std::fs::read_to_string(path)
+    .map_err(|e| ParseError::IoError(e.to_string()))?;
+  parse_basis_str_with(&content, options)
+}
 
-  let z_str: &str = match elements_obj.len() {
-    0 => return Err(ParseError::NoElements),
-    1 => elements_obj.keys().next().unwrap(),
-    n => return Err(ParseError::MultipleElements { found: n }),
-  };
+/// Parses QCSchema basis set JSON text; see [`parse_basis_with`].
+pub fn parse_basis_str_with(
+  json: &str,
+  options: &ParseBasisOptions,
+) -> Result<CombinedBasisSet, ParseError> {
+  let combined = parse_single_element(json)?;
+  if !options.split_sp {
+    return Ok(combined);
+  }
+  let split = combined.split();
+  Ok(CombinedBasisSet {
+    element: split.element,
+    atomic_number: split.atomic_number,
+    shells: split
+      .shells
+      .into_iter()
+      .map(|sh| CombinedShell {
+        angular_momenta: vec![sh.angular_momentum],
+        exponents: sh.exponents,
+        coefficients: vec![sh.coefficients],
+        general_contraction_group: sh.general_contraction_group,
+      })
+      .collect(),
+    ecp: split.ecp,
+  })
+}
 
-  let z: u32 = match z_str.parse::<u32>() {
-    Ok(n) if (1..=118).contains(&n) => n,
-    _ => return Err(ParseError::InvalidAtomicNumber(z_str.to_string())),
+/// Parses a document that must hold exactly one element, shells unsplit.
+fn parse_single_element(json: &str) -> Result<CombinedBasisSet, ParseError> {
+  let elements_obj = elements_object(json)?;
+
+  let mut entries = elements_obj.iter();
+  match (entries.next(), elements_obj.len()) {
+    (Some((z_str, element_data)), 1) => parse_element(z_str, element_data),
+    (None, _) => Err(ParseError::NoElements),
+    (_, n) => Err(ParseError::MultipleElements { found: n }),
+  }
+}
+
+/// Reads and parses a QCSchema basis set JSON file containing any number of
+/// elements, returning one `BasisSet` per element sorted by atomic number.
+pub fn parse_basis_all(path: &Path) -> Result<Vec<BasisSet>, ParseError> {
+  let content = std::fs::read_to_string(path)
+    .map_err(|e| ParseError::IoError(e.to_string()))?;
+  parse_basis_all_str(&content)
+}
+
+/// Reads a user-supplied basis file in either QCSchema JSON (detected by a
+/// leading `{`) or Gaussian94 format, returning one `BasisSet` per element
+/// sorted by atomic number.
+pub fn parse_local_basis(path: &Path) -> Result<Vec<BasisSet>, ParseError> {
+  let content = std::fs::read_to_string(path)
+    .map_err(|e| ParseError::IoError(e.to_string()))?;
+  if content.trim_start().starts_with('{') {
+    parse_basis_all_str(&content)
+  } else {
+    parse_g94_all(&content)
+  }
+}
+
+fn parse_basis_all_str(content: &str) -> Result<Vec<BasisSet>, ParseError> {
+  let elements_obj = elements_object(content)?;
+  if elements_obj.is_empty() {
+    return Err(ParseError::NoElements);
+  }
+
+  let mut sets = elements_obj
+    .iter()
+    .map(|(z_str, data)| parse_element(z_str, data).map(|bs| bs.split()))
+    .collect::<Result<Vec<_>, _>>()?;
+  sets.sort_by_key(|bs| bs.atomic_number);
+  Ok(sets)
+}
+
+/// Parses `json` and returns its top-level `elements` object.
+fn elements_object(json: &str) -> Result<serde_json::Map<String, serde_json::Value>, ParseError> {
+  let value: serde_json::Value = serde_json::from_str(json)
+    .map_err(|e| ParseError::InvalidJson(e.to_string()))?;
+
+  // Take ownership of the elements object rather than cloning it.
+  let serde_json::Value::Object(mut root) = value else {
+    return Err(ParseError::NoElements);
   };
+  match root.remove("elements") {
+    Some(serde_json::Value::Object(m)) => Ok(m),
+    _ => Err(ParseError::NoElements),
+  }
+}
 
-  let symbol = ELEMENTS[(z - 1) as usize].to_string();
-  let element_data = &elements_obj[z_str];
+/// Parses the entry for one element, keyed by its atomic number `z_str`.
+fn parse_element(
+  z_str: &str,
+  element_data: &serde_json::Value,
+) -> Result<CombinedBasisSet, ParseError> {
+  let invalid = || ParseError::InvalidAtomicNumber(z_str.to_string());
+  let z: u32 = z_str.parse().map_err(|_| invalid())?;
+  let symbol = element_symbol(z).ok_or_else(invalid)?.to_string();
 
   let shells_raw = element_data
     .get("electron_shells")
@@ -254,7 +904,7 @@ pub fn parse_basis(path: &Path) -> Result<BasisSet, ParseError> {
     return Err(ParseError::NoElectronShells);
   }
 
-  let mut shells: Vec<ElectronShell> = Vec::new();
+  let mut shells: Vec<CombinedShell> = Vec::new();
 
   for (idx, shell_val) in shells_raw.iter().enumerate() {
     let am_arr = shell_val
@@ -323,7 +973,8 @@ pub fn parse_basis(path: &Path) -> Result<BasisSet, ParseError> {
       });
     }
 
-    for (am, coeff_vec_val) in angular_momenta.iter().zip(coeff_arr.iter()) {
+    let mut coefficient_sets = Vec::with_capacity(coeff_arr.len());
+    for coeff_vec_val in coeff_arr {
       let coeff_vec =
         coeff_vec_val.as_array().ok_or_else(|| ParseError::MalformedShell {
           index: idx,
@@ -355,15 +1006,255 @@ pub fn parse_basis(path: &Path) -> Result<BasisSet, ParseError> {
         })
         .collect::<Result<_, _>>()?;
 
-      shells.push(ElectronShell {
-        angular_momentum: *am,
-        exponents: exponents.clone(),
-        coefficients,
-      });
+      coefficient_sets.push(coefficients);
     }
+    shells.push(CombinedShell {
+      angular_momenta,
+      exponents,
+      coefficients: coefficient_sets,
+      general_contraction_group: None,
+    });
   }
 
-  Ok(BasisSet { element: symbol, atomic_number: z, shells })
+  let ecp = parse_ecp(element_data)?;
+  Ok(CombinedBasisSet { element: symbol, atomic_number: z, shells, ecp })
+}
+
+/// Parses `ecp_potentials` and `ecp_electrons` from one element's entry;
+/// `None` when the entry has no `ecp_potentials`.
+fn parse_ecp(element_data: &serde_json::Value) -> Result<Option<Ecp>, ParseError> {
+  let Some(potentials) = element_data.get("ecp_potentials") else {
+    return Ok(None);
+  };
+  let malformed = |index: usize, reason: String| ParseError::MalformedEcp { index, reason };
+  let potentials = potentials
+    .as_array()
+    .ok_or_else(|| malformed(0, "ecp_potentials is not an array".to_string()))?;
+  let ecp_electrons = element_data
+    .get("ecp_electrons")
+    .and_then(|v| v.as_u64())
+    .ok_or_else(|| malformed(0, "missing or invalid ecp_electrons".to_string()))?
+    as u32;
+
+  let floats = |idx: usize, key: &str, values: &[serde_json::Value]| -> Result<Vec<f64>, ParseError> {
+    values
+      .iter()
+      .map(|v| {
+        v.as_str()
+          .and_then(|s| s.parse::<f64>().ok())
+          .ok_or_else(|| malformed(idx, format!("cannot parse {} entry {:?} as f64", key, v)))
+      })
+      .collect()
+  };
+
+  let mut shells = Vec::with_capacity(potentials.len());
+  for (idx, pot) in potentials.iter().enumerate() {
+    let field = |key: &str| {
+      pot
+        .get(key)
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| malformed(idx, format!("missing or invalid {}", key)))
+    };
+
+    let angular_momentum = match field("angular_momentum")?.as_slice() {
+      [l] => l.as_u64().map(|n| n as u32).ok_or_else(|| {
+        malformed(idx, format!("angular_momentum entry {:?} is not a non-negative integer", l))
+      })?,
+      am => {
+        return Err(malformed(
+          idx,
+          format!("expected one angular_momentum, found {}", am.len()),
+        ))
+      }
+    };
+
+    let r_exponents: Vec<i32> = field("r_exponents")?
+      .iter()
+      .map(|v| {
+        v.as_i64()
+          .map(|n| n as i32)
+          .ok_or_else(|| malformed(idx, format!("r_exponents entry {:?} is not an integer", v)))
+      })
+      .collect::<Result<_, _>>()?;
+    let gaussian_exponents = floats(idx, "gaussian_exponents", field("gaussian_exponents")?)?;
+    let coefficients = match field("coefficients")?.as_slice() {
+      [serde_json::Value::Array(c)] => floats(idx, "coefficients", c)?,
+      _ => return Err(malformed(idx, "expected one coefficient vector".to_string())),
+    };
+
+    if r_exponents.len() != gaussian_exponents.len()
+      || coefficients.len() != gaussian_exponents.len()
+    {
+      return Err(malformed(
+        idx,
+        format!(
+          "r_exponents, gaussian_exponents and coefficients have lengths {}, {} and {}",
+          r_exponents.len(),
+          gaussian_exponents.len(),
+          coefficients.len()
+        ),
+      ));
+    }
+    shells.push(EcpShell { angular_momentum, r_exponents, gaussian_exponents, coefficients });
+  }
+
+  Ok(Some(Ecp { ecp_electrons, shells }))
+}
+
+// ============================================================================
+// parse_basis_g94
+// ============================================================================
+
+/// Parses the block for `element` from Gaussian94-format basis text.
+///
+/// Each element block starts with a `Sym 0` line and ends with `****`; each
+/// shell is a `TYPE n_prim scale` header (TYPE one of S, P, D, F, G, H, I, or
+/// SP/L) followed by `n_prim` lines of exponent and coefficient(s). Exponents
+/// are multiplied by scale², Fortran `D` exponents are accepted, and `!`
+/// comments and blank lines are ignored. SP shells are split as in
+/// [`parse_basis`].
+pub fn parse_basis_g94(content: &str, element: &str) -> Result<BasisSet, ParseError> {
+  let wanted = normalize_element(element)
+    .map_err(|_| ParseError::InvalidElement(element.to_string()))?;
+  parse_g94_all(content)?
+    .into_iter()
+    .find(|bs| bs.element == wanted)
+    .ok_or(ParseError::ElementNotFound(wanted))
+}
+
+/// Parses every element block of Gaussian94 text, sorted by atomic number.
+fn parse_g94_all(content: &str) -> Result<Vec<BasisSet>, ParseError> {
+  let mut lines = content
+    .lines()
+    .map(str::trim)
+    .filter(|l| !l.is_empty() && !l.starts_with('!'));
+
+  let mut sets = Vec::new();
+  while let Some(line) = lines.next() {
+    if line == "****" {
+      continue;
+    }
+    let sym = line.split_whitespace().next().unwrap_or(line);
+    let element = normalize_element(sym)
+      .map_err(|_| ParseError::InvalidElement(sym.to_string()))?;
+    let atomic_number = atomic_number(&element).unwrap_or(0);
+
+    let mut shells = Vec::new();
+    while let Some(header) = lines.next() {
+      if header == "****" {
+        break;
+      }
+      shells.push(parse_g94_shell(header, &mut lines, shells.len())?);
+    }
+    if shells.is_empty() {
+      return Err(ParseError::NoElectronShells);
+    }
+    sets.push(CombinedBasisSet { element, atomic_number, shells, ecp: None }.split());
+  }
+
+  if sets.is_empty() {
+    return Err(ParseError::NoElements);
+  }
+  sets.sort_by_key(|bs| bs.atomic_number);
+  Ok(sets)
+}
+
+/// Parses one shell whose `TYPE n_prim scale` header is `header`, consuming
+/// its primitive lines from `lines`.
+fn parse_g94_shell<'a>(
+  header: &str,
+  lines: &mut impl Iterator<Item = &'a str>,
+  index: usize,
+) -> Result<CombinedShell, ParseError> {
+  let malformed = |reason: String| ParseError::MalformedShell { index, reason };
+
+  let fields: Vec<&str> = header.split_whitespace().collect();
+  let angular_momenta = match fields[0].to_ascii_uppercase().as_str() {
+    "SP" | "L" => vec![0, 1],
+    t => match "SPDFGHI".find(t).filter(|_| t.len() == 1) {
+      Some(l) => vec![l as u32],
+      None => return Err(malformed(format!("unknown shell type {:?}", fields[0]))),
+    },
+  };
+  let n_prim: usize = fields
+    .get(1)
+    .and_then(|n| n.parse().ok())
+    .filter(|&n| n > 0)
+    .ok_or_else(|| malformed(format!("bad shell header {:?}", header)))?;
+  let scale = match fields.get(2) {
+    Some(f) => g94_number(f).ok_or_else(|| malformed(format!("bad scale factor {:?}", f)))?,
+    None => 1.0,
+  };
+
+  let mut exponents = Vec::with_capacity(n_prim);
+  let mut coefficients = vec![Vec::with_capacity(n_prim); angular_momenta.len()];
+  for _ in 0..n_prim {
+    let row = lines
+      .next()
+      .filter(|l| *l != "****")
+      .ok_or_else(|| malformed(format!("expected {} primitives", n_prim)))?;
+    let values = row
+      .split_whitespace()
+      .map(|v| g94_number(v).ok_or_else(|| malformed(format!("cannot parse {:?} as f64", v))))
+      .collect::<Result<Vec<f64>, _>>()?;
+    if values.len() != 1 + angular_momenta.len() {
+      return Err(malformed(format!(
+        "expected an exponent and {} coefficient(s), found {:?}",
+        angular_momenta.len(),
+        row
+      )));
+    }
+    exponents.push(values[0] * scale * scale);
+    for (set, &c) in coefficients.iter_mut().zip(&values[1..]) {
+      set.push(c);
+    }
+  }
+  Ok(CombinedShell { angular_momenta, exponents, coefficients, general_contraction_group: None })
+}
+
+/// Parses a Gaussian94 number, accepting Fortran `D` exponents.
+fn g94_number(s: &str) -> Option<f64> {
+  s.replace(['D', 'd'], "E").parse().ok()
+}
+
+// ============================================================================
+// Normalization
+// ============================================================================
+
+/// Normalization constant of a Cartesian Gaussian x^l e^{-αr²} with exponent
+/// `alpha` and total angular momentum `l` (all angular momentum along one axis):
+///
+///   N = (2α/π)^{3/4} (4α)^{l/2} / √((2l−1)!!)
+pub fn primitive_norm(alpha: f64, l: u32) -> f64 {
+  (2.0 * alpha / std::f64::consts::PI).powf(0.75)
+    * (4.0 * alpha).powf(l as f64 / 2.0)
+    / odd_double_factorial(l).sqrt()
+}
+
+/// Self-overlap ⟨φ|φ⟩ of the contracted function described by `shell`, where
+/// the coefficients multiply normalized primitives.
+pub fn shell_self_overlap(shell: &ElectronShell) -> f64 {
+  let l = shell.angular_momentum;
+  let double_factorial = odd_double_factorial(l);
+  let exps = &shell.exponents;
+  let coefs = &shell.coefficients;
+  let mut sum = 0.0;
+  for i in 0..exps.len() {
+    for j in 0..exps.len() {
+      let p = exps[i] + exps[j];
+      // Overlap of the unnormalized primitives x^l e^{-α_i r²} and x^l e^{-α_j r²}.
+      let raw = (std::f64::consts::PI / p).powf(1.5)
+        * double_factorial / (2.0 * p).powi(l as i32);
+      sum += coefs[i] * coefs[j]
+        * primitive_norm(exps[i], l) * primitive_norm(exps[j], l) * raw;
+    }
+  }
+  sum
+}
+
+/// (2l − 1)!!, with (−1)!! = 1.
+pub fn odd_double_factorial(l: u32) -> f64 {
+  (1..2 * l).step_by(2).map(|k| k as f64).product()
 }
 
 // ============================================================================
@@ -371,9 +1262,37 @@ pub fn parse_basis(path: &Path) -> Result<BasisSet, ParseError> {
 // ============================================================================
 
 /// Fetches (if needed) and parses the QCSchema basis set for `element` in
-/// `basis_name`, using the live BSE API and `data/basis` as the cache root.
+/// `basis_name`, using the live BSE API and [`default_cache_root`].
 pub fn load_basis(element: &str, basis_name: &str) -> Result<BasisSet, LoadError> {
-  load_basis_impl(element, basis_name, BSE_BASE_URL, Path::new(DEFAULT_CACHE_ROOT))
+  load_basis_in(element, basis_name, &default_cache_root())
+}
+
+/// [`load_basis`] with the cache rooted at `cache_root` instead of the default;
+/// files are read from and written to `{cache_root}/{basis_name}/{element}.json`.
+pub fn load_basis_in(
+  element: &str,
+  basis_name: &str,
+  cache_root: &Path,
+) -> Result<BasisSet, LoadError> {
+  load_basis_impl(element, basis_name, BSE_BASE_URL, cache_root)
+}
+
+/// [`load_basis_in`] against the BSE at `base_url` instead of
+/// [`BSE_BASE_URL`].
+pub fn load_basis_from(
+  element: &str,
+  basis_name: &str,
+  base_url: &str,
+  cache_root: &Path,
+) -> Result<BasisSet, LoadError> {
+  load_basis_impl(element, basis_name, base_url, cache_root)
+}
+
+/// Parses a user-supplied single-element QCSchema basis file, with no BSE
+/// request and no cache involved. For a file holding several elements use
+/// [`parse_basis_all`].
+pub fn load_basis_from_file(path: &Path) -> Result<BasisSet, ParseError> {
+  parse_basis(path)
 }
 
 fn load_basis_impl(
@@ -384,7 +1303,12 @@ fn load_basis_impl(
 ) -> Result<BasisSet, LoadError> {
   let path = fetch_basis_impl(element, basis_name, base_url, cache_root)
     .map_err(LoadError::Fetch)?;
-  parse_basis(&path).map_err(LoadError::Parse)
+  let basis = parse_basis(&path).map_err(LoadError::Parse)?;
+  let requested = normalize_element(element).map_err(LoadError::Fetch)?;
+  if basis.element != requested {
+    return Err(LoadError::ElementMismatch { requested, found: basis.element });
+  }
+  Ok(basis)
 }
 
 #[cfg(test)]
@@ -421,6 +1345,29 @@ mod tests {
     assert_eq!(content, VALID_RESPONSE);
   }
 
+  // Scenario: A gzip-encoded response is decoded before it is cached
+  #[test]
+  fn download_gzip_encoded_basis() {
+    use std::io::Write;
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(VALID_RESPONSE.as_bytes()).unwrap();
+    let gzipped = encoder.finish().unwrap();
+
+    let mut server = mockito::Server::new();
+    let mock = server
+      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=H")
+      .match_header("accept-encoding", mockito::Matcher::Regex("gzip".to_string()))
+      .with_status(200)
+      .with_header("content-encoding", "gzip")
+      .with_body(gzipped)
+      .create();
+
+    let dir = temp_dir();
+    let path = fetch_basis_impl("H", "sto-3g", &server.url(), dir.path()).expect("should succeed");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), VALID_RESPONSE);
+    mock.assert();
+  }
+
   // Scenario: Return cached file when a valid cache exists
   // (also verifies no HTTP request is made)
   #[test]
@@ -435,6 +1382,45 @@ mod tests {
     assert_eq!(result.expect("should succeed"), cache_path);
   }
 
+  // Scenario: fetch_basis_in and load_basis_in read from an explicit root
+  #[test]
+  fn explicit_cache_root_is_used() {
+    let dir = temp_dir();
+    let cache_path = dir.path().join("sto-3g").join("H.json");
+    std::fs::create_dir_all(cache_path.parent().unwrap()).unwrap();
+    std::fs::write(&cache_path, H_1S).unwrap();
+
+    assert_eq!(fetch_basis_in("h", "STO-3G", dir.path()), Ok(cache_path));
+    assert_eq!(load_basis_in("H", "sto-3g", dir.path()).unwrap().element, "H");
+  }
+
+  // Scenario: QCHEM_BASIS_CACHE overrides the built-in cache root
+  #[test]
+  fn cache_root_env_override() {
+    assert_eq!(cache_root_from(Some("/srv/basis".to_string())), PathBuf::from("/srv/basis"));
+    assert_eq!(cache_root_from(None), PathBuf::from(DEFAULT_CACHE_ROOT));
+    assert_eq!(cache_root_from(Some(String::new())), PathBuf::from(DEFAULT_CACHE_ROOT));
+  }
+
+  // Scenario: Cache-only coverage reports elements missing from the cache
+  #[test]
+  fn cached_coverage_reports_missing() {
+    let dir = temp_dir();
+    let cache_path = dir.path().join("sto-3g").join("H.json");
+    std::fs::create_dir_all(cache_path.parent().unwrap()).unwrap();
+    std::fs::write(&cache_path, VALID_RESPONSE).unwrap();
+
+    let missing = cached_coverage(&["h", "O", "H"], "STO-3G", dir.path());
+    assert_eq!(missing, vec!["O".to_string()]);
+  }
+
+  // Scenario: Cache-only coverage treats unknown symbols as missing
+  #[test]
+  fn cached_coverage_unknown_element() {
+    let dir = temp_dir();
+    assert_eq!(cached_coverage(&["Xx"], "sto-3g", dir.path()), vec!["Xx".to_string()]);
+  }
+
   // Scenario: Create data/basis directory if it does not exist
   #[test]
   fn creates_missing_data_basis_directory() {
@@ -534,83 +1520,395 @@ mod tests {
   }
 
   // -------------------------------------------------------------------------
-  // BSE API error responses
+  // BSE API error responses
+  // -------------------------------------------------------------------------
+
+  // Scenario: Basis set name is not known to the BSE (HTTP 404)
+  #[test]
+  fn unknown_basis_set_on_404() {
+    let mut server = mockito::Server::new();
+    let _mock = server
+      .mock("GET", "/api/basis/unknown-basis/format/qcschema?elements=H")
+      .with_status(404)
+      .create();
+
+    let dir = temp_dir();
+    let result = fetch_basis_impl("H", "unknown-basis", &server.url(), dir.path());
+    assert!(matches!(
+      result,
+      Err(BseError::UnknownBasisSet { ref basis_name, .. }) if basis_name == "unknown-basis"
+    ));
+    assert!(!dir.path().join("unknown-basis").join("H.json").exists());
+  }
+
+  // Scenario: Element is not included in the requested basis set
+  #[test]
+  fn element_not_in_basis_set_on_empty_elements() {
+    let mut server = mockito::Server::new();
+    let _mock = server
+      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=Au")
+      .with_status(200)
+      .with_body(r#"{"elements":{}}"#)
+      .create();
+
+    let dir = temp_dir();
+    let result = fetch_basis_impl("Au", "sto-3g", &server.url(), dir.path());
+    assert!(
+      matches!(result, Err(BseError::ElementNotInBasisSet { ref element, ref basis_name })
+        if element == "Au" && basis_name == "sto-3g"),
+      "expected ElementNotInBasisSet, got {:?}",
+      result
+    );
+    assert!(!dir.path().join("sto-3g").join("Au.json").exists());
+  }
+
+  // Scenario: BSE API returns an unexpected HTTP status code
+  #[test]
+  fn network_error_on_unexpected_status() {
+    let mut server = mockito::Server::new();
+    let _mock = server
+      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=H")
+      .with_status(500)
+      .create();
+
+    let dir = temp_dir();
+    let result = fetch_basis_impl("H", "sto-3g", &server.url(), dir.path());
+    assert!(matches!(result, Err(BseError::NetworkError(_))));
+    assert!(!dir.path().join("sto-3g").join("H.json").exists());
+  }
+
+  // Scenario: A server error names the basis and element it was fetching
+  #[test]
+  fn server_error_message_names_request() {
+    let mut server = mockito::Server::new();
+    let _mock = server
+      .mock("GET", "/api/basis/6-31g/format/qcschema?elements=Li")
+      .with_status(500)
+      .create();
+
+    let dir = temp_dir();
+    let policy = RequestPolicy { retries: 0, ..RequestPolicy::default() };
+    let err = fetch_basis_timed("li", "6-31G", &server.url(), dir.path(), policy).unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("500"), "{}", message);
+    assert!(message.contains("6-31g"), "{}", message);
+    assert!(message.contains("elements=Li"), "{}", message);
+    assert!(message.contains(&server.url()), "{}", message);
+  }
+
+  // Scenario: BSE API is unreachable
+  #[test]
+  fn network_error_when_unreachable() {
+    let dir = temp_dir();
+    // Port 59999 is highly unlikely to be bound
+    let result = fetch_basis_impl("H", "sto-3g", "http://127.0.0.1:59999", dir.path());
+    assert!(matches!(result, Err(BseError::NetworkError(_))));
+  }
+
+  // Scenario: BSE API returns a response that is not valid JSON
+  #[test]
+  fn invalid_response_on_non_json_body() {
+    let mut server = mockito::Server::new();
+    let _mock = server
+      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=H")
+      .with_status(200)
+      .with_body("this is not json")
+      .create();
+
+    let dir = temp_dir();
+    let result = fetch_basis_impl("H", "sto-3g", &server.url(), dir.path());
+    assert!(matches!(result, Err(BseError::InvalidResponse(_))));
+    assert!(!dir.path().join("sto-3g").join("H.json").exists());
+  }
+
+  // -------------------------------------------------------------------------
+  // Timeouts
+  // -------------------------------------------------------------------------
+
+  // Scenario: A BSE response slower than the timeout is a Timeout error
+  #[test]
+  fn slow_response_times_out() {
+    let mut server = mockito::Server::new();
+    let _mock = server
+      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=H")
+      .with_status(200)
+      .with_chunked_body(|w| {
+        std::thread::sleep(Duration::from_millis(1000));
+        w.write_all(VALID_RESPONSE.as_bytes())
+      })
+      .create();
+
+    let dir = temp_dir();
+    let timeout = Duration::from_millis(100);
+    let policy = RequestPolicy { timeout, ..RequestPolicy::default() };
+    let result = fetch_basis_timed("H", "sto-3g", &server.url(), dir.path(), policy);
+    assert_eq!(result, Err(BseError::Timeout(timeout)));
+    assert!(!dir.path().join("sto-3g").join("H.json").exists());
+  }
+
+  // Scenario: A response within the timeout succeeds
+  #[test]
+  fn fast_response_within_timeout() {
+    let mut server = mockito::Server::new();
+    let _mock = server
+      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=H")
+      .with_status(200)
+      .with_body(VALID_RESPONSE)
+      .create();
+
+    let dir = temp_dir();
+    let policy = RequestPolicy { timeout: Duration::from_secs(5), ..RequestPolicy::default() };
+    let result = fetch_basis_timed("H", "sto-3g", &server.url(), dir.path(), policy);
+    assert!(result.is_ok());
+  }
+
+  // -------------------------------------------------------------------------
+  // Retries
+  // -------------------------------------------------------------------------
+
+  fn fast_retry_policy() -> RequestPolicy {
+    RequestPolicy { backoff: Duration::from_millis(1), ..RequestPolicy::default() }
+  }
+
+  // Scenario: Two 503s followed by a 200 succeed after exactly three requests
+  #[test]
+  fn retries_after_server_errors() {
+    let mut server = mockito::Server::new();
+    let unavailable = server
+      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=H")
+      .with_status(503)
+      .expect(2)
+      .create();
+    let ok = server
+      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=H")
+      .with_status(200)
+      .with_body(VALID_RESPONSE)
+      .expect(1)
+      .create();
+
+    let dir = temp_dir();
+    let result = fetch_basis_timed("H", "sto-3g", &server.url(), dir.path(), fast_retry_policy());
+    assert_eq!(result, Ok(dir.path().join("sto-3g").join("H.json")));
+    unavailable.assert();
+    ok.assert();
+  }
+
+  // Scenario: A persistent 5xx gives up after the configured number of retries
+  #[test]
+  fn retries_exhausted_on_server_errors() {
+    let mut server = mockito::Server::new();
+    let unavailable = server
+      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=H")
+      .with_status(502)
+      .expect(3)
+      .create();
+
+    let dir = temp_dir();
+    let policy = RequestPolicy { retries: 2, ..fast_retry_policy() };
+    let result = fetch_basis_timed("H", "sto-3g", &server.url(), dir.path(), policy);
+    assert_eq!(
+      result,
+      Err(BseError::NetworkError(format!(
+        "unexpected HTTP status 502 for {}/api/basis/sto-3g/format/qcschema?elements=H",
+        server.url()
+      )))
+    );
+    unavailable.assert();
+  }
+
+  // Scenario: A 404 or other 4xx is not retried
+  #[test]
+  fn client_errors_are_not_retried() {
+    let mut server = mockito::Server::new();
+    let not_found = server
+      .mock("GET", "/api/basis/nope/format/qcschema?elements=H")
+      .with_status(404)
+      .expect(1)
+      .create();
+    let bad_request = server
+      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=H")
+      .with_status(400)
+      .expect(1)
+      .create();
+
+    let dir = temp_dir();
+    let result = fetch_basis_timed("H", "nope", &server.url(), dir.path(), fast_retry_policy());
+    assert_eq!(
+      result,
+      Err(BseError::UnknownBasisSet {
+        basis_name: "nope".to_string(),
+        url: format!("{}/api/basis/nope/format/qcschema?elements=H", server.url()),
+      })
+    );
+    let result = fetch_basis_timed("H", "sto-3g", &server.url(), dir.path(), fast_retry_policy());
+    assert!(matches!(result, Err(BseError::NetworkError(_))));
+    not_found.assert();
+    bad_request.assert();
+  }
+
+  // -------------------------------------------------------------------------
+  // check_basis_covers
+  // -------------------------------------------------------------------------
+
+  const METADATA_RESPONSE: &str = r#"{
+    "sto-3g":{"display_name":"STO-3G","latest_version":"1",
+      "versions":{"0":{"elements":["1"]},"1":{"elements":["1","2","6","8"]}}},
+    "def2-svp":{"display_name":"def2-SVP","latest_version":"1",
+      "versions":{"1":{"elements":["1","79"]}}}}"#;
+
+  fn metadata_server() -> (mockito::ServerGuard, mockito::Mock) {
+    let mut server = mockito::Server::new();
+    let mock = server
+      .mock("GET", "/api/metadata")
+      .with_status(200)
+      .with_body(METADATA_RESPONSE)
+      .expect(1)
+      .create();
+    (server, mock)
+  }
+
+  // Scenario: Metadata covering H but not Au reports only Au as missing
+  #[test]
+  fn check_basis_covers_reports_missing_elements() {
+    let (server, mock) = metadata_server();
+    let missing = check_basis_covers_impl(&["H", "au", "H"], "STO-3G", &server.url());
+    assert_eq!(missing, Ok(vec!["Au".to_string()]));
+    mock.assert();
+  }
+
+  // Scenario: Every missing element is listed in request order, from one request
+  #[test]
+  fn check_basis_covers_lists_all_missing() {
+    let (server, mock) = metadata_server();
+    let missing = check_basis_covers_impl(&["Au", "O", "Li", "H"], "sto-3g", &server.url());
+    assert_eq!(missing, Ok(vec!["Au".to_string(), "Li".to_string()]));
+    mock.assert();
+  }
+
+  // Scenario: Full coverage yields an empty list
+  #[test]
+  fn check_basis_covers_full_coverage() {
+    let (server, _mock) = metadata_server();
+    let missing = check_basis_covers_impl(&["H", "Au"], "def2-svp", &server.url());
+    assert_eq!(missing, Ok(vec![]));
+  }
+
+  // Scenario: A basis absent from the metadata is UnknownBasisSet
+  #[test]
+  fn check_basis_covers_unknown_basis() {
+    let (server, _mock) = metadata_server();
+    let result = check_basis_covers_impl(&["H"], "no-such-basis", &server.url());
+    assert_eq!(
+      result,
+      Err(BseError::UnknownBasisSet {
+        basis_name: "no-such-basis".to_string(),
+        url: format!("{}/api/metadata", server.url()),
+      })
+    );
+  }
+
+  // Scenario: An invalid symbol is rejected before any request is made
+  #[test]
+  fn check_basis_covers_invalid_element() {
+    let (server, mock) = metadata_server();
+    let result = check_basis_covers_impl(&["H", "Xx"], "sto-3g", &server.url());
+    assert_eq!(result, Err(BseError::InvalidElement("Xx".to_string())));
+    mock.expect(0).assert();
+  }
+
+  // -------------------------------------------------------------------------
+  // Batch fetch
   // -------------------------------------------------------------------------
 
-  // Scenario: Basis set name is not known to the BSE (HTTP 404)
+  const HO_RESPONSE: &str = r#"{"name":"STO-3G","elements":{
+    "1":{"electron_shells":[{"function_type":"gto","angular_momentum":[0],
+      "exponents":["3.42525091"],"coefficients":[["1.0"]]}]},
+    "8":{"electron_shells":[{"function_type":"gto","angular_momentum":[0],
+      "exponents":["130.70932"],"coefficients":[["1.0"]]}]}}}"#;
+
+  // Scenario: A two-element molecule is fetched with a single GET and split
+  // into per-element cache files
   #[test]
-  fn unknown_basis_set_on_404() {
+  fn fetch_multi_single_request() {
     let mut server = mockito::Server::new();
-    let _mock = server
-      .mock("GET", "/api/basis/unknown-basis/format/qcschema?elements=H")
-      .with_status(404)
+    let mock = server
+      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=H,O")
+      .with_status(200)
+      .with_body(HO_RESPONSE)
+      .expect(1)
       .create();
 
     let dir = temp_dir();
-    let result = fetch_basis_impl("H", "unknown-basis", &server.url(), dir.path());
-    assert!(matches!(result, Err(BseError::UnknownBasisSet(s)) if s == "unknown-basis"));
-    assert!(!dir.path().join("unknown-basis").join("H.json").exists());
+    let paths = fetch_basis_multi_impl(&["o", "H", "H"], "STO-3G", &server.url(), dir.path())
+      .expect("should succeed");
+    let basis_dir = dir.path().join("sto-3g");
+    assert_eq!(paths, vec![basis_dir.join("O.json"), basis_dir.join("H.json"), basis_dir.join("H.json")]);
+    assert!(basis_dir.join("_multi_H_O.json").exists());
+    assert_eq!(parse_basis(&paths[0]).unwrap().element, "O");
+    assert_eq!(parse_basis(&paths[1]).unwrap().element, "H");
+    let h: serde_json::Value =
+      serde_json::from_str(&std::fs::read_to_string(&paths[1]).unwrap()).unwrap();
+    assert_eq!(h["name"], "STO-3G");
+
+    // Both elements now load from the cache without another request.
+    for el in ["H", "O"] {
+      load_basis_impl(el, "sto-3g", &server.url(), dir.path()).expect("cache hit");
+    }
+    mock.assert();
   }
 
-  // Scenario: Element is not included in the requested basis set
+  // Scenario: Only uncached elements are requested; none at all when every
+  // element is cached
   #[test]
-  fn element_not_in_basis_set_on_empty_elements() {
+  fn fetch_multi_skips_cached_elements() {
     let mut server = mockito::Server::new();
-    let _mock = server
-      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=Au")
+    let mock = server
+      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=O")
       .with_status(200)
-      .with_body(r#"{"elements":{}}"#)
+      .with_body(HO_RESPONSE)
+      .expect(1)
       .create();
 
     let dir = temp_dir();
-    let result = fetch_basis_impl("Au", "sto-3g", &server.url(), dir.path());
-    assert!(
-      matches!(result, Err(BseError::ElementNotInBasisSet { ref element, ref basis_name })
-        if element == "Au" && basis_name == "sto-3g"),
-      "expected ElementNotInBasisSet, got {:?}",
-      result
-    );
-    assert!(!dir.path().join("sto-3g").join("Au.json").exists());
+    let h_path = dir.path().join("sto-3g").join("H.json");
+    std::fs::create_dir_all(h_path.parent().unwrap()).unwrap();
+    std::fs::write(&h_path, VALID_RESPONSE).unwrap();
+
+    fetch_basis_multi_impl(&["H", "O"], "sto-3g", &server.url(), dir.path())
+      .expect("should succeed");
+    assert_eq!(std::fs::read_to_string(&h_path).unwrap(), VALID_RESPONSE);
+    fetch_basis_multi_impl(&["H", "O"], "sto-3g", &server.url(), dir.path())
+      .expect("should succeed");
+    mock.assert();
   }
 
-  // Scenario: BSE API returns an unexpected HTTP status code
+  // Scenario: An element absent from the combined response is reported
   #[test]
-  fn network_error_on_unexpected_status() {
+  fn fetch_multi_element_missing_from_response() {
     let mut server = mockito::Server::new();
     let _mock = server
-      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=H")
-      .with_status(500)
+      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=H,C")
+      .with_status(200)
+      .with_body(HO_RESPONSE)
       .create();
 
     let dir = temp_dir();
-    let result = fetch_basis_impl("H", "sto-3g", &server.url(), dir.path());
-    assert!(matches!(result, Err(BseError::NetworkError(_))));
+    let result = fetch_basis_multi_impl(&["C", "H"], "sto-3g", &server.url(), dir.path());
+    assert_eq!(
+      result,
+      Err(BseError::ElementNotInBasisSet { element: "C".to_string(), basis_name: "sto-3g".to_string() })
+    );
+    // H was in the response, but a partial response caches nothing.
     assert!(!dir.path().join("sto-3g").join("H.json").exists());
+    assert!(!dir.path().join("sto-3g").join("_multi_H_C.json").exists());
   }
 
-  // Scenario: BSE API is unreachable
-  #[test]
-  fn network_error_when_unreachable() {
-    let dir = temp_dir();
-    // Port 59999 is highly unlikely to be bound
-    let result = fetch_basis_impl("H", "sto-3g", "http://127.0.0.1:59999", dir.path());
-    assert!(matches!(result, Err(BseError::NetworkError(_))));
-  }
-
-  // Scenario: BSE API returns a response that is not valid JSON
+  // Scenario: Batch fetch validates symbols before any request
   #[test]
-  fn invalid_response_on_non_json_body() {
-    let mut server = mockito::Server::new();
-    let _mock = server
-      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=H")
-      .with_status(200)
-      .with_body("this is not json")
-      .create();
-
+  fn fetch_multi_invalid_element() {
     let dir = temp_dir();
-    let result = fetch_basis_impl("H", "sto-3g", &server.url(), dir.path());
-    assert!(matches!(result, Err(BseError::InvalidResponse(_))));
-    assert!(!dir.path().join("sto-3g").join("H.json").exists());
+    let result = fetch_basis_multi_impl(&["H", "Xx"], "sto-3g", "http://localhost:1", dir.path());
+    assert_eq!(result, Err(BseError::InvalidElement("Xx".to_string())));
   }
 
   // -------------------------------------------------------------------------
@@ -737,12 +2035,22 @@ mod tests {
      ]}
   ]}}}"#;
 
-  // Copper with one s-shell and a spurious ecp_potentials key.
+  // Copper with one s-shell and a two-channel scalar-relativistic ECP
+  // replacing the 10 [Ne] core electrons.
   const CU_WITH_ECP: &str = r#"{"elements":{"29":{"electron_shells":[
     {"function_type":"gto","angular_momentum":[0],
      "exponents":["1.2","0.5"],
      "coefficients":[["0.8","0.4"]]}
-  ],"ecp_potentials":[{"ecp_type":"scalar"}]}}}"#;
+  ],"ecp_electrons":10,"ecp_potentials":[
+    {"ecp_type":"scalar_ecp","angular_momentum":[0],
+     "r_exponents":[2,2],
+     "gaussian_exponents":["30.22","13.19"],
+     "coefficients":[["355.77","70.92"]]},
+    {"ecp_type":"scalar_ecp","angular_momentum":[1],
+     "r_exponents":[2],
+     "gaussian_exponents":["33.28"],
+     "coefficients":[["-12.1"]]}
+  ]}}}"#;
 
   fn write_json(dir: &TempDir, content: &str) -> std::path::PathBuf {
     let path = dir.path().join("test.json");
@@ -764,6 +2072,16 @@ mod tests {
     assert_eq!(bs.shells[0].coefficients.len(), 3);
   }
 
+  // Scenario: Parsing JSON text in memory matches parsing the same file
+  #[test]
+  fn parse_str_matches_file() {
+    let from_str = parse_basis_str(H_1S).expect("should succeed");
+    assert_eq!(from_str.element, "H");
+    assert_eq!(from_str.shells[0].exponents.len(), 3);
+    let dir = temp_dir();
+    assert_eq!(parse_basis(&write_json(&dir, H_1S)).unwrap(), from_str);
+  }
+
   // Scenario: Parse a file with multiple shells
   #[test]
   fn parse_multiple_shells() {
@@ -798,14 +2116,231 @@ mod tests {
     assert!((bs.shells[1].coefficients[0] - 0.2494820).abs() < 1e-6);
   }
 
-  // Scenario: ECP data is ignored when electron shells are present
+  // Scenario: With split_sp false the Li SP shell stays one combined shell
   #[test]
-  fn ecp_data_is_ignored() {
+  fn parse_sp_shell_kept_combined() {
     let dir = temp_dir();
-    let path = write_json(&dir, CU_WITH_ECP);
-    let bs = parse_basis(&path).expect("should succeed");
+    let path = write_json(&dir, LI_SP);
+    let bs = parse_basis_with(&path, &ParseBasisOptions { split_sp: false })
+      .expect("should succeed");
+    assert_eq!(bs.element, "Li");
+    assert_eq!(bs.shells.len(), 1);
+    let sp = &bs.shells[0];
+    assert_eq!(sp.angular_momenta, vec![0, 1]);
+    assert_eq!(sp.exponents.len(), 3);
+    assert_eq!(sp.coefficients.len(), 2);
+    assert!((sp.coefficients[1][0] - 0.2494820).abs() < 1e-6);
+  }
+
+  // Scenario: With split_sp true (the default) the Li SP shell gives S and P
+  #[test]
+  fn parse_sp_shell_split_by_default_options() {
+    let dir = temp_dir();
+    let path = write_json(&dir, LI_SP);
+    assert_eq!(ParseBasisOptions::default(), ParseBasisOptions { split_sp: true });
+    let bs = parse_basis_with(&path, &ParseBasisOptions::default()).expect("should succeed");
+    let ams: Vec<&[u32]> = bs.shells.iter().map(|sh| sh.angular_momenta.as_slice()).collect();
+    assert_eq!(ams, vec![&[0][..], &[1][..]]);
+    assert_eq!(bs.split(), parse_basis(&path).unwrap());
+  }
+
+  // Scenario: Splitting a combined basis matches parse_basis
+  #[test]
+  fn combined_split_matches_parse_basis() {
+    let combined = parse_basis_str_with(LI_SP, &ParseBasisOptions { split_sp: false }).unwrap();
+    assert_eq!(combined.split(), parse_basis_str(LI_SP).unwrap());
+  }
+
+  // -------------------------------------------------------------------------
+  // parse_basis_g94
+  // -------------------------------------------------------------------------
+
+  // H_1S and LI_SP in Gaussian94 form, with a comment and a D exponent.
+  const H_LI_G94: &str = "\
+! STO-3G
+****
+H     0
+S   3   1.00
+      3.4252509              0.1543290
+      0.6239137              0.5353281
+      0.1688554              0.4446345
+****
+Li     0
+SP   3   1.00
+     16.1195750              0.1543290              0.2494820
+      0.29362007D+01         0.5353281              0.8657560
+      0.7946505              0.4446345              0.0000000
+****
+";
+
+  // Scenario: A G94 hydrogen block matches the QCSchema equivalent
+  #[test]
+  fn g94_hydrogen_matches_qcschema() {
+    let g94 = parse_basis_g94(H_LI_G94, "h").expect("should succeed");
+    assert_eq!(g94, parse_basis_str(H_1S).unwrap());
+  }
+
+  // Scenario: A G94 SP shell is split like a QCSchema one
+  #[test]
+  fn g94_sp_shell_split() {
+    let g94 = parse_basis_g94(H_LI_G94, "Li").expect("should succeed");
+    assert!(g94.approx_eq(&parse_basis_str(LI_SP).unwrap(), 1e-12));
+  }
+
+  // Scenario: The G94 scale factor multiplies exponents by its square
+  #[test]
+  fn g94_scale_factor() {
+    let bs = parse_basis_g94("H 0\nS 1 1.24\n 1.0 1.0\n****\n", "H").unwrap();
+    assert!((bs.shells[0].exponents[0] - 1.24 * 1.24).abs() < 1e-12);
+  }
+
+  // Scenario: Requesting an element the G94 text lacks
+  #[test]
+  fn g94_element_not_found() {
+    assert_eq!(
+      parse_basis_g94(H_LI_G94, "C"),
+      Err(ParseError::ElementNotFound("C".to_string()))
+    );
+    assert_eq!(
+      parse_basis_g94(H_LI_G94, "Xx"),
+      Err(ParseError::InvalidElement("Xx".to_string()))
+    );
+  }
+
+  // Scenario: Malformed G94 shells are reported with their index
+  #[test]
+  fn g94_malformed_shells() {
+    let cases = [
+      "H 0\nS 1 1.0\n 1.0 1.0\nQ 1 1.0\n 1.0 1.0\n****\n",
+      "H 0\nS 1 1.0\n 1.0 1.0\nS 2 1.0\n 1.0 1.0\n****\n",
+      "H 0\nS 1 1.0\n 1.0 1.0\nSP 1 1.0\n 1.0 1.0\n****\n",
+      "H 0\nS 1 1.0\n 1.0 1.0\nS 1 1.0\n 1.0 abc\n****\n",
+    ];
+    for g94 in cases {
+      assert!(
+        matches!(parse_basis_g94(g94, "H"), Err(ParseError::MalformedShell { index: 1, .. })),
+        "{:?}",
+        g94
+      );
+    }
+  }
+
+  // Scenario: parse_local_basis reads both formats
+  #[test]
+  fn local_basis_detects_format() {
+    let dir = temp_dir();
+    let json = write_json(&dir, HCO_MULTI);
+    let elements: Vec<String> =
+      parse_local_basis(&json).unwrap().into_iter().map(|bs| bs.element).collect();
+    assert_eq!(elements, vec!["H", "C", "O"]);
+
+    let g94 = dir.path().join("basis.gbs");
+    std::fs::write(&g94, H_LI_G94).unwrap();
+    let elements: Vec<String> =
+      parse_local_basis(&g94).unwrap().into_iter().map(|bs| bs.element).collect();
+    assert_eq!(elements, vec!["H", "Li"]);
+  }
+
+  // Scenario: ECP data does not change the parsed electron shells
+  #[test]
+  fn ecp_leaves_electron_shells_unchanged() {
+    let bs = parse_basis_str(CU_WITH_ECP).expect("should succeed");
     assert_eq!(bs.element, "Cu");
     assert_eq!(bs.shells.len(), 1);
+    assert_eq!(bs.shells[0].exponents, vec![1.2, 0.5]);
+  }
+
+  // Scenario: A scalar-relativistic ECP is parsed with its core electron count
+  #[test]
+  fn ecp_potentials_are_parsed() {
+    let bs = parse_basis_str(CU_WITH_ECP).unwrap();
+    let ecp = bs.ecp.expect("ECP present");
+    assert_eq!(ecp.ecp_electrons, 10);
+    assert_eq!(ecp.shells.len(), 2);
+    assert_eq!(ecp.shells[0].angular_momentum, 0);
+    assert_eq!(ecp.shells[0].r_exponents, vec![2, 2]);
+    assert_eq!(ecp.shells[0].gaussian_exponents, vec![30.22, 13.19]);
+    assert_eq!(ecp.shells[0].coefficients[1], 70.92);
+    assert_eq!(ecp.shells[1].angular_momentum, 1);
+    assert_eq!(ecp.shells[1].coefficients, vec![-12.1]);
+  }
+
+  // Scenario: A basis without ecp_potentials has no ECP
+  #[test]
+  fn no_ecp_when_absent() {
+    let dir = temp_dir();
+    let path = write_json(&dir, C_1S_1P);
+    assert_eq!(parse_basis(&path).unwrap().ecp, None);
+  }
+
+  // Scenario: ECP channels whose arrays disagree in length are rejected
+  #[test]
+  fn ecp_length_mismatch_is_rejected() {
+    let json = CU_WITH_ECP.replace(r#""r_exponents":[2],"#, r#""r_exponents":[1,2],"#);
+    let result = parse_basis_str(&json);
+    assert!(
+      matches!(result, Err(ParseError::MalformedEcp { index: 1, .. })),
+      "got {:?}",
+      result
+    );
+  }
+
+  // Scenario: ecp_potentials without ecp_electrons is rejected
+  #[test]
+  fn ecp_without_electron_count_is_rejected() {
+    let json = CU_WITH_ECP.replace(r#""ecp_electrons":10,"#, "");
+    assert!(matches!(parse_basis_str(&json), Err(ParseError::MalformedEcp { .. })));
+  }
+
+  // Scenario: An ECP survives the to_qcschema_json round trip
+  #[test]
+  fn ecp_round_trips_through_qcschema_json() {
+    let bs = parse_basis_str(CU_WITH_ECP).unwrap();
+    let back = parse_basis_str(&bs.to_qcschema_json()).unwrap();
+    assert_eq!(back, bs);
+  }
+
+  // Scenario: Shell and primitive counts summarise a parsed basis
+  #[test]
+  fn shell_and_primitive_counts() {
+    let dir = temp_dir();
+    let path = write_json(&dir, C_1S_1P);
+    let bs = parse_basis(&path).expect("should succeed");
+    assert_eq!(bs.n_shells(), 2);
+    assert_eq!(bs.n_primitives(), 6);
+  }
+
+  // Scenario: Carbon 1s + 1p has max l = 1 and 1 + 3 Cartesian functions
+  #[test]
+  fn carbon_angular_momentum_and_function_count() {
+    let bs = parse_basis_str(C_1S_1P).expect("should succeed");
+    assert_eq!(bs.max_angular_momentum(), 1);
+    assert_eq!(bs.n_cartesian_functions(), 4);
+  }
+
+  // Scenario: The split Li SP shell counts as an s and a p shell
+  #[test]
+  fn lithium_sp_angular_momentum_and_function_count() {
+    let bs = parse_basis_str(LI_SP).expect("should succeed");
+    assert_eq!(bs.n_shells(), 2);
+    assert_eq!(bs.max_angular_momentum(), 1);
+    assert_eq!(bs.n_cartesian_functions(), 4);
+  }
+
+  // Scenario: Cartesian d and f shells have 6 and 10 functions; no shells, none
+  #[test]
+  fn cartesian_function_count_for_higher_l() {
+    let shell = |l| ElectronShell {
+      angular_momentum: l,
+      exponents: vec![1.0],
+      coefficients: vec![1.0],
+      general_contraction_group: None,
+    };
+    let mut bs =
+      BasisSet { element: "Cu".to_string(), atomic_number: 29, shells: vec![], ecp: None };
+    assert_eq!((bs.max_angular_momentum(), bs.n_cartesian_functions()), (0, 0));
+    bs.shells = vec![shell(2), shell(3)];
+    assert_eq!((bs.max_angular_momentum(), bs.n_cartesian_functions()), (3, 16));
   }
 
   // Scenario: File does not exist
@@ -845,6 +2380,14 @@ mod tests {
     assert!(matches!(parse_basis(&path), Err(ParseError::NoElements)));
   }
 
+  // Scenario: JSON whose root is not an object has no elements
+  #[test]
+  fn non_object_root_has_no_elements() {
+    for json in [r#"[{"elements":{}}]"#, "42", r#""elements""#] {
+      assert_eq!(parse_basis_str(json), Err(ParseError::NoElements), "{}", json);
+    }
+  }
+
   // Scenario: Atomic number key is not a number
   #[test]
   fn atomic_number_not_a_number() {
@@ -1023,7 +2566,7 @@ mod tests {
 
     let dir = temp_dir();
     let result = load_basis_impl("H", "unknown-basis", &server.url(), dir.path());
-    assert!(matches!(result, Err(LoadError::Fetch(BseError::UnknownBasisSet(_)))));
+    assert!(matches!(result, Err(LoadError::Fetch(BseError::UnknownBasisSet { .. }))));
   }
 
   // Scenario: load_basis propagates a parse error
@@ -1040,4 +2583,300 @@ mod tests {
     let result = load_basis_impl("H", "sto-3g", "http://localhost:1", dir.path());
     assert!(matches!(result, Err(LoadError::Parse(_))));
   }
+
+  // Scenario: A cache file whose element key disagrees with the request is rejected
+  #[test]
+  fn load_basis_element_mismatch() {
+    let dir = temp_dir();
+    let cache_path = dir.path().join("sto-3g").join("H.json");
+    std::fs::create_dir_all(cache_path.parent().unwrap()).unwrap();
+    // Element key 2 is He, but the file sits in H's cache slot.
+    std::fs::write(&cache_path, H_STO3G_FULL.replace(r#""1":"#, r#""2":"#)).unwrap();
+
+    let result = load_basis_impl("h", "sto-3g", "http://localhost:1", dir.path());
+    match result {
+      Err(LoadError::ElementMismatch { requested, found }) => {
+        assert_eq!(requested, "H");
+        assert_eq!(found, "He");
+      }
+      other => panic!("expected ElementMismatch, got {:?}", other),
+    }
+  }
+
+  // Scenario: load_basis_in reads from the custom cache root
+  #[test]
+  fn load_basis_in_uses_custom_cache_root() {
+    let dir = temp_dir();
+    let root = dir.path().join("custom").join("cache");
+    let cache_path = root.join("sto-3g").join("H.json");
+    std::fs::create_dir_all(cache_path.parent().unwrap()).unwrap();
+    std::fs::write(&cache_path, H_STO3G_FULL).unwrap();
+
+    let bs = load_basis_in("H", "sto-3g", &root).expect("should load from cache");
+    assert_eq!(bs.element, "H");
+    assert_eq!(bs.shells[0].exponents.len(), 3);
+  }
+
+  // Scenario: A fetched file lands under the custom cache root
+  #[test]
+  fn load_basis_custom_root_receives_download() {
+    let mut server = mockito::Server::new();
+    let _mock = server
+      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=H")
+      .with_status(200)
+      .with_body(H_STO3G_FULL)
+      .create();
+
+    let dir = temp_dir();
+    let root = dir.path().join("elsewhere");
+    load_basis_impl("H", "sto-3g", &server.url(), &root).expect("should succeed");
+    assert!(root.join("sto-3g").join("H.json").is_file());
+  }
+
+  // -------------------------------------------------------------------------
+  // Normalization
+  // -------------------------------------------------------------------------
+
+  // Scenario: A normalized primitive has unit self-overlap for any l
+  #[test]
+  fn single_primitive_has_unit_overlap() {
+    for l in 0..4 {
+      let shell = ElectronShell {
+        angular_momentum: l,
+        exponents: vec![0.8],
+        coefficients: vec![1.0],
+        general_contraction_group: None,
+      };
+      assert!((shell_self_overlap(&shell) - 1.0).abs() < 1e-12, "l = {}", l);
+    }
+  }
+
+  // Scenario: An unnormalized shell is rescaled to unit self-overlap
+  #[test]
+  fn renormalize_unnormalized_shell() {
+    let mut bs = BasisSet {
+      element: "C".to_string(),
+      atomic_number: 6,
+      shells: vec![
+        ElectronShell {
+          angular_momentum: 0,
+          exponents: vec![3.0, 0.5],
+          coefficients: vec![1.0, 1.0],
+          general_contraction_group: None,
+        },
+        ElectronShell {
+          angular_momentum: 1,
+          exponents: vec![2.0, 0.4],
+          coefficients: vec![0.3, 2.0],
+          general_contraction_group: None,
+        },
+      ],
+      ecp: None,
+    };
+    assert!((shell_self_overlap(&bs.shells[0]) - 1.0).abs() > 0.1);
+    bs.renormalize();
+    for shell in &bs.shells {
+      assert!((shell_self_overlap(shell) - 1.0).abs() < 1e-12);
+    }
+  }
+
+  // Scenario: Renormalizing twice leaves coefficients unchanged
+  #[test]
+  fn renormalize_is_idempotent() {
+    let dir = temp_dir();
+    let path = write_json(&dir, C_1S_1P);
+    let mut bs = parse_basis(&path).expect("should succeed");
+    bs.renormalize();
+    let once = bs.clone();
+    bs.renormalize();
+    for (a, b) in once.shells.iter().zip(&bs.shells) {
+      for (ca, cb) in a.coefficients.iter().zip(&b.coefficients) {
+        assert!((ca - cb).abs() < 1e-12);
+      }
+    }
+  }
+
+  // -------------------------------------------------------------------------
+  // parse_basis_all
+  // -------------------------------------------------------------------------
+
+  // H, C and O in one file (keys deliberately out of numeric order).
+  const HCO_MULTI: &str = r#"{"elements":{
+    "8":{"electron_shells":[
+      {"function_type":"gto","angular_momentum":[0],
+       "exponents":["130.7093200","23.8088610","6.4436083"],
+       "coefficients":[["0.1543290","0.5353281","0.4446345"]]}]},
+    "1":{"electron_shells":[
+      {"function_type":"gto","angular_momentum":[0],
+       "exponents":["3.4252509","0.6239137","0.1688554"],
+       "coefficients":[["0.1543290","0.5353281","0.4446345"]]}]},
+    "6":{"electron_shells":[
+      {"function_type":"gto","angular_momentum":[0],
+       "exponents":["71.6168370","13.0450963","3.5305122"],
+       "coefficients":[["0.1543290","0.5353281","0.4446345"]]},
+      {"function_type":"gto","angular_momentum":[1],
+       "exponents":["2.9412494","0.6834831","0.2222899"],
+       "coefficients":[["0.2364600","0.8768660","0.2364600"]]}]}
+  }}"#;
+
+  // Scenario: A three-element file yields one BasisSet per element
+  #[test]
+  fn parse_all_three_elements() {
+    let dir = temp_dir();
+    let path = write_json(&dir, HCO_MULTI);
+    let sets = parse_basis_all(&path).expect("should succeed");
+    let elements: Vec<&str> = sets.iter().map(|bs| bs.element.as_str()).collect();
+    assert_eq!(elements, vec!["H", "C", "O"]);
+    assert_eq!(sets[1].atomic_number, 6);
+    assert_eq!(sets[1].n_shells(), 2);
+  }
+
+  // Scenario: parse_basis still rejects a multi-element file
+  #[test]
+  fn parse_basis_rejects_multi_element_file() {
+    let dir = temp_dir();
+    let path = write_json(&dir, HCO_MULTI);
+    assert_eq!(parse_basis(&path), Err(ParseError::MultipleElements { found: 3 }));
+  }
+
+  // Scenario: parse_basis_all accepts a single-element file
+  #[test]
+  fn parse_all_single_element() {
+    let dir = temp_dir();
+    let path = write_json(&dir, H_1S);
+    let sets = parse_basis_all(&path).expect("should succeed");
+    assert_eq!(sets.len(), 1);
+    assert_eq!(sets[0].element, "H");
+  }
+
+  // Scenario: parse_basis_all with an empty elements object
+  #[test]
+  fn parse_all_empty_elements() {
+    let dir = temp_dir();
+    let path = write_json(&dir, r#"{"elements":{}}"#);
+    assert_eq!(parse_basis_all(&path), Err(ParseError::NoElements));
+  }
+
+  // Scenario: load_basis_from_file reads a local single-element file offline
+  #[test]
+  fn load_from_file_single_element() {
+    let dir = temp_dir();
+    let path = write_json(&dir, H_1S);
+    let bs = load_basis_from_file(&path).expect("should succeed");
+    assert_eq!(bs.element, "H");
+    assert_eq!(dir.path().read_dir().unwrap().count(), 1, "nothing cached");
+  }
+
+  // Scenario: load_basis_from_file points multi-element files elsewhere
+  #[test]
+  fn load_from_file_rejects_multi_element() {
+    let dir = temp_dir();
+    let path = write_json(&dir, HCO_MULTI);
+    assert_eq!(load_basis_from_file(&path), Err(ParseError::MultipleElements { found: 3 }));
+  }
+
+  // Scenario: A renormalized basis is approximately but not exactly equal
+  #[test]
+  fn renormalized_basis_is_approx_eq() {
+    let dir = temp_dir();
+    // Published STO-3G coefficients are rounded to 7 digits, so renormalizing
+    // perturbs them only in the last few places.
+    let path = write_json(&dir, H_1S);
+    let original = parse_basis(&path).expect("should succeed");
+    let mut renormalized = original.clone();
+    renormalized.renormalize();
+    assert_ne!(renormalized, original);
+    assert!(renormalized.approx_eq(&original, 1e-6));
+    assert!(!renormalized.approx_eq(&original, 0.0));
+  }
+
+  // Scenario: Bases for different elements are never approx_eq
+  #[test]
+  fn approx_eq_requires_same_element() {
+    let dir = temp_dir();
+    let h = parse_basis(&write_json(&dir, H_1S)).expect("should succeed");
+    let mut other = h.clone();
+    other.element = "He".to_string();
+    other.atomic_number = 2;
+    assert!(h.approx_eq(&h, 0.0));
+    assert!(!h.approx_eq(&other, 1.0));
+  }
+
+  // -------------------------------------------------------------------------
+  // to_qcschema_json
+  // -------------------------------------------------------------------------
+
+  // Scenario: Serialized JSON parses back to bitwise-identical values
+  #[test]
+  fn qcschema_json_round_trip_is_exact() {
+    let bs = BasisSet {
+      element: "C".to_string(),
+      atomic_number: 6,
+      shells: vec![
+        ElectronShell {
+          angular_momentum: 0,
+          exponents: vec![71.616837, 0.1 + 0.2, std::f64::consts::PI, 1e-300],
+          coefficients: vec![0.154329, 1.0 / 3.0, -2.0_f64.sqrt(), 5e-324],
+          general_contraction_group: None,
+        },
+        ElectronShell {
+          angular_momentum: 2,
+          exponents: vec![f64::MAX, 0.6834831],
+          coefficients: vec![0.876866 * 1.1, f64::EPSILON],
+          general_contraction_group: None,
+        },
+      ],
+      ecp: None,
+    };
+    let back = parse_basis_str(&bs.to_qcschema_json()).expect("should parse");
+    assert_eq!(back.element, "C");
+    assert_eq!(back.atomic_number, 6);
+    assert_eq!(back.shells.len(), 2);
+    for (a, b) in bs.shells.iter().zip(&back.shells) {
+      assert_eq!(a.angular_momentum, b.angular_momentum);
+      for (x, y) in a.exponents.iter().zip(&b.exponents) {
+        assert_eq!(x.to_bits(), y.to_bits());
+      }
+      for (x, y) in a.coefficients.iter().zip(&b.coefficients) {
+        assert_eq!(x.to_bits(), y.to_bits());
+      }
+    }
+  }
+
+  // -------------------------------------------------------------------------
+  // General contraction groups
+  // -------------------------------------------------------------------------
+
+  // Scenario: Both shells split from an SP shell share one group id
+  #[test]
+  fn sp_split_shells_share_group() {
+    let bs = parse_basis_str(LI_SP).expect("should succeed");
+    assert_eq!(bs.shells.len(), 2);
+    assert_eq!(bs.shells[0].general_contraction_group, Some(0));
+    assert_eq!(bs.shells[1].general_contraction_group, Some(0));
+
+    // Splitting through the options keeps the group on the combined shells.
+    let combined = parse_basis_str_with(LI_SP, &ParseBasisOptions::default()).unwrap();
+    let groups: Vec<_> = combined.shells.iter().map(|sh| sh.general_contraction_group).collect();
+    assert_eq!(groups, vec![Some(0), Some(0)]);
+  }
+
+  // Scenario: Separate file shells get distinct group ids in file order
+  #[test]
+  fn separate_shells_get_distinct_groups() {
+    let bs = parse_basis_str(C_1S_1P).expect("should succeed");
+    let groups: Vec<_> = bs.shells.iter().map(|sh| sh.general_contraction_group).collect();
+    assert_eq!(groups, vec![Some(0), Some(1)]);
+  }
+
+  // Scenario: An SP group is written back as one SP shell
+  #[test]
+  fn sp_group_survives_qcschema_round_trip() {
+    let bs = parse_basis_str(LI_SP).expect("should succeed");
+    let json: serde_json::Value = serde_json::from_str(&bs.to_qcschema_json()).unwrap();
+    let shells = json["elements"]["3"]["electron_shells"].as_array().unwrap();
+    assert_eq!(shells.len(), 1);
+    assert_eq!(shells[0]["angular_momentum"], serde_json::json!([0, 1]));
+    assert_eq!(parse_basis_str(&bs.to_qcschema_json()), Ok(bs));
+  }
 }
@@ -0,0 +1,67 @@
+/// All 118 known element symbols in title case, indexed by atomic number (1-based).
+pub const ELEMENTS: &[&str] = &[
+  "H",  "He", "Li", "Be", "B",  "C",  "N",  "O",  "F",  "Ne",
+  "Na", "Mg", "Al", "Si", "P",  "S",  "Cl", "Ar", "K",  "Ca",
+  "Sc", "Ti", "V",  "Cr", "Mn", "Fe", "Co", "Ni", "Cu", "Zn",
+  "Ga", "Ge", "As", "Se", "Br", "Kr", "Rb", "Sr", "Y",  "Zr",
+  "Nb", "Mo", "Tc", "Ru", "Rh", "Pd", "Ag", "Cd", "In", "Sn",
+  "Sb", "Te", "I",  "Xe", "Cs", "Ba", "La", "Ce", "Pr", "Nd",
+  "Pm", "Sm", "Eu", "Gd", "Tb", "Dy", "Ho", "Er", "Tm", "Yb",
+  "Lu", "Hf", "Ta", "W",  "Re", "Os", "Ir", "Pt", "Au", "Hg",
+  "Tl", "Pb", "Bi", "Po", "At", "Rn", "Fr", "Ra", "Ac", "Th",
+  "Pa", "U",  "Np", "Pu", "Am", "Cm", "Bk", "Cf", "Es", "Fm",
+  "Md", "No", "Lr", "Rf", "Db", "Sg", "Bh", "Hs", "Mt", "Ds",
+  "Rg", "Cn", "Nh", "Fl", "Mc", "Lv", "Ts", "Og",
+];
+
+// ── Public functions ──────────────────────────────────────────────────────────
+
+/// Atomic number of a title-case element symbol; `None` if it is not one.
+pub fn atomic_number(symbol: &str) -> Option<u32> {
+  ELEMENTS.iter().position(|&e| e == symbol).map(|i| i as u32 + 1)
+}
+
+/// Title-case symbol of element `z`; `None` outside 1..=118.
+pub fn element_symbol(z: u32) -> Option<&'static str> {
+  ELEMENTS.get((z as usize).checked_sub(1)?).copied()
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn hydrogen_round_trips() {
+    assert_eq!(atomic_number("H"), Some(1));
+    assert_eq!(element_symbol(1), Some("H"));
+  }
+
+  #[test]
+  fn oganesson_round_trips() {
+    assert_eq!(atomic_number("Og"), Some(118));
+    assert_eq!(element_symbol(118), Some("Og"));
+  }
+
+  #[test]
+  fn every_element_round_trips() {
+    for z in 1..=118 {
+      assert_eq!(atomic_number(element_symbol(z).unwrap()), Some(z));
+    }
+  }
+
+  #[test]
+  fn out_of_range_atomic_numbers() {
+    assert_eq!(element_symbol(0), None);
+    assert_eq!(element_symbol(119), None);
+  }
+
+  #[test]
+  fn unknown_symbols() {
+    assert_eq!(atomic_number("Xx"), None);
+    assert_eq!(atomic_number(""), None);
+    // Symbols must already be title case.
+    assert_eq!(atomic_number("he"), None);
+  }
+}
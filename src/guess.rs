@@ -1,5 +1,8 @@
 use faer::{Mat, Side};
 
+use crate::input::CartesianGeometry;
+use crate::orbital::AoBasis;
+
 // ── Public types ──────────────────────────────────────────────────────────────
 
 /// Error type returned by `guess_hcore`.
@@ -20,6 +23,22 @@ pub enum GuessError {
   },
   /// S is not positive definite; Cholesky factorisation would fail.
   SingularOverlap,
+  /// The atomic density supplied for `atom` is `got` (nrows, ncols) but the
+  /// atom carries `expected` basis functions.
+  AtomicDensityMismatch {
+    atom: usize,
+    expected: usize,
+    got: (usize, usize),
+  },
+}
+
+/// How the orthogonaliser X (with X^T S X = I) is built from S = U Λ U^T.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrthoScheme {
+  /// X = U Λ^{−1/2}.
+  Canonical,
+  /// Löwdin: X = U Λ^{−1/2} U^T, which is itself symmetric.
+  Symmetric,
 }
 
 // ── Public functions ──────────────────────────────────────────────────────────
@@ -43,10 +62,219 @@ pub fn guess_hcore(
   n_alpha: usize,
   n_beta: usize,
 ) -> Result<Mat<f64>, GuessError> {
-  let s_shape = (s.nrows(), s.ncols());
-  let t_shape = (t.nrows(), t.ncols());
-  let v_shape = (v.nrows(), v.ncols());
+  guess_hcore_with(s, t, v, n_alpha, n_beta, OrthoScheme::Canonical)
+}
+
+/// [`guess_hcore`] with the orthogonalisation in step 2 selected by `scheme`.
+pub fn guess_hcore_with(
+  s: &Mat<f64>,
+  t: &Mat<f64>,
+  v: &Mat<f64>,
+  n_alpha: usize,
+  n_beta: usize,
+  scheme: OrthoScheme,
+) -> Result<Mat<f64>, GuessError> {
+  let n = validate_inputs(s, t, v, n_alpha, n_beta)?;
+
+  // Degenerate case: no basis functions → empty coefficient matrix.
+  if n == 0 {
+    return Ok(Mat::zeros(0, 0));
+  }
+
+  let x = orthogonalizer(s, scheme)?;
+  Ok(diagonalize_core(&x, t, v))
+}
+
+/// [`guess_hcore`] with near-linear-dependence removal: eigenvectors of S whose
+/// eigenvalue is below `lindep_threshold` are dropped in step 2, so C is
+/// n_basis × n_kept with C^T S C = I. `SingularOverlap` is returned only for a
+/// negative eigenvalue (or if nothing is kept); `TooManyElectrons` compares
+/// against n_kept.
+pub fn guess_hcore_with_threshold(
+  s: &Mat<f64>,
+  t: &Mat<f64>,
+  v: &Mat<f64>,
+  n_alpha: usize,
+  n_beta: usize,
+  lindep_threshold: f64,
+) -> Result<Mat<f64>, GuessError> {
+  let n = validate_inputs(s, t, v, n_alpha, n_beta)?;
+  if n == 0 {
+    return Ok(Mat::zeros(0, 0));
+  }
+
+  let x = truncated_orthogonalizer(s, lindep_threshold)?;
+  guess_hcore_with_x(&x, t, v, n_alpha, n_beta)
+}
+
+/// Builds the canonical orthogonaliser X = U_s Λ_s^{−1/2} for S, for reuse
+/// with [`guess_hcore_with_x`].
+pub fn build_orthogonalizer(s: &Mat<f64>) -> Result<Mat<f64>, GuessError> {
+  if s.nrows() != s.ncols() {
+    let shape = (s.nrows(), s.ncols());
+    return Err(GuessError::DimensionMismatch { s_shape: shape, t_shape: shape, v_shape: shape });
+  }
+  if s.nrows() == 0 {
+    return Ok(Mat::zeros(0, 0));
+  }
+  orthogonalizer(s, OrthoScheme::Canonical)
+}
+
+/// [`guess_hcore`] with a precomputed orthogonaliser X in place of S, skipping
+/// steps 1–2. X must have as many rows as T and V; its shape is reported in the
+/// `s_shape` field of `DimensionMismatch`.
+pub fn guess_hcore_with_x(
+  x: &Mat<f64>,
+  t: &Mat<f64>,
+  v: &Mat<f64>,
+  n_alpha: usize,
+  n_beta: usize,
+) -> Result<Mat<f64>, GuessError> {
+  let n = x.nrows();
+  if t.nrows() != t.ncols()
+    || v.nrows() != v.ncols()
+    || t.nrows() != n
+    || v.nrows() != n
+    || x.ncols() > n
+  {
+    return Err(GuessError::DimensionMismatch {
+      s_shape: (x.nrows(), x.ncols()),
+      t_shape: (t.nrows(), t.ncols()),
+      v_shape: (v.nrows(), v.ncols()),
+    });
+  }
+
+  if n_alpha > x.ncols() || n_beta > x.ncols() {
+    return Err(GuessError::TooManyElectrons { n_alpha, n_beta, n_basis: x.ncols() });
+  }
+
+  if n == 0 {
+    return Ok(Mat::zeros(0, 0));
+  }
+
+  Ok(diagonalize_core(x, t, v))
+}
+
+/// Returns the initial MO coefficient matrix C using the generalized
+/// Wolfsberg–Helmholtz guess in place of the bare core Hamiltonian.
+///
+/// With H = T + V, the guess Fock matrix is
+///   F_ii = H_ii
+///   F_ij = ½ K S_ij (H_ii + H_jj)   (i ≠ j)
+/// where K = `h_diag_scale` (typically 1.75). F is diagonalised through the
+/// canonical orthogonaliser exactly as H is in [`guess_hcore`], so C^T S C = I
+/// and the errors are the same.
+pub fn guess_gwh(
+  s: &Mat<f64>,
+  t: &Mat<f64>,
+  v: &Mat<f64>,
+  h_diag_scale: f64,
+  n_alpha: usize,
+  n_beta: usize,
+) -> Result<Mat<f64>, GuessError> {
+  let n = validate_inputs(s, t, v, n_alpha, n_beta)?;
+  if n == 0 {
+    return Ok(Mat::zeros(0, 0));
+  }
+
+  let x = orthogonalizer(s, OrthoScheme::Canonical)?;
+  let f = gwh_fock(s, &(t + v), h_diag_scale);
+  Ok(diagonalize(&x, &f))
+}
+
+/// Runs `guess_hcore` for every (T, V) pair in `th_pairs`, all sharing the
+/// overlap matrix `s`. S is decomposed once and X reused for every pair.
+pub fn build_guesses(
+  s: &Mat<f64>,
+  th_pairs: &[(Mat<f64>, Mat<f64>)],
+  n_alpha: usize,
+  n_beta: usize,
+) -> Result<Vec<Mat<f64>>, GuessError> {
+  build_guesses_impl(s, th_pairs, n_alpha, n_beta, |s| {
+    orthogonalizer(s, OrthoScheme::Canonical)
+  })
+}
+
+/// The HOMO (column `n_occ − 1`) and LUMO (column `n_occ`) coefficient vectors
+/// of a C whose columns are sorted by ascending orbital energy, as returned by
+/// [`guess_hcore`]. `None` when there is no occupied or no virtual orbital.
+pub fn frontier_orbitals(c: &Mat<f64>, n_occ: usize) -> Option<(Vec<f64>, Vec<f64>)> {
+  if n_occ == 0 || n_occ >= c.ncols() {
+    return None;
+  }
+  let column = |j: usize| (0..c.nrows()).map(|i| c[(i, j)]).collect();
+  Some((column(n_occ - 1), column(n_occ)))
+}
+
+/// Density matrix P = C_occ C_occ^T (n_basis × n_basis) for one spin, where
+/// C_occ is the first `n_occ` columns of `c`. P is symmetric and, for C with
+/// C^T S C = I, Tr(P S) = n_occ.
+///
+/// Panics if `n_occ` exceeds the number of columns of `c`.
+pub fn density_matrix(c: &Mat<f64>, n_occ: usize) -> Mat<f64> {
+  assert!(n_occ <= c.ncols(), "n_occ = {} exceeds {} MOs", n_occ, c.ncols());
+  let n = c.nrows();
+  Mat::from_fn(n, n, |i, j| (0..n_occ).map(|k| c[(i, k)] * c[(j, k)]).sum())
+}
+
+/// The alpha and beta density matrices `(P_α, P_β)` from separate coefficient
+/// matrices, each as [`density_matrix`]. For a restricted guess pass the same
+/// C twice.
+pub fn alpha_beta_densities(
+  c_alpha: &Mat<f64>,
+  c_beta: &Mat<f64>,
+  n_alpha: usize,
+  n_beta: usize,
+) -> (Mat<f64>, Mat<f64>) {
+  (density_matrix(c_alpha, n_alpha), density_matrix(c_beta, n_beta))
+}
+
+/// Superposition-of-atomic-densities guess: a block-diagonal density
+/// (n_basis × n_basis) with `atomic_density(symbol)` placed on each atom's
+/// basis-function range and zeros between atoms. Ghost atoms carry no
+/// electrons, so their blocks stay zero.
+///
+/// Each block must be square with one row per function on that atom;
+/// otherwise `AtomicDensityMismatch` is returned.
+pub fn sad_density(
+  basis: &AoBasis,
+  geometry: &CartesianGeometry,
+  atomic_density: impl Fn(&str) -> Mat<f64>,
+) -> Result<Mat<f64>, GuessError> {
+  let mut p = Mat::zeros(basis.n_basis, basis.n_basis);
+  for (atom, symbol) in geometry.symbols.iter().enumerate() {
+    if geometry.is_ghost[atom] {
+      continue;
+    }
+    let range = basis.functions_on_atom(atom);
+    let block = atomic_density(symbol);
+    if (block.nrows(), block.ncols()) != (range.len(), range.len()) {
+      return Err(GuessError::AtomicDensityMismatch {
+        atom,
+        expected: range.len(),
+        got: (block.nrows(), block.ncols()),
+      });
+    }
+    for (i, mu) in range.clone().enumerate() {
+      for (j, nu) in range.clone().enumerate() {
+        p.write(mu, nu, block[(i, j)]);
+      }
+    }
+  }
+  Ok(p)
+}
 
+// ── Private helpers ───────────────────────────────────────────────────────────
+
+/// Checks that S, T and V share one n × n shape and that neither spin count
+/// exceeds n. Returns n.
+fn validate_inputs(
+  s: &Mat<f64>,
+  t: &Mat<f64>,
+  v: &Mat<f64>,
+  n_alpha: usize,
+  n_beta: usize,
+) -> Result<usize, GuessError> {
   // Dimension check: all three must be square and share the same side length.
   let n = s.nrows();
   if s.nrows() != s.ncols()
@@ -55,21 +283,61 @@ pub fn guess_hcore(
     || t.nrows() != n
     || v.nrows() != n
   {
-    return Err(GuessError::DimensionMismatch { s_shape, t_shape, v_shape });
+    return Err(GuessError::DimensionMismatch {
+      s_shape: (s.nrows(), s.ncols()),
+      t_shape: (t.nrows(), t.ncols()),
+      v_shape: (v.nrows(), v.ncols()),
+    });
   }
 
   // Electron count check (performed after dimension check).
   if n_alpha > n || n_beta > n {
     return Err(GuessError::TooManyElectrons { n_alpha, n_beta, n_basis: n });
   }
+  Ok(n)
+}
 
-  // Degenerate case: no basis functions → empty coefficient matrix.
+/// Implementation of `build_guesses` with an injectable orthogonaliser builder.
+fn build_guesses_impl(
+  s: &Mat<f64>,
+  th_pairs: &[(Mat<f64>, Mat<f64>)],
+  n_alpha: usize,
+  n_beta: usize,
+  mut ortho: impl FnMut(&Mat<f64>) -> Result<Mat<f64>, GuessError>,
+) -> Result<Vec<Mat<f64>>, GuessError> {
+  let n = s.nrows();
+  let s_shape = (s.nrows(), s.ncols());
+  for (t, v) in th_pairs {
+    if s.nrows() != s.ncols()
+      || t.nrows() != n || t.ncols() != n
+      || v.nrows() != n || v.ncols() != n
+    {
+      return Err(GuessError::DimensionMismatch {
+        s_shape,
+        t_shape: (t.nrows(), t.ncols()),
+        v_shape: (v.nrows(), v.ncols()),
+      });
+    }
+  }
+
+  if n_alpha > n || n_beta > n {
+    return Err(GuessError::TooManyElectrons { n_alpha, n_beta, n_basis: n });
+  }
+
+  if th_pairs.is_empty() {
+    return Ok(Vec::new());
+  }
   if n == 0 {
-    return Ok(Mat::zeros(0, 0));
+    return Ok(th_pairs.iter().map(|_| Mat::zeros(0, 0)).collect());
   }
 
-  // H_core = T + V.
-  let h_core: Mat<f64> = t + v;
+  let x = ortho(s)?;
+  Ok(th_pairs.iter().map(|(t, v)| diagonalize_core(&x, t, v)).collect())
+}
+
+/// Builds the orthogonaliser X for `scheme` from S (n ≥ 1).
+fn orthogonalizer(s: &Mat<f64>, scheme: OrthoScheme) -> Result<Mat<f64>, GuessError> {
+  let n = s.nrows();
 
   // Eigendecompose S: S = U_s Λ_s U_s^T.
   let evd_s = s.selfadjoint_eigendecomposition(Side::Lower);
@@ -82,20 +350,77 @@ pub fn guess_hcore(
     return Err(GuessError::SingularOverlap);
   }
 
-  // Build orthogonaliser X = U_s * diag(λ_s^{−1/2}).
-  // Column j of X is the j-th eigenvector of S scaled by λ_s[j]^{-1/2}.
   let u_s = evd_s.u().to_owned();
-  let mut x: Mat<f64> = Mat::zeros(n, n);
-  for j in 0..n {
+  let x = scaled_eigenvectors(&u_s, &lambdas_s, &(0..n).collect::<Vec<_>>());
+
+  match scheme {
+    OrthoScheme::Canonical => Ok(x),
+    // X_sym = (U_s Λ_s^{−1/2}) U_s^T.
+    OrthoScheme::Symmetric => Ok(&x * u_s.transpose()),
+  }
+}
+
+/// Canonical orthogonaliser with eigenvalues of S below `threshold` dropped,
+/// giving an n × n_kept X. Only negative eigenvalues (or nothing kept) are an
+/// error.
+fn truncated_orthogonalizer(s: &Mat<f64>, threshold: f64) -> Result<Mat<f64>, GuessError> {
+  let n = s.nrows();
+
+  let evd_s = s.selfadjoint_eigendecomposition(Side::Lower);
+  let lambdas_s: Vec<f64> = (0..n)
+    .map(|i| evd_s.s().column_vector().read(i))
+    .collect();
+
+  if lambdas_s.iter().any(|&l| l < 0.0) {
+    return Err(GuessError::SingularOverlap);
+  }
+  let kept: Vec<usize> = (0..n).filter(|&j| lambdas_s[j] >= threshold && lambdas_s[j] > 0.0).collect();
+  if kept.is_empty() {
+    return Err(GuessError::SingularOverlap);
+  }
+
+  Ok(scaled_eigenvectors(&evd_s.u().to_owned(), &lambdas_s, &kept))
+}
+
+/// X = U_s[:, cols] diag(λ_s[cols]^{−1/2}).
+fn scaled_eigenvectors(u_s: &Mat<f64>, lambdas_s: &[f64], cols: &[usize]) -> Mat<f64> {
+  // Column k of X is eigenvector cols[k] of S scaled by λ^{-1/2}.
+  let n = u_s.nrows();
+  let mut x: Mat<f64> = Mat::zeros(n, cols.len());
+  for (k, &j) in cols.iter().enumerate() {
     let scale = lambdas_s[j].powf(-0.5);
     for i in 0..n {
-      x.write(i, j, u_s[(i, j)] * scale);
+      x.write(i, k, u_s[(i, j)] * scale);
     }
   }
+  x
+}
 
-  // H' = X^T H_core X.
-  let xt_hc: Mat<f64> = x.transpose() * &h_core;
-  let h_prime: Mat<f64> = &xt_hc * &x;
+/// Steps 3–5 of `guess_hcore`: C = X U' with H' = X^T (T + V) X = U' ε U'^T.
+fn diagonalize_core(x: &Mat<f64>, t: &Mat<f64>, v: &Mat<f64>) -> Mat<f64> {
+  // H_core = T + V.
+  let h_core: Mat<f64> = t + v;
+  diagonalize(x, &h_core)
+}
+
+/// GWH Fock matrix: H on the diagonal, ½ K S_ij (H_ii + H_jj) off it.
+fn gwh_fock(s: &Mat<f64>, h: &Mat<f64>, k: f64) -> Mat<f64> {
+  Mat::from_fn(h.nrows(), h.ncols(), |i, j| {
+    if i == j {
+      h[(i, i)]
+    } else {
+      0.5 * k * s[(i, j)] * (h[(i, i)] + h[(j, j)])
+    }
+  })
+}
+
+/// C = X U' with H' = X^T H X = U' ε U'^T, columns sorted by ascending ε.
+fn diagonalize(x: &Mat<f64>, h: &Mat<f64>) -> Mat<f64> {
+  let n = x.ncols();
+
+  // H' = X^T H X.
+  let xt_hc: Mat<f64> = x.transpose() * h;
+  let h_prime: Mat<f64> = &xt_hc * x;
 
   // Eigendecompose H' and obtain indices sorted by ascending orbital energy.
   let evd_h = h_prime.selfadjoint_eigendecomposition(Side::Lower);
@@ -120,7 +445,7 @@ pub fn guess_hcore(
   }
 
   // C = X U'_sorted.
-  Ok(&x * &u_sorted)
+  x * &u_sorted
 }
 
 // ── Tests ─────────────────────────────────────────────────────────────────────
@@ -142,20 +467,10 @@ mod tests {
 
   // Verify C^T S C ≈ identity to within `tol`.
   fn assert_orthonormal(c: &Mat<f64>, s: &Mat<f64>, tol: f64) {
-    let n = c.nrows();
+    let n = c.ncols();
     let ct_s: Mat<f64> = c.transpose() * s;
     let ct_s_c: Mat<f64> = &ct_s * c;
-    for i in 0..n {
-      for j in 0..n {
-        let expected = if i == j { 1.0 } else { 0.0 };
-        let diff = (ct_s_c[(i, j)] - expected).abs();
-        assert!(
-          diff < tol,
-          "C^T S C [{i},{j}] = {:.6}, expected {expected:.1}, diff {diff:.2e}",
-          ct_s_c[(i, j)]
-        );
-      }
-    }
+    assert_mat_eq(&ct_s_c, &Mat::identity(n, n), tol);
   }
 
   // H₂-like STO-3G matrices at ~1.4 Bohr.
@@ -398,4 +713,448 @@ mod tests {
     let v = Mat::<f64>::zeros(2, 2);
     assert_eq!(guess_hcore(&s, &t, &v, 0, 0), Err(GuessError::SingularOverlap));
   }
+
+  // ── Batch guesses ──────────────────────────────────────────────────────────
+
+  type TvPairs = Vec<(Mat<f64>, Mat<f64>)>;
+
+  fn h2_scan_pairs() -> (Mat<f64>, TvPairs) {
+    let (s, t, v) = h2();
+    let t2 = mat2([0.800, 0.200, 0.200, 0.700]);
+    let v2 = mat2([-1.900, -1.100, -1.100, -1.800]);
+    (s, vec![(t, v), (t2, v2)])
+  }
+
+  fn assert_mat_eq(a: &Mat<f64>, b: &Mat<f64>, tol: f64) {
+    assert_eq!((a.nrows(), a.ncols()), (b.nrows(), b.ncols()));
+    if crate::util::mats_approx_eq(a, b, tol) {
+      return;
+    }
+    let close = |i: usize, j: usize| (a[(i, j)] - b[(i, j)]).abs() <= tol;
+    let (i, j) = (0..a.nrows())
+      .flat_map(|i| (0..a.ncols()).map(move |j| (i, j)))
+      .find(|&(i, j)| !close(i, j))
+      .unwrap();
+    panic!("mismatch at ({}, {}): {} vs {}", i, j, a[(i, j)], b[(i, j)]);
+  }
+
+  /// Scenario: Batch results equal individually computed guesses.
+  #[test]
+  fn batch_matches_individual_guesses() {
+    let (s, pairs) = h2_scan_pairs();
+    let batch = build_guesses(&s, &pairs, 1, 1).expect("should succeed");
+    assert_eq!(batch.len(), 2);
+    for ((t, v), c) in pairs.iter().zip(&batch) {
+      let single = guess_hcore(&s, t, v, 1, 1).unwrap();
+      assert_mat_eq(c, &single, 1e-12);
+    }
+  }
+
+  /// Scenario: S is decomposed exactly once for the whole batch.
+  #[test]
+  fn batch_decomposes_overlap_once() {
+    let (s, pairs) = h2_scan_pairs();
+    let mut calls = 0;
+    build_guesses_impl(&s, &pairs, 1, 1, |s| {
+      calls += 1;
+      orthogonalizer(s, OrthoScheme::Canonical)
+    })
+    .unwrap();
+    assert_eq!(calls, 1);
+  }
+
+  /// Scenario: A pair whose shape differs from S → DimensionMismatch.
+  #[test]
+  fn batch_rejects_mismatched_pair() {
+    let (s, mut pairs) = h2_scan_pairs();
+    pairs.push((Mat::zeros(3, 3), Mat::zeros(2, 2)));
+    assert_eq!(
+      build_guesses(&s, &pairs, 1, 1),
+      Err(GuessError::DimensionMismatch {
+        s_shape: (2, 2),
+        t_shape: (3, 3),
+        v_shape: (2, 2),
+      })
+    );
+  }
+
+  /// Scenario: An empty batch returns no guesses.
+  #[test]
+  fn batch_empty() {
+    let (s, _, _) = h2();
+    assert_eq!(build_guesses(&s, &[], 1, 1), Ok(Vec::new()));
+  }
+
+  // ── Orthogonalisation schemes ──────────────────────────────────────────────
+
+  /// Scenario: Symmetric orthogonalisation yields orthonormal MOs.
+  #[test]
+  fn symmetric_scheme_c_is_orthonormal() {
+    let (s, t, v) = h2();
+    let c = guess_hcore_with(&s, &t, &v, 1, 1, OrthoScheme::Symmetric)
+      .expect("should succeed");
+    assert_orthonormal(&c, &s, 1e-10);
+  }
+
+  /// Scenario: The Löwdin orthogonaliser for the H2 overlap is symmetric.
+  #[test]
+  fn symmetric_scheme_x_is_symmetric() {
+    let (s, _, _) = h2();
+    let x = orthogonalizer(&s, OrthoScheme::Symmetric).unwrap();
+    assert!((x[(0, 1)] - x[(1, 0)]).abs() < 1e-12);
+    // For S = [[1, a], [a, 1]], S^{-1/2} has diagonal ((1+a)^{-1/2} + (1-a)^{-1/2}) / 2.
+    let expected = (1.5_f64.powf(-0.5) + 0.5_f64.powf(-0.5)) / 2.0;
+    assert!((x[(0, 0)] - expected).abs() < 1e-12);
+  }
+
+  /// Scenario: guess_hcore defaults to canonical orthogonalisation.
+  #[test]
+  fn default_scheme_is_canonical() {
+    let (s, t, v) = three_by_three();
+    let a = guess_hcore(&s, &t, &v, 1, 1).unwrap();
+    let b = guess_hcore_with(&s, &t, &v, 1, 1, OrthoScheme::Canonical).unwrap();
+    assert_mat_eq(&a, &b, 1e-14);
+  }
+
+  // ── Precomputed orthogonaliser ─────────────────────────────────────────────
+
+  /// Scenario: Supplying X from build_orthogonalizer reproduces guess_hcore.
+  #[test]
+  fn precomputed_x_reproduces_guess_hcore() {
+    let (s, t, v) = h2();
+    let x = build_orthogonalizer(&s).unwrap();
+    let c = guess_hcore_with_x(&x, &t, &v, 1, 1).unwrap();
+    assert_eq!(c, guess_hcore(&s, &t, &v, 1, 1).unwrap());
+  }
+
+  /// Scenario: X whose row count differs from T/V → DimensionMismatch.
+  #[test]
+  fn precomputed_x_shape_mismatch() {
+    let (_, t, v) = h2();
+    let x = Mat::<f64>::identity(3, 3);
+    assert_eq!(
+      guess_hcore_with_x(&x, &t, &v, 1, 1),
+      Err(GuessError::DimensionMismatch {
+        s_shape: (3, 3),
+        t_shape: (2, 2),
+        v_shape: (2, 2),
+      })
+    );
+  }
+
+  /// Scenario: build_orthogonalizer rejects a singular S.
+  #[test]
+  fn build_orthogonalizer_singular() {
+    let s = mat2([1.0, 1.0, 1.0, 1.0]);
+    assert_eq!(build_orthogonalizer(&s), Err(GuessError::SingularOverlap));
+  }
+
+  // ── Linear-dependence truncation ───────────────────────────────────────────
+
+  // S = R diag(2, 1e-8) R^T for a 30° rotation R.
+  fn near_singular_overlap() -> Mat<f64> {
+    let (c, sn) = (30.0_f64.to_radians().cos(), 30.0_f64.to_radians().sin());
+    let (l0, l1) = (2.0, 1e-8);
+    mat2([
+      l0 * c * c + l1 * sn * sn, (l0 - l1) * c * sn,
+      (l0 - l1) * c * sn, l0 * sn * sn + l1 * c * c,
+    ])
+  }
+
+  /// Scenario: A tiny eigenvalue below the threshold is dropped.
+  #[test]
+  fn truncation_drops_small_eigenvalue() {
+    let s = near_singular_overlap();
+    let t = mat2([0.8, 0.1, 0.1, 0.6]);
+    let v = mat2([-1.5, -0.4, -0.4, -1.2]);
+    let c = guess_hcore_with_threshold(&s, &t, &v, 1, 1, 1e-6).expect("should succeed");
+    assert_eq!((c.nrows(), c.ncols()), (2, 1));
+    assert_orthonormal(&c, &s, 1e-8);
+  }
+
+  /// Scenario: Electron count is checked against the kept functions.
+  #[test]
+  fn truncation_too_many_electrons_for_kept() {
+    let s = near_singular_overlap();
+    let t = Mat::<f64>::identity(2, 2);
+    let v = Mat::<f64>::zeros(2, 2);
+    assert_eq!(
+      guess_hcore_with_threshold(&s, &t, &v, 2, 0, 1e-6),
+      Err(GuessError::TooManyElectrons { n_alpha: 2, n_beta: 0, n_basis: 1 })
+    );
+  }
+
+  /// Scenario: A negative eigenvalue is still SingularOverlap.
+  #[test]
+  fn truncation_negative_eigenvalue_is_singular() {
+    let s = mat2([1.0, 0.0, 0.0, -1e-3]);
+    let t = Mat::<f64>::identity(2, 2);
+    let v = Mat::<f64>::zeros(2, 2);
+    assert_eq!(
+      guess_hcore_with_threshold(&s, &t, &v, 1, 1, 1e-6),
+      Err(GuessError::SingularOverlap)
+    );
+  }
+
+  /// Scenario: With nothing below the threshold the result matches guess_hcore.
+  #[test]
+  fn truncation_noop_matches_guess_hcore() {
+    let (s, t, v) = h2();
+    let a = guess_hcore_with_threshold(&s, &t, &v, 1, 1, 1e-6).unwrap();
+    let b = guess_hcore(&s, &t, &v, 1, 1).unwrap();
+    assert_mat_eq(&a, &b, 1e-14);
+  }
+
+  // ── GWH guess ──────────────────────────────────────────────────────────────
+
+  /// Scenario: The GWH Fock matrix keeps H_ii on the diagonal exactly.
+  #[test]
+  fn gwh_fock_diagonal_equals_h() {
+    let (s, t, v) = three_by_three();
+    let h: Mat<f64> = &t + &v;
+    let f = gwh_fock(&s, &h, 1.75);
+    for i in 0..3 {
+      assert_eq!(f[(i, i)], h[(i, i)]);
+    }
+  }
+
+  /// Scenario: Off-diagonal elements follow ½ K S_ij (H_ii + H_jj).
+  #[test]
+  fn gwh_fock_off_diagonal() {
+    let (s, t, v) = h2();
+    let h: Mat<f64> = &t + &v;
+    let f = gwh_fock(&s, &h, 1.75);
+    let expected = 0.5 * 1.75 * 0.5 * (h[(0, 0)] + h[(1, 1)]);
+    assert!((f[(0, 1)] - expected).abs() < 1e-14);
+    assert!((f[(1, 0)] - expected).abs() < 1e-14);
+  }
+
+  /// Scenario: The GWH coefficients are S-orthonormal.
+  #[test]
+  fn gwh_orthonormal() {
+    let (s, t, v) = h2();
+    let c = guess_gwh(&s, &t, &v, 1.75, 1, 1).expect("should succeed");
+    assert_eq!((c.nrows(), c.ncols()), (2, 2));
+    assert_orthonormal(&c, &s, 1e-10);
+  }
+
+  /// Scenario: With S = I and a diagonal H, GWH and Hcore give the same orbitals.
+  #[test]
+  fn gwh_diagonal_h_matches_hcore() {
+    let s = Mat::<f64>::identity(3, 3);
+    let t = mat3([1.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 3.0]);
+    let v = Mat::<f64>::zeros(3, 3);
+    let a = guess_gwh(&s, &t, &v, 1.75, 1, 1).unwrap();
+    let b = guess_hcore(&s, &t, &v, 1, 1).unwrap();
+    let abs = |m: &Mat<f64>| Mat::from_fn(3, 3, |i, j| m[(i, j)].abs());
+    assert_mat_eq(&abs(&a), &abs(&b), 1e-12);
+  }
+
+  /// Scenario: GWH reuses the dimension check.
+  #[test]
+  fn gwh_dimension_mismatch() {
+    let (s, t, _) = h2();
+    let v = Mat::<f64>::zeros(3, 3);
+    assert_eq!(
+      guess_gwh(&s, &t, &v, 1.75, 1, 1),
+      Err(GuessError::DimensionMismatch {
+        s_shape: (2, 2),
+        t_shape: (2, 2),
+        v_shape: (3, 3),
+      })
+    );
+  }
+
+  /// Scenario: GWH reuses the electron-count check.
+  #[test]
+  fn gwh_too_many_electrons() {
+    let (s, t, v) = h2();
+    assert_eq!(
+      guess_gwh(&s, &t, &v, 1.75, 3, 0),
+      Err(GuessError::TooManyElectrons { n_alpha: 3, n_beta: 0, n_basis: 2 })
+    );
+  }
+
+  /// Scenario: GWH rejects a singular S.
+  #[test]
+  fn gwh_singular_overlap() {
+    let s = mat2([1.0, 1.0, 1.0, 1.0]);
+    let t = Mat::<f64>::identity(2, 2);
+    let v = Mat::<f64>::zeros(2, 2);
+    assert_eq!(guess_gwh(&s, &t, &v, 1.75, 1, 1), Err(GuessError::SingularOverlap));
+  }
+
+  // ── Frontier orbitals ──────────────────────────────────────────────────────
+
+  /// Scenario: n_occ = 2 in a 4 × 4 C gives columns 1 and 2
+  #[test]
+  fn frontier_orbitals_columns() {
+    let c = mat4([
+      0.0, 1.0, 2.0, 3.0,
+      4.0, 5.0, 6.0, 7.0,
+      8.0, 9.0, 10.0, 11.0,
+      12.0, 13.0, 14.0, 15.0,
+    ]);
+    let (homo, lumo) = frontier_orbitals(&c, 2).unwrap();
+    assert_eq!(homo, vec![1.0, 5.0, 9.0, 13.0]);
+    assert_eq!(lumo, vec![2.0, 6.0, 10.0, 14.0]);
+  }
+
+  /// Scenario: No HOMO without electrons, no LUMO when every MO is occupied
+  #[test]
+  fn frontier_orbitals_missing() {
+    let c = Mat::<f64>::identity(4, 4);
+    assert_eq!(frontier_orbitals(&c, 0), None);
+    assert_eq!(frontier_orbitals(&c, 4), None);
+    assert_eq!(frontier_orbitals(&c, 5), None);
+    assert_eq!(frontier_orbitals(&Mat::zeros(0, 0), 0), None);
+  }
+
+  /// Scenario: Frontier orbitals of an H₂ guess straddle the energy gap
+  #[test]
+  fn frontier_orbitals_of_h2_guess() {
+    let (s, t, v) = h2();
+    let c = guess_hcore(&s, &t, &v, 1, 1).unwrap();
+    let (homo, lumo) = frontier_orbitals(&c, 1).unwrap();
+    // Bonding σ_g has equal-sign coefficients; antibonding σ_u opposite.
+    assert!(homo[0] * homo[1] > 0.0);
+    assert!(lumo[0] * lumo[1] < 0.0);
+  }
+
+  // ── Density matrices ───────────────────────────────────────────────────────
+
+  fn trace_ps(p: &Mat<f64>, s: &Mat<f64>) -> f64 {
+    let ps: Mat<f64> = p * s;
+    (0..ps.nrows()).map(|i| ps[(i, i)]).sum()
+  }
+
+  fn assert_symmetric(p: &Mat<f64>) {
+    assert_mat_eq(p, &p.transpose().to_owned(), 1e-14);
+  }
+
+  /// Scenario: The H₂ guess density holds one electron per spin
+  #[test]
+  fn density_of_h2_guess_has_one_electron() {
+    let (s, t, v) = h2();
+    let c = guess_hcore(&s, &t, &v, 1, 1).unwrap();
+    let p = density_matrix(&c, 1);
+    assert_eq!((p.nrows(), p.ncols()), (2, 2));
+    assert_symmetric(&p);
+    assert!((trace_ps(&p, &s) - 1.0).abs() < 1e-10, "Tr(PS) = {}", trace_ps(&p, &s));
+    // σ_g: both atoms equally weighted, P_11 = P_12 = 1 / (2 (1 + S_12)).
+    assert!((p[(0, 0)] - 1.0 / 3.0).abs() < 1e-10);
+    assert!((p[(0, 1)] - 1.0 / 3.0).abs() < 1e-10);
+  }
+
+  /// Scenario: Tr(P S) counts every occupied orbital
+  #[test]
+  fn density_trace_equals_n_occ() {
+    let (s, t, v) = three_by_three();
+    let c = guess_hcore(&s, &t, &v, 2, 2).unwrap();
+    for n_occ in 0..=3 {
+      let p = density_matrix(&c, n_occ);
+      assert_symmetric(&p);
+      assert!((trace_ps(&p, &s) - n_occ as f64).abs() < 1e-10, "n_occ = {}", n_occ);
+    }
+  }
+
+  /// Scenario: Alpha and beta densities follow their own occupations
+  #[test]
+  fn alpha_beta_densities_of_h2_cation() {
+    let (s, t, v) = h2();
+    let c = guess_hcore(&s, &t, &v, 1, 0).unwrap();
+    let (p_a, p_b) = alpha_beta_densities(&c, &c, 1, 0);
+    assert!((trace_ps(&p_a, &s) - 1.0).abs() < 1e-10);
+    assert_eq!((p_b.nrows(), p_b.ncols()), (2, 2));
+    assert!((0..2).all(|i| (0..2).all(|j| p_b[(i, j)] == 0.0)));
+  }
+
+  /// Scenario: More occupied orbitals than MOs is a caller bug
+  #[test]
+  #[should_panic(expected = "exceeds")]
+  fn density_too_many_occupied_panics() {
+    density_matrix(&Mat::<f64>::identity(2, 2), 3);
+  }
+
+  // ── SAD guess ──────────────────────────────────────────────────────────────
+
+  // An AoBasis with s functions on the atoms listed in `atom_index`; only the
+  // atom bookkeeping matters to `sad_density`.
+  fn ao_basis(atom_index: Vec<usize>) -> AoBasis {
+    let n = atom_index.len();
+    AoBasis {
+      n_basis: n,
+      n_shells: n,
+      center_x: vec![0.0; n],
+      center_y: vec![0.0; n],
+      center_z: vec![0.0; n],
+      lx: vec![0; n],
+      ly: vec![0; n],
+      lz: vec![0; n],
+      shell_index: (0..n).collect(),
+      atom_index,
+      prim_offset: (0..n).collect(),
+      n_primitives: vec![1; n],
+      exponents: vec![1.0; n],
+      coefficients: vec![1.0; n],
+      norms_folded: false,
+    }
+  }
+
+  fn geometry(symbols: &[&str]) -> CartesianGeometry {
+    let n = symbols.len();
+    CartesianGeometry {
+      symbols: symbols.iter().map(|s| s.to_string()).collect(),
+      labels: symbols.iter().map(|s| s.to_string()).collect(),
+      x: vec![0.0; n],
+      y: vec![0.0; n],
+      z: (0..n).map(|i| 1.4 * i as f64).collect(),
+      is_ghost: vec![false; n],
+    }
+  }
+
+  // Two functions holding two electrons on He, one holding one on H.
+  fn atomic_block(symbol: &str) -> Mat<f64> {
+    match symbol {
+      "He" => mat2([1.5, 0.2, 0.2, 0.5]),
+      "H" => Mat::from_fn(1, 1, |_, _| 1.0),
+      other => panic!("no atomic density for {other}"),
+    }
+  }
+
+  /// Scenario: HeH⁺-like pair gives atomic blocks on the diagonal, zeros between
+  #[test]
+  fn sad_density_is_block_diagonal() {
+    let basis = ao_basis(vec![0, 0, 1]);
+    let p = sad_density(&basis, &geometry(&["He", "H"]), atomic_block).unwrap();
+    let expected = mat3([
+      1.5, 0.2, 0.0,
+      0.2, 0.5, 0.0,
+      0.0, 0.0, 1.0,
+    ]);
+    assert_mat_eq(&p, &expected, 0.0);
+    // With S = I, Tr(P S) is the summed atomic electron counts, 2 + 1.
+    assert!((trace_ps(&p, &Mat::identity(3, 3)) - 3.0).abs() < 1e-14);
+  }
+
+  /// Scenario: A ghost atom's functions get no density
+  #[test]
+  fn sad_density_skips_ghost_atoms() {
+    let basis = ao_basis(vec![0, 0, 1]);
+    let mut geom = geometry(&["He", "H"]);
+    geom.is_ghost[1] = true;
+    let p = sad_density(&basis, &geom, atomic_block).unwrap();
+    assert_eq!(p[(2, 2)], 0.0);
+    assert!((trace_ps(&p, &Mat::identity(3, 3)) - 2.0).abs() < 1e-14);
+  }
+
+  /// Scenario: An atomic block of the wrong size is rejected
+  #[test]
+  fn sad_density_block_size_mismatch() {
+    let basis = ao_basis(vec![0, 1, 1]);
+    assert_eq!(
+      sad_density(&basis, &geometry(&["He", "H"]), atomic_block).unwrap_err(),
+      GuessError::AtomicDensityMismatch { atom: 0, expected: 1, got: (2, 2) }
+    );
+  }
 }
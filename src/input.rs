@@ -1,21 +1,29 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 
+use crate::elements::{atomic_number, ELEMENTS};
+
 const ANGSTROM_TO_BOHR: f64 = 1.8897259886;
 
-/// All 118 known element symbols in title case, indexed by atomic number (1-based).
-const ELEMENTS: &[&str] = &[
-  "H",  "He", "Li", "Be", "B",  "C",  "N",  "O",  "F",  "Ne",
-  "Na", "Mg", "Al", "Si", "P",  "S",  "Cl", "Ar", "K",  "Ca",
-  "Sc", "Ti", "V",  "Cr", "Mn", "Fe", "Co", "Ni", "Cu", "Zn",
-  "Ga", "Ge", "As", "Se", "Br", "Kr", "Rb", "Sr", "Y",  "Zr",
-  "Nb", "Mo", "Tc", "Ru", "Rh", "Pd", "Ag", "Cd", "In", "Sn",
-  "Sb", "Te", "I",  "Xe", "Cs", "Ba", "La", "Ce", "Pr", "Nd",
-  "Pm", "Sm", "Eu", "Gd", "Tb", "Dy", "Ho", "Er", "Tm", "Yb",
-  "Lu", "Hf", "Ta", "W",  "Re", "Os", "Ir", "Pt", "Au", "Hg",
-  "Tl", "Pb", "Bi", "Po", "At", "Rn", "Fr", "Ra", "Ac", "Th",
-  "Pa", "U",  "Np", "Pu", "Am", "Cm", "Bk", "Cf", "Es", "Fm",
-  "Md", "No", "Lr", "Rf", "Db", "Sg", "Bh", "Hs", "Mt", "Ds",
-  "Rg", "Cn", "Nh", "Fl", "Mc", "Lv", "Ts", "Og",
+/// Inline atoms closer than this (Bohr) are rejected as a paste error.
+pub const COINCIDENT_ATOM_THRESHOLD: f64 = 1e-3;
+
+/// Standard atomic weights in amu, indexed like [`ELEMENTS`]. Elements without
+/// a stable isotope use the mass number of their longest-lived isotope.
+const ATOMIC_MASSES: &[f64] = &[
+  1.008,   4.0026,  6.94,    9.0122,  10.81,   12.011,  14.007,  15.999,  18.998,  20.180,
+  22.990,  24.305,  26.982,  28.085,  30.974,  32.06,   35.45,   39.948,  39.098,  40.078,
+  44.956,  47.867,  50.942,  51.996,  54.938,  55.845,  58.933,  58.693,  63.546,  65.38,
+  69.723,  72.630,  74.922,  78.971,  79.904,  83.798,  85.468,  87.62,   88.906,  91.224,
+  92.906,  95.95,   97.0,    101.07,  102.91,  106.42,  107.87,  112.41,  114.82,  118.71,
+  121.76,  127.60,  126.90,  131.29,  132.91,  137.33,  138.91,  140.12,  140.91,  144.24,
+  145.0,   150.36,  151.96,  157.25,  158.93,  162.50,  164.93,  167.26,  168.93,  173.05,
+  174.97,  178.49,  180.95,  183.84,  186.21,  190.23,  192.22,  195.08,  196.97,  200.59,
+  204.38,  207.2,   208.98,  209.0,   210.0,   222.0,   223.0,   226.0,   227.0,   232.04,
+  231.04,  238.03,  237.0,   244.0,   243.0,   247.0,   247.0,   251.0,   252.0,   257.0,
+  258.0,   259.0,   266.0,   267.0,   268.0,   269.0,   270.0,   269.0,   278.0,   281.0,
+  282.0,   285.0,   286.0,   289.0,   290.0,   293.0,   294.0,   294.0,
 ];
 
 // ── Error type ────────────────────────────────────────────────────────────────
@@ -26,10 +34,22 @@ pub enum InputError {
   InvalidYaml(String),
   MissingField(String),
   InvalidValue { field: String, reason: String },
-  AmbiguousGeometry,
+  /// More than one geometry source was given; lists them in input-key order.
+  AmbiguousGeometry { sources: Vec<String> },
   CoordinateMismatch { n_symbols: usize, n_coords: usize },
+  /// `keywords.velocities` does not hold 3 numbers per atom.
+  VelocityMismatch { n_atoms: usize, n_velocities: usize },
+  /// `molecule.lattice` does not hold exactly 9 numbers.
+  LatticeMismatch { n_values: usize },
   InvalidElement(String),
   InvalidZMatrix { row: usize, reason: String },
+  /// Atoms `i` < `j` (1-based) lie closer than [`COINCIDENT_ATOM_THRESHOLD`];
+  /// `distance` is in Bohr.
+  CoincidentAtoms { i: usize, j: usize, distance: f64 },
+  /// `line` is 1-based.
+  InvalidXyz { line: usize, reason: String },
+  /// `line` is 1-based.
+  PdbParse { line: usize, reason: String },
   UnknownField(String),
 }
 
@@ -44,15 +64,27 @@ impl std::fmt::Display for InputError {
         write!(f, "missing required field: {}", s),
       InputError::InvalidValue { field, reason } =>
         write!(f, "invalid value for {}: {}", field, reason),
-      InputError::AmbiguousGeometry =>
-        write!(f, "molecule block contains both Cartesian and Z-matrix keys"),
+      InputError::AmbiguousGeometry { sources } =>
+        write!(f, "molecule block specifies more than one geometry source: {}",
+          sources.join(", ")),
       InputError::CoordinateMismatch { n_symbols, n_coords } =>
         write!(f, "geometry has {} coordinates but expected {} (3 × {})",
           n_coords, 3 * n_symbols, n_symbols),
+      InputError::VelocityMismatch { n_atoms, n_velocities } =>
+        write!(f, "keywords.velocities has {} components but expected {} (3 × {})",
+          n_velocities, 3 * n_atoms, n_atoms),
+      InputError::LatticeMismatch { n_values } =>
+        write!(f, "molecule.lattice has {} values but expected 9 (3 vectors × 3)", n_values),
       InputError::InvalidElement(s) =>
         write!(f, "unknown element symbol: {:?}", s),
       InputError::InvalidZMatrix { row, reason } =>
         write!(f, "invalid z_matrix row {}: {}", row, reason),
+      InputError::CoincidentAtoms { i, j, distance } =>
+        write!(f, "atoms {} and {} are only {:.2e} Bohr apart", i, j, distance),
+      InputError::InvalidXyz { line, reason } =>
+        write!(f, "invalid XYZ data at line {}: {}", line, reason),
+      InputError::PdbParse { line, reason } =>
+        write!(f, "invalid PDB data at line {}: {}", line, reason),
       InputError::UnknownField(s) =>
         write!(f, "unknown top-level field: {:?}", s),
     }
@@ -61,40 +93,103 @@ impl std::fmt::Display for InputError {
 
 // ── Public types ──────────────────────────────────────────────────────────────
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Driver {
   Energy,
   Gradient,
   Hessian,
   Md,
+  Frequency,
+  Optimize,
+  /// Post-processing of `molecule.trajectory_file`; runs no electronic structure.
+  Analysis,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Molecule {
   pub geometry: Geometry,
   pub charge: i32,
   pub multiplicity: u32,
+  /// Explicit electron count; overrides Σ Z − charge when present.
+  pub n_electrons: Option<usize>,
+  /// Explicit `(molecule.n_alpha, molecule.n_beta)`, e.g. for a
+  /// broken-symmetry guess; overrides the counts derived from `multiplicity`.
+  pub n_alpha_beta: Option<(usize, usize)>,
+  /// Unit-cell vectors `[a, b, c]` in Bohr for periodic inputs
+  /// (`molecule.lattice`); `None` for an isolated molecule.
+  pub lattice: Option<[[f64; 3]; 3]>,
+  /// Frames of `molecule.trajectory_file` in file order; `geometry` holds the
+  /// first. `None` unless the driver is `analysis`.
+  pub trajectory: Option<Vec<CartesianGeometry>>,
+  /// Per-atom masses in amu (`molecule.masses`), e.g. for isotope
+  /// substitution; `None` means standard atomic masses.
+  pub masses: Option<Vec<f64>>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Geometry {
   Cartesian(CartesianGeometry),
   ZMatrix(ZMatrixGeometry),
 }
 
+/// An external point charge for QM/MM embedding. It carries no basis
+/// functions; it only interacts electrostatically.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PointCharge {
+  /// Charge in units of e.
+  pub charge: f64,
+  /// Position in Bohr.
+  pub position: [f64; 3],
+}
+
 /// Structure of arrays; all coordinate vectors have the same length as `symbols`.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CartesianGeometry {
   pub symbols: Vec<String>,
+  /// Atom labels as written in the input (e.g. `C1`); equal to the symbol
+  /// when the input carried no label.
+  pub labels: Vec<String>,
   pub x: Vec<f64>,
   pub y: Vec<f64>,
   pub z: Vec<f64>,
+  /// True for ghost centers (`Gh(X)` or `@X` in the input): they carry the
+  /// basis functions of element X but no nuclear charge or electrons.
+  pub is_ghost: Vec<bool>,
+}
+
+impl CartesianGeometry {
+  /// Distinct element symbols in order of first occurrence.
+  pub fn unique_elements(&self) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::new();
+    for s in &self.symbols {
+      if !unique.contains(s) {
+        unique.push(s.clone());
+      }
+    }
+    unique
+  }
+
+  /// Nuclear charge of every atom, in order: the atomic number, or 0 for a
+  /// ghost center. Computed on each call since the fields are public and may
+  /// change.
+  ///
+  /// Panics on a symbol outside the element table; parsing rejects those.
+  pub fn atomic_numbers(&self) -> Vec<u32> {
+    self
+      .symbols
+      .iter()
+      .enumerate()
+      .map(|(i, s)| if self.is_ghost[i] { 0 } else { atomic_number(s).expect("validated element symbol") })
+      .collect()
+  }
 }
 
 /// Structure of arrays; all vectors have the same length (number of atoms).
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ZMatrixGeometry {
   pub symbols: Vec<String>,
+  /// Atom labels as written in the input; see `CartesianGeometry::labels`.
+  pub labels: Vec<String>,
   /// 1-based reference indices; `None` for row 0.
   pub bond_atoms: Vec<Option<usize>>,
   /// Bond lengths in Bohr; `None` for row 0.
@@ -107,35 +202,146 @@ pub struct ZMatrixGeometry {
   pub dihedral_atoms: Vec<Option<usize>>,
   /// Dihedral angles in degrees; `None` for rows 0–2.
   pub dihedrals_deg: Vec<Option<f64>>,
+  /// True for dummy rows (`X`/`Xx`, symbol `X`): placement references that
+  /// carry no nucleus and are dropped by [`zmatrix_to_cartesian`].
+  pub is_dummy: Vec<bool>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Model {
+  /// The method exactly as written in the input, e.g. `b3lyp-d3/def2-svp`.
   pub method: String,
+  /// `method` split into its components.
+  pub method_spec: MethodSpec,
   pub basis: String,
+  /// Local QCSchema JSON or Gaussian94 basis file (`model.basis_file`);
+  /// elements it contains are read from it instead of being fetched as `basis`.
+  pub basis_file: Option<String>,
+  /// Per-element basis names from `model.basis_overrides`, keyed by title-case
+  /// symbol; elements not listed use `basis`. Empty when absent.
+  pub basis_overrides: BTreeMap<String, String>,
+  pub reference: Reference,
 }
 
-#[derive(Debug, PartialEq)]
+/// SCF spin treatment.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Reference {
+  Rhf,
+  Uhf,
+  Rohf,
+}
+
+/// Structured form of a composite method string `base[-dispersion][/basis]`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MethodSpec {
+  pub base: String,
+  /// Recognised dispersion suffix (e.g. `d3`), if any.
+  pub dispersion: Option<String>,
+  /// Basis named after a `/` in the method string, if any.
+  pub embedded_basis: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MdKeywords {
   pub timestep_fs: f64,
   pub n_steps: usize,
   pub temperature_k: f64,
   pub thermostat: Thermostat,
+  /// Initial velocities for a restart, flat [vx0, vy0, vz0, vx1, …] in
+  /// Bohr/fs (converted from the molecule's length units per fs). `None`
+  /// means velocities are sampled.
+  pub velocities: Option<Vec<f64>>,
 }
 
-#[derive(Debug, PartialEq)]
+/// SCF controls for the non-MD drivers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScfKeywords {
+  pub max_iterations: usize,
+  /// Schwarz screening threshold: shell quartets whose bound falls below it
+  /// are skipped. Always in (0, 1).
+  pub integral_threshold: f64,
+  /// Convergence threshold on the energy change between iterations (Hartree).
+  pub energy_tol: f64,
+  /// Convergence threshold on the RMS density-matrix change.
+  pub density_tol: f64,
+  /// Whether to extrapolate the Fock matrix with DIIS.
+  pub diis: bool,
+}
+
+/// Geometry optimization controls for the optimize driver.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OptKeywords {
+  pub max_steps: usize,
+  /// Convergence threshold on the largest gradient component (Hartree/Bohr).
+  pub gradient_threshold: f64,
+  /// Convergence threshold on the largest step component (Bohr).
+  pub step_threshold: f64,
+}
+
+/// Thermochemistry conditions for the frequency driver.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FreqKeywords {
+  pub temperature_k: f64,
+  pub pressure_atm: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Thermostat {
   None,
   VelocityRescaling,
+  /// Nosé–Hoover thermostat with relaxation time `tau_fs` (> 0).
+  NoseHoover { tau_fs: f64 },
 }
 
-#[derive(Debug, PartialEq)]
+/// Coarse compute-cost class of a parsed input, for scheduling and display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CostTier {
+  Small,
+  Medium,
+  Large,
+}
+
+/// Parser behaviour switches; `ParseOptions::default()` is the strict behaviour
+/// of [`parse_input_str`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParseOptions {
+  /// Wrap out-of-range Z-matrix dihedrals into [−180°, 180°] instead of
+  /// rejecting them.
+  pub wrap_dihedrals: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SimulationInput {
   pub molecule: Molecule,
   pub model: Model,
-  pub driver: Driver,
-  /// `Some` when `driver` is `Md`; `None` otherwise.
+  /// Drivers to run, in order. A single `driver: name` gives one entry; a
+  /// sequence such as `driver: [optimize, frequency]` gives a workflow.
+  pub drivers: Vec<Driver>,
+  /// `Some` when `drivers` includes `Md`; `None` otherwise.
   pub keywords: Option<MdKeywords>,
+  /// `Some` when `drivers` includes anything other than `Md` or `Analysis`;
+  /// `None` otherwise.
+  pub scf: Option<ScfKeywords>,
+  /// `Some` when `drivers` includes `Frequency`; `None` otherwise.
+  pub freq: Option<FreqKeywords>,
+  /// `Some` when `drivers` includes `Optimize`; `None` otherwise.
+  pub opt: Option<OptKeywords>,
+  /// Top-level `point_charges` embedding the molecule; empty when absent.
+  pub point_charges: Vec<PointCharge>,
+  /// Top-level `metadata` mapping, carried through unchanged for tools that
+  /// annotate their exports; never read by the parser.
+  pub metadata: Option<serde_yaml::Value>,
+  /// Top-level free-text `comment`.
+  pub comment: Option<String>,
+}
+
+impl SimulationInput {
+  /// `(n_alpha, n_beta)` for the molecule, as [`occupation_from_input`]:
+  /// n_alpha + n_beta is the electron count and n_alpha − n_beta is
+  /// `multiplicity − 1`.
+  pub fn electron_counts(&self) -> Result<(usize, usize), InputError> {
+    occupation_from_input(&self.molecule)
+  }
 }
 
 // ── Public functions ──────────────────────────────────────────────────────────
@@ -149,42 +355,847 @@ pub fn parse_input(path: &Path) -> Result<SimulationInput, InputError> {
 
 /// Parses and fully validates a YAML simulation input string.
 pub fn parse_input_str(yaml: &str) -> Result<SimulationInput, InputError> {
+  parse_input_str_with_warnings(yaml).map(|(input, _)| input)
+}
+
+/// Like [`parse_input_str`], but also returns advisory messages for input that
+/// parsed successfully but deserves attention (e.g. deprecated keyword names).
+pub fn parse_input_str_with_warnings(
+  yaml: &str,
+) -> Result<(SimulationInput, Vec<String>), InputError> {
+  let mut warnings = Vec::new();
+  let input = parse_input_impl(yaml, &ParseOptions::default(), &mut warnings)?;
+  Ok((input, warnings))
+}
+
+/// Like [`parse_input_str`], with parser behaviour controlled by `options`.
+pub fn parse_input_str_with_options(
+  yaml: &str,
+  options: &ParseOptions,
+) -> Result<SimulationInput, InputError> {
+  parse_input_impl(yaml, options, &mut Vec::new())
+}
+
+/// Checks a YAML simulation input string and returns every independent
+/// validation failure found, or an empty list when [`parse_input_str`] would
+/// succeed. Unknown top-level fields, drivers, the molecule (each malformed
+/// z-matrix row separately) and the model are checked independently; keyword
+/// blocks are only checked once drivers and molecule are valid. A YAML syntax
+/// error or non-mapping document is reported alone.
+pub fn validate_input_str(yaml: &str) -> Vec<InputError> {
+  let options = ParseOptions::default();
+  let mapping = match top_level_mapping(yaml) {
+    Ok(m) => m,
+    Err(e) => return vec![e],
+  };
+
+  let mut errors = unknown_top_level_fields(&mapping);
+  let drivers = parse_drivers(&mapping).map_err(|e| errors.push(e)).ok();
+  let molecule = match parse_molecule(&mapping, &options) {
+    Ok(molecule) => Some(molecule),
+    Err(e) => {
+      let rows = zmatrix_row_errors(&mapping, &options);
+      if !rows.contains(&e) {
+        errors.push(e);
+      }
+      errors.extend(rows);
+      Option::None
+    }
+  };
+  if let Err(e) = parse_model(&mapping, molecule.as_ref()) {
+    errors.push(e);
+  }
+  if let Err(e) = parse_annotations(&mapping) {
+    errors.push(e);
+  }
+  if let Err(e) = parse_point_charges(&mapping) {
+    errors.push(e);
+  }
+  if let (Some(drivers), Some(molecule)) = (&drivers, &molecule) {
+    if let Err(e) = check_analysis_driver(drivers, molecule) {
+      errors.push(e);
+    }
+    if let Err(e) = parse_keyword_blocks(&mapping, drivers, molecule, &mut Vec::new()) {
+      errors.push(e);
+    }
+  }
+  errors
+}
+
+fn parse_input_impl(
+  yaml: &str,
+  options: &ParseOptions,
+  warnings: &mut Vec<String>,
+) -> Result<SimulationInput, InputError> {
+  let mapping = &top_level_mapping(yaml)?;
+  if let Some(e) = unknown_top_level_fields(mapping).into_iter().next() {
+    return Err(e);
+  }
+
+  let drivers = parse_drivers(mapping)?;
+  let molecule = parse_molecule(mapping, options)?;
+  check_analysis_driver(&drivers, &molecule)?;
+  let model = parse_model(mapping, Some(&molecule))?;
+  let KeywordBlocks { keywords, scf, freq, opt } =
+    parse_keyword_blocks(mapping, &drivers, &molecule, warnings)?;
+
+  let point_charges = parse_point_charges(mapping)?;
+  let (metadata, comment) = parse_annotations(mapping)?;
+
+  Ok(SimulationInput {
+    molecule,
+    model,
+    drivers,
+    keywords,
+    scf,
+    freq,
+    opt,
+    point_charges,
+    metadata,
+    comment,
+  })
+}
+
+/// Parses `yaml` and returns its top-level mapping.
+fn top_level_mapping(yaml: &str) -> Result<serde_yaml::Mapping, InputError> {
   let value: serde_yaml::Value = serde_yaml::from_str(yaml)
     .map_err(|e| InputError::InvalidYaml(e.to_string()))?;
-
-  let mapping = match &value {
-    serde_yaml::Value::Mapping(m) => m,
-    _ => return Err(InputError::InvalidYaml(
+  match value {
+    serde_yaml::Value::Mapping(m) => Ok(m),
+    _ => Err(InputError::InvalidYaml(
       "expected a mapping at top level".to_string()
     )),
-  };
+  }
+}
+
+/// One `UnknownField` per top-level key outside the known set, in input order.
+fn unknown_top_level_fields(mapping: &serde_yaml::Mapping) -> Vec<InputError> {
+  const KNOWN: &[&str] =
+    &["driver", "molecule", "model", "keywords", "point_charges", "metadata", "comment"];
+  mapping
+    .iter()
+    .map(|(key, _)| key.as_str().unwrap_or(""))
+    .filter(|k| !KNOWN.contains(k))
+    .map(|k| InputError::UnknownField(k.to_string()))
+    .collect()
+}
 
-  // Reject unknown top-level keys.
-  const KNOWN: &[&str] = &["driver", "molecule", "model", "keywords"];
-  for (key, _) in mapping.iter() {
-    let k = key.as_str().unwrap_or("");
-    if !KNOWN.contains(&k) {
-      return Err(InputError::UnknownField(k.to_string()));
+/// A trajectory and the `analysis` driver require each other, and `analysis`
+/// cannot be combined with drivers that need a single geometry.
+fn check_analysis_driver(drivers: &[Driver], molecule: &Molecule) -> Result<(), InputError> {
+  let analysis = drivers.contains(&Driver::Analysis);
+  if molecule.trajectory.is_none() {
+    if analysis {
+      return Err(InputError::MissingField("molecule.trajectory_file".to_string()));
     }
+    return Ok(());
+  }
+  if !drivers.iter().all(|d| *d == Driver::Analysis) {
+    return Err(InputError::InvalidValue {
+      field: "driver".to_string(),
+      reason: "molecule.trajectory_file requires driver: analysis".to_string(),
+    });
+  }
+  Ok(())
+}
+
+/// Reads the optional top-level `point_charges`: a sequence of
+/// `{charge, position: [x, y, z]}` with positions in the molecule's units.
+fn parse_point_charges(mapping: &serde_yaml::Mapping) -> Result<Vec<PointCharge>, InputError> {
+  let Some(v) = map_get(mapping, "point_charges") else {
+    return Ok(Vec::new());
+  };
+  let invalid = |reason: String| InputError::InvalidValue {
+    field: "point_charges".to_string(),
+    reason,
+  };
+  let seq = v.as_sequence()
+    .ok_or_else(|| invalid("expected a sequence of {charge, position} entries".to_string()))?;
+  // Positions share the molecule's declared length units.
+  let factor = match map_get(mapping, "molecule").and_then(|v| v.as_mapping()) {
+    Some(mol_map) => parse_units(mol_map)?,
+    Option::None => ANGSTROM_TO_BOHR,
+  };
+  let mut charges = Vec::with_capacity(seq.len());
+  for (i, entry) in seq.iter().enumerate() {
+    let n = i + 1;
+    let entry = entry.as_mapping()
+      .ok_or_else(|| invalid(format!("point charge {} must be a mapping", n)))?;
+    let charge = map_get(entry, "charge")
+      .and_then(yaml_f64)
+      .ok_or_else(|| invalid(format!("point charge {} needs a numeric charge", n)))?;
+    let position = map_get(entry, "position")
+      .and_then(|p| p.as_sequence())
+      .filter(|p| p.len() == 3)
+      .and_then(|p| p.iter().map(yaml_f64).collect::<Option<Vec<f64>>>())
+      .ok_or_else(|| invalid(format!("point charge {} needs a position of 3 numbers", n)))?;
+    charges.push(PointCharge {
+      charge,
+      position: [position[0] * factor, position[1] * factor, position[2] * factor],
+    });
   }
+  Ok(charges)
+}
 
-  let driver = parse_driver(mapping)?;
-  let molecule = parse_molecule(mapping)?;
-  let model = parse_model(mapping)?;
+/// Reads the optional top-level `metadata` (a mapping) and `comment` (a string).
+fn parse_annotations(
+  mapping: &serde_yaml::Mapping,
+) -> Result<(Option<serde_yaml::Value>, Option<String>), InputError> {
+  let metadata = match map_get(mapping, "metadata") {
+    Some(v) if v.is_mapping() => Some(v.clone()),
+    Some(_) => return Err(InputError::InvalidValue {
+      field: "metadata".to_string(),
+      reason: "expected a mapping".to_string(),
+    }),
+    Option::None => Option::None,
+  };
+  let comment = match map_get(mapping, "comment") {
+    Some(v) => Some(v.as_str().map(str::to_string).ok_or_else(|| InputError::InvalidValue {
+      field: "comment".to_string(),
+      reason: "expected a string".to_string(),
+    })?),
+    Option::None => Option::None,
+  };
+  Ok((metadata, comment))
+}
+
+/// The keyword blocks of a `SimulationInput`, each `Some` as documented there.
+struct KeywordBlocks {
+  keywords: Option<MdKeywords>,
+  scf: Option<ScfKeywords>,
+  freq: Option<FreqKeywords>,
+  opt: Option<OptKeywords>,
+}
+
+/// Parses the keyword blocks that `drivers` need.
+fn parse_keyword_blocks(
+  mapping: &serde_yaml::Mapping,
+  drivers: &[Driver],
+  molecule: &Molecule,
+  warnings: &mut Vec<String>,
+) -> Result<KeywordBlocks, InputError> {
+  // Deprecated names and aliases are resolved once for every keyword consumer.
+  let kw_map = match map_get(mapping, "keywords") {
+    Some(v) => Some(resolve_keyword_names(
+      v.as_mapping().ok_or_else(|| InputError::InvalidValue {
+        field: "keywords".to_string(),
+        reason: "expected a mapping".to_string(),
+      })?,
+      warnings,
+    )?),
+    Option::None => Option::None,
+  };
 
-  let keywords = if driver == Driver::Md {
-    let kw_val = map_get(mapping, "keywords")
+  let keywords = if drivers.contains(&Driver::Md) {
+    let kw_map = kw_map.as_ref()
       .ok_or_else(|| InputError::MissingField("keywords".to_string()))?;
-    Some(parse_keywords(kw_val)?)
+    // Velocities share the molecule's declared length units.
+    let units_factor = match map_get(mapping, "molecule").and_then(|v| v.as_mapping()) {
+      Some(mol_map) => parse_units(mol_map)?,
+      Option::None => ANGSTROM_TO_BOHR,
+    };
+    let n_atoms = molecule_symbols(molecule).len();
+    let md = parse_keywords(kw_map, n_atoms, units_factor)?;
+    if md.thermostat == Thermostat::VelocityRescaling && md.temperature_k == 0.0 {
+      warnings.push(
+        "temperature_k is 0 with a velocity_rescaling thermostat — no rescaling will occur"
+          .to_string(),
+      );
+    }
+    Some(md)
+  } else {
+    Option::None
+  };
+
+  let scf = if drivers.iter().all(|d| matches!(d, Driver::Md | Driver::Analysis)) {
+    Option::None
+  } else {
+    Some(parse_scf_keywords(kw_map.as_ref())?)
+  };
+
+  let freq = if drivers.contains(&Driver::Frequency) {
+    Some(parse_freq_keywords(kw_map.as_ref())?)
+  } else {
+    Option::None
+  };
+
+  let opt = if drivers.contains(&Driver::Optimize) {
+    Some(parse_opt_keywords(kw_map.as_ref())?)
   } else {
     Option::None
   };
 
-  Ok(SimulationInput { molecule, model, driver, keywords })
+  Ok(KeywordBlocks { keywords, scf, freq, opt })
+}
+
+/// Parses standard XYZ content (atom count, comment line, `symbol x y z` rows).
+///
+/// Coordinates are multiplied by `factor` (raw → Bohr), unless the comment line
+/// carries a `units=bohr` or `units=angstrom` token, which takes precedence for
+/// this file. Unrecognised `units=` values are ignored.
+pub fn parse_xyz_str(content: &str, factor: f64) -> Result<CartesianGeometry, InputError> {
+  let mut lines = content.lines();
+
+  let count_line = lines.next()
+    .ok_or_else(|| ixyz(1, "missing atom count line"))?;
+  let n_atoms: usize = count_line.trim().parse()
+    .map_err(|_| ixyz(1, &format!("invalid atom count {:?}", count_line.trim())))?;
+
+  let comment = lines.next()
+    .ok_or_else(|| ixyz(2, "missing comment line"))?;
+  let factor = xyz_comment_units(comment).unwrap_or(factor);
+
+  let mut symbols = Vec::with_capacity(n_atoms);
+  let mut labels = Vec::with_capacity(n_atoms);
+  let mut x = Vec::with_capacity(n_atoms);
+  let mut y = Vec::with_capacity(n_atoms);
+  let mut z = Vec::with_capacity(n_atoms);
+
+  for (i, line) in lines.enumerate() {
+    let line_no = i + 3;
+    if line.trim().is_empty() {
+      continue;
+    }
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 4 {
+      return Err(ixyz(line_no, "expected `symbol x y z`"));
+    }
+    symbols.push(normalize_element(fields[0])?);
+    labels.push(fields[0].to_string());
+    let mut coords = [0.0; 3];
+    for (c, f) in coords.iter_mut().zip(&fields[1..4]) {
+      *c = f.parse::<f64>()
+        .map_err(|_| ixyz(line_no, &format!("invalid coordinate {:?}", f)))?
+        * factor;
+    }
+    x.push(coords[0]);
+    y.push(coords[1]);
+    z.push(coords[2]);
+  }
+
+  if symbols.len() != n_atoms {
+    return Err(ixyz(1, &format!(
+      "header declares {} atoms but {} were found", n_atoms, symbols.len()
+    )));
+  }
+
+  let is_ghost = ghost_flags(&labels);
+  Ok(CartesianGeometry { symbols, labels, x, y, z, is_ghost })
+}
+
+/// Parses a multi-frame XYZ trajectory: consecutive XYZ blocks, each read as
+/// by [`parse_xyz_str`], optionally separated by blank lines.
+///
+/// Every frame must list the same symbols in the same order as the first.
+/// `InvalidXyz` line numbers count from the start of the whole file.
+pub fn parse_trajectory_str(
+  content: &str,
+  factor: f64,
+) -> Result<Vec<CartesianGeometry>, InputError> {
+  let lines: Vec<&str> = content.lines().collect();
+  let mut frames: Vec<CartesianGeometry> = Vec::new();
+  let mut start = 0;
+  while start < lines.len() {
+    let count_line = lines[start].trim();
+    if count_line.is_empty() {
+      start += 1;
+      continue;
+    }
+    let n_atoms: usize = count_line.parse()
+      .map_err(|_| ixyz(start + 1, &format!("invalid atom count {:?}", count_line)))?;
+    let end = (start + n_atoms + 2).min(lines.len());
+    let frame = parse_xyz_str(&lines[start..end].join("\n"), factor).map_err(|e| match e {
+      InputError::InvalidXyz { line, reason } => InputError::InvalidXyz { line: line + start, reason },
+      other => other,
+    })?;
+    if let Some(first) = frames.first() {
+      if frame.symbols.len() != first.symbols.len() {
+        return Err(ixyz(start + 1, &format!(
+          "frame {} has {} atoms but frame 1 has {}",
+          frames.len() + 1, frame.symbols.len(), first.symbols.len()
+        )));
+      }
+      if frame.symbols != first.symbols {
+        return Err(ixyz(start + 1, &format!(
+          "frame {} lists different elements than frame 1", frames.len() + 1
+        )));
+      }
+    }
+    frames.push(frame);
+    start = end;
+  }
+  if frames.is_empty() {
+    return Err(ixyz(1, "trajectory contains no frames"));
+  }
+  Ok(frames)
+}
+
+/// Converts a Z-matrix into Cartesian coordinates (Bohr).
+///
+/// Atom 0 sits at the origin, atom 1 on the +z axis and atom 2 in the xz-plane
+/// (positive x); every later atom is placed from its bond/angle/dihedral
+/// references by the natural extension reference frame (NeRF) construction.
+/// Dummy rows are placed like any other, so later rows may reference them,
+/// but are left out of the result.
+///
+/// Errors with `InvalidZMatrix` for a row that lacks one of its values or
+/// references, refers to itself or a later row, or whose dihedral, angle and
+/// bond reference atoms are (nearly) collinear, since the dihedral is then
+/// undefined.
+pub fn zmatrix_to_cartesian(zmat: &ZMatrixGeometry) -> Result<CartesianGeometry, InputError> {
+  let n = zmat.symbols.len();
+  let mut pos: Vec<[f64; 3]> = Vec::with_capacity(n);
+
+  for i in 0..n {
+    let p = match i {
+      0 => [0.0, 0.0, 0.0],
+      1 => [0.0, 0.0, zmat_value(zmat.bond_lengths_bohr[1], 1, "bond_length")?],
+      _ => {
+        let r = zmat_value(zmat.bond_lengths_bohr[i], i, "bond_length")?;
+        let theta = zmat_value(zmat.angles_deg[i], i, "angle")?.to_radians();
+        let c = zmat_reference(&pos, zmat.bond_atoms[i], i, "bond_atom")?;
+        let b = zmat_reference(&pos, zmat.angle_atoms[i], i, "angle_atom")?;
+        if i == 2 {
+          // Both references lie on the z axis; bend towards +x.
+          let u = unit(sub(b, c));
+          [
+            c[0] + r * (theta.cos() * u[0] + theta.sin()),
+            c[1] + r * theta.cos() * u[1],
+            c[2] + r * theta.cos() * u[2],
+          ]
+        } else {
+          let phi = zmat_value(zmat.dihedrals_deg[i], i, "dihedral")?.to_radians();
+          let a = zmat_reference(&pos, zmat.dihedral_atoms[i], i, "dihedral_atom")?;
+          let ab = sub(b, a);
+          let normal = cross(ab, sub(c, b));
+          // |ab × bc| = |ab| |bc| sin(angle); below 1e-6 the plane is lost.
+          if norm(normal) <= 1e-6 * norm(ab) * norm(sub(c, b)) {
+            return Err(izm(i, "dihedral, angle and bond reference atoms are collinear"));
+          }
+          let bc = unit(sub(c, b));
+          let nv = unit(normal);
+          let m = cross(nv, bc);
+          let d = [
+            -r * theta.cos(),
+            r * theta.sin() * phi.cos(),
+            r * theta.sin() * phi.sin(),
+          ];
+          [
+            c[0] + d[0] * bc[0] + d[1] * m[0] + d[2] * nv[0],
+            c[1] + d[0] * bc[1] + d[1] * m[1] + d[2] * nv[1],
+            c[2] + d[0] * bc[2] + d[1] * m[2] + d[2] * nv[2],
+          ]
+        }
+      }
+    };
+    pos.push(p);
+  }
+
+  let real: Vec<usize> = (0..n).filter(|&i| !zmat.is_dummy[i]).collect();
+  let labels: Vec<String> = real.iter().map(|&i| zmat.labels[i].clone()).collect();
+  Ok(CartesianGeometry {
+    symbols: real.iter().map(|&i| zmat.symbols[i].clone()).collect(),
+    x: real.iter().map(|&i| pos[i][0]).collect(),
+    y: real.iter().map(|&i| pos[i][1]).collect(),
+    z: real.iter().map(|&i| pos[i][2]).collect(),
+    is_ghost: ghost_flags(&labels),
+    labels,
+  })
+}
+
+/// Standard atomic weight in amu of an element symbol (any case), from
+/// `ATOMIC_MASSES`; `None` if the symbol is not an element.
+pub fn atomic_mass(symbol: &str) -> Option<f64> {
+  ELEMENTS
+    .iter()
+    .position(|e| e.eq_ignore_ascii_case(symbol))
+    .map(|i| ATOMIC_MASSES[i])
+}
+
+/// `(Z, x, y, z)` for every nucleus in `molecule`, in Bohr, as taken by the
+/// nuclear attraction and repulsion integrals. Ghost atoms are left out.
+/// With an ECP basis, `ecp_core` maps an atom index (in input order) to the
+/// core electrons its potential replaces, which are subtracted from Z.
+///
+/// Panics if a Z-matrix cannot be converted; `parse_molecule` already
+/// rejects those.
+pub fn nuclei_list(
+  molecule: &Molecule,
+  ecp_core: Option<&HashMap<usize, usize>>,
+) -> Vec<(f64, f64, f64, f64)> {
+  let converted;
+  let cart = match &molecule.geometry {
+    Geometry::Cartesian(g) => g,
+    Geometry::ZMatrix(g) => {
+      converted = zmatrix_to_cartesian(g).expect("validated Z-matrix");
+      &converted
+    }
+  };
+  cart.atomic_numbers()
+    .into_iter()
+    .enumerate()
+    .filter(|&(i, _)| !cart.is_ghost[i])
+    .map(|(i, z)| {
+      let n_core = ecp_core.and_then(|core| core.get(&i)).copied().unwrap_or(0);
+      (z as f64 - n_core as f64, cart.x[i], cart.y[i], cart.z[i])
+    })
+    .collect()
+}
+
+/// Center of nuclear charge Σ Z_i r_i / Σ Z_i in Bohr.
+///
+/// Atoms with Z = 0 (ghosts) do not contribute; if no atom carries charge the
+/// origin is returned.
+pub fn center_of_charge(geometry: &CartesianGeometry) -> (f64, f64, f64) {
+  let mut total = 0.0;
+  let (mut cx, mut cy, mut cz) = (0.0, 0.0, 0.0);
+  for (i, z) in geometry.atomic_numbers().into_iter().enumerate() {
+    let z = z as f64;
+    total += z;
+    cx += z * geometry.x[i];
+    cy += z * geometry.y[i];
+    cz += z * geometry.z[i];
+  }
+  if total == 0.0 {
+    return (0.0, 0.0, 0.0);
+  }
+  (cx / total, cy / total, cz / total)
+}
+
+/// Nuclear repulsion energy E_nn = Σ_{i<j} Z_i Z_j / r_ij in Hartree, with
+/// coordinates in Bohr and Z the bare atomic number (no ECP core removal).
+/// Zero for fewer than two atoms.
+pub fn nuclear_repulsion(geometry: &CartesianGeometry) -> f64 {
+  let n = geometry.symbols.len();
+  let charges: Vec<f64> = geometry.atomic_numbers().into_iter().map(f64::from).collect();
+  let mut energy = 0.0;
+  for i in 0..n {
+    for j in (i + 1)..n {
+      let dx = geometry.x[i] - geometry.x[j];
+      let dy = geometry.y[i] - geometry.y[j];
+      let dz = geometry.z[i] - geometry.z[j];
+      energy += charges[i] * charges[j] / (dx * dx + dy * dy + dz * dz).sqrt();
+    }
+  }
+  energy
+}
+
+/// Interaction energy Σ_i Σ_k Z_i q_k / r_ik in Hartree between the nuclei of
+/// `geometry` (as in [`nuclear_repulsion`]) and external `point_charges`.
+/// Charge–charge terms are excluded.
+pub fn nuclear_point_charge_energy(
+  geometry: &CartesianGeometry,
+  point_charges: &[PointCharge],
+) -> f64 {
+  let charges = geometry.atomic_numbers();
+  let mut energy = 0.0;
+  for (i, z) in charges.into_iter().enumerate() {
+    for pc in point_charges {
+      let dx = geometry.x[i] - pc.position[0];
+      let dy = geometry.y[i] - pc.position[1];
+      let dz = geometry.z[i] - pc.position[2];
+      energy += f64::from(z) * pc.charge / (dx * dx + dy * dy + dz * dz).sqrt();
+    }
+  }
+  energy
+}
+
+/// Parses the ATOM/HETATM records of PDB content into a geometry in Bohr.
+///
+/// Coordinates are read from the fixed columns 31–54 (Angstrom). The element
+/// comes from columns 77–78 or, when that column is blank, from columns 13–14
+/// of the atom name, where two-letter elements are right-justified (`FE`,
+/// `CL`); a blank or digit in column 13 leaves a one-letter element in column
+/// 14 (` CA `, `1HB `). Only the first MODEL is read.
+pub fn parse_pdb_str(content: &str) -> Result<CartesianGeometry, InputError> {
+  let mut geom = CartesianGeometry {
+    symbols: Vec::new(),
+    labels: Vec::new(),
+    x: Vec::new(),
+    y: Vec::new(),
+    z: Vec::new(),
+    is_ghost: Vec::new(),
+  };
+
+  for (i, line) in content.lines().enumerate() {
+    let line_no = i + 1;
+    if line.starts_with("ENDMDL") {
+      break;
+    }
+    if !(line.starts_with("ATOM") || line.starts_with("HETATM")) {
+      continue;
+    }
+
+    let column = |start: usize, end: usize| line.get(start..end.min(line.len())).unwrap_or("");
+    let mut coords = [0.0; 3];
+    for (k, c) in coords.iter_mut().enumerate() {
+      let field = column(30 + 8 * k, 38 + 8 * k).trim();
+      *c = field.parse::<f64>().map_err(|_| InputError::PdbParse {
+        line: line_no,
+        reason: format!("invalid coordinate {:?}", field),
+      })? * ANGSTROM_TO_BOHR;
+    }
+
+    let element = column(76, 78).trim();
+    let symbol = if !element.is_empty() {
+      element.to_string()
+    } else {
+      let name = column(12, 14);
+      let name = match name.chars().next() {
+        Some(c) if c == ' ' || c.is_ascii_digit() => &name[1..],
+        _ => name,
+      }
+      .trim();
+      if name.is_empty() {
+        return Err(InputError::PdbParse {
+          line: line_no,
+          reason: "no element column and no atom name".to_string(),
+        });
+      }
+      name.to_string()
+    };
+
+    geom.symbols.push(normalize_element(&symbol)?);
+    geom.is_ghost.push(false);
+    geom.labels.push(symbol);
+    geom.x.push(coords[0]);
+    geom.y.push(coords[1]);
+    geom.z.push(coords[2]);
+  }
+
+  if geom.symbols.is_empty() {
+    return Err(InputError::InvalidValue {
+      field: "molecule.pdb_file".to_string(),
+      reason: "no ATOM or HETATM records".to_string(),
+    });
+  }
+  Ok(geom)
+}
+
+/// Returns a copy of `geometry` with every position scaled by `factor` about
+/// the center of mass, which stays fixed. `factor` must be > 0.
+pub fn scale_about_com(
+  geometry: &CartesianGeometry,
+  factor: f64,
+) -> Result<CartesianGeometry, InputError> {
+  if factor.is_nan() || factor <= 0.0 {
+    return Err(InputError::InvalidValue {
+      field: "factor".to_string(),
+      reason: format!("must be > 0, got {}", factor),
+    });
+  }
+  let (cx, cy, cz) = center_of_mass(geometry);
+  Ok(CartesianGeometry {
+    symbols: geometry.symbols.clone(),
+    labels: geometry.labels.clone(),
+    x: geometry.x.iter().map(|x| cx + factor * (x - cx)).collect(),
+    y: geometry.y.iter().map(|y| cy + factor * (y - cy)).collect(),
+    z: geometry.z.iter().map(|z| cz + factor * (z - cz)).collect(),
+    is_ghost: geometry.is_ghost.clone(),
+  })
+}
+
+/// Total number of electrons: `molecule.n_electrons` if given, otherwise the
+/// sum of nuclear charges minus `molecule.charge`.
+pub fn electron_count(molecule: &Molecule) -> Result<usize, InputError> {
+  if let Some(n) = molecule.n_electrons {
+    return Ok(n);
+  }
+  let nuclear: i64 = nuclear_charges(molecule).into_iter().map(i64::from).sum();
+  let n = nuclear - molecule.charge as i64;
+  if n < 0 {
+    return Err(InputError::InvalidValue {
+      field: "molecule.charge".to_string(),
+      reason: format!(
+        "charge {} exceeds total nuclear charge {}", molecule.charge, nuclear
+      ),
+    });
+  }
+  Ok(n as usize)
+}
+
+/// Returns `(n_alpha, n_beta)` for `molecule`, ready for `guess_hcore`.
+///
+/// Explicit `n_alpha`/`n_beta` are returned as given once they sum to the
+/// electron count. Otherwise the number of unpaired electrons is
+/// `multiplicity - 1`; it must not exceed the electron count and must have the
+/// same parity.
+pub fn occupation_from_input(molecule: &Molecule) -> Result<(usize, usize), InputError> {
+  let n = electron_count(molecule)?;
+  if let Some((n_alpha, n_beta)) = molecule.n_alpha_beta {
+    if n_alpha + n_beta != n {
+      return Err(InputError::InvalidValue {
+        field: "molecule.n_alpha".to_string(),
+        reason: format!(
+          "n_alpha + n_beta = {} + {} does not match {} electrons",
+          n_alpha, n_beta, n
+        ),
+      });
+    }
+    return Ok((n_alpha, n_beta));
+  }
+  let unpaired = (molecule.multiplicity as usize).saturating_sub(1);
+  if unpaired > n || !(n - unpaired).is_multiple_of(2) {
+    return Err(InputError::InvalidValue {
+      field: "molecule.multiplicity".to_string(),
+      reason: format!(
+        "multiplicity {} is inconsistent with {} electrons",
+        molecule.multiplicity, n
+      ),
+    });
+  }
+  let n_beta = (n - unpaired) / 2;
+  Ok((n_beta + unpaired, n_beta))
+}
+
+/// Exact ⟨S²⟩ = S(S+1) for a state with S = |n_alpha − n_beta| / 2, the value
+/// a spin-pure wavefunction would have.
+pub fn ideal_s_squared(n_alpha: usize, n_beta: usize) -> f64 {
+  let s = n_alpha.abs_diff(n_beta) as f64 / 2.0;
+  s * (s + 1.0)
+}
+
+/// Rough number of contracted basis functions for `molecule` in `basis`.
+///
+/// Each atom contributes its minimal-basis size (by period) times the zeta
+/// level read from the basis name: 1 for `sto-*`, 3 for triple-zeta names
+/// (`*tz*`, `*311*`), 4 for quadruple-zeta (`*qz*`), and 2 otherwise.
+pub fn n_basis_functions(molecule: &Molecule, basis: &str) -> usize {
+  let name = basis.to_ascii_lowercase();
+  let zeta = if name.starts_with("sto") {
+    1
+  } else if name.contains("qz") {
+    4
+  } else if name.contains("tz") || name.contains("311") {
+    3
+  } else {
+    2
+  };
+  molecule_symbols(molecule)
+    .into_iter()
+    .map(|s| minimal_basis_size(atomic_number(s).map_or(0, |z| z as usize)) * zeta)
+    .sum()
+}
+
+/// Coarse cost class of `input`.
+///
+/// The cost is n_bf⁴ (the formal two-electron integral scaling, with n_bf from
+/// [`n_basis_functions`]) times the number of energy-equivalent evaluations
+/// the drivers imply, summed over the workflow: 1 for an energy, 2 for a
+/// gradient, 2 per MD or optimization step (up to `max_steps`), and
+/// 2 · 3N for a (finite-difference) Hessian or frequency run, and none for
+/// analysis. Below 10⁸ is
+/// `Small`, below 10¹¹ `Medium`, anything else `Large`.
+pub fn cost_tier(input: &SimulationInput) -> CostTier {
+  let n_bf = n_basis_functions(&input.molecule, &input.model.basis) as f64;
+  let n_atoms = molecule_symbols(&input.molecule).len() as f64;
+  let evaluations: f64 = input
+    .drivers
+    .iter()
+    .map(|driver| match driver {
+      Driver::Energy => 1.0,
+      Driver::Gradient => 2.0,
+      Driver::Optimize => 2.0 * input.opt.as_ref().map_or(1, |o| o.max_steps) as f64,
+      Driver::Md => 2.0 * input.keywords.as_ref().map_or(1, |k| k.n_steps) as f64,
+      Driver::Hessian | Driver::Frequency => 2.0 * 3.0 * n_atoms,
+      Driver::Analysis => 0.0,
+    })
+    .sum();
+  let cost = n_bf.powi(4) * evaluations;
+  if cost < 1e8 {
+    CostTier::Small
+  } else if cost < 1e11 {
+    CostTier::Medium
+  } else {
+    CostTier::Large
+  }
 }
 
 // ── Private helpers ───────────────────────────────────────────────────────────
 
+/// Symbols of the atoms of `molecule`; Z-matrix dummies are not atoms.
+fn molecule_symbols(molecule: &Molecule) -> Vec<&str> {
+  match &molecule.geometry {
+    Geometry::Cartesian(g) => g.symbols.iter().map(String::as_str).collect(),
+    Geometry::ZMatrix(g) => g
+      .symbols
+      .iter()
+      .zip(&g.is_dummy)
+      .filter(|&(_, &dummy)| !dummy)
+      .map(|(s, _)| s.as_str())
+      .collect(),
+  }
+}
+
+/// Nuclear charge of every atom of `molecule`; 0 for ghosts.
+fn nuclear_charges(molecule: &Molecule) -> Vec<u32> {
+  match &molecule.geometry {
+    Geometry::Cartesian(g) => g.atomic_numbers(),
+    Geometry::ZMatrix(g) => g
+      .symbols
+      .iter()
+      .zip(ghost_flags(&g.labels))
+      .zip(&g.is_dummy)
+      .filter(|&(_, &dummy)| !dummy)
+      .map(|((s, ghost), _)| if ghost { 0 } else { atomic_number(s).expect("validated element symbol") })
+      .collect(),
+  }
+}
+
+/// Minimal-basis function count for atomic number `z` (one per occupied
+/// sub-shell orbital of the period).
+fn minimal_basis_size(z: usize) -> usize {
+  match z {
+    0..=2 => 1,
+    3..=10 => 5,
+    11..=18 => 9,
+    19..=36 => 18,
+    37..=54 => 27,
+    _ => 43,
+  }
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+  [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+  [
+    a[1] * b[2] - a[2] * b[1],
+    a[2] * b[0] - a[0] * b[2],
+    a[0] * b[1] - a[1] * b[0],
+  ]
+}
+
+fn unit(a: [f64; 3]) -> [f64; 3] {
+  let norm = norm(a);
+  [a[0] / norm, a[1] / norm, a[2] / norm]
+}
+
+fn norm(a: [f64; 3]) -> f64 {
+  (a[0] * a[0] + a[1] * a[1] + a[2] * a[2]).sqrt()
+}
+
+/// Mass-weighted mean position; the origin if the total mass is zero.
+fn center_of_mass(geometry: &CartesianGeometry) -> (f64, f64, f64) {
+  let mut total = 0.0;
+  let (mut cx, mut cy, mut cz) = (0.0, 0.0, 0.0);
+  for (i, z) in geometry.atomic_numbers().into_iter().enumerate() {
+    let m = match z {
+      0 => 0.0,
+      z => ATOMIC_MASSES[z as usize - 1],
+    };
+    total += m;
+    cx += m * geometry.x[i];
+    cy += m * geometry.y[i];
+    cz += m * geometry.z[i];
+  }
+  if total == 0.0 {
+    return (0.0, 0.0, 0.0);
+  }
+  (cx / total, cy / total, cz / total)
+}
+
 /// Look up a string key in a YAML mapping.
 fn map_get<'a>(
   m: &'a serde_yaml::Mapping,
@@ -195,13 +1206,24 @@ fn map_get<'a>(
    .map(|(_, v)| v)
 }
 
-/// Normalise `sym` to title case and validate against the periodic table.
-/// Returns the normalised symbol on success.
+/// Read a YAML number as a finite `f64`. Integers (`0`) and exponents
+/// (`1.4e0`) are accepted; quoted strings (`"1.4"`) are not numbers.
+fn yaml_f64(v: &serde_yaml::Value) -> Option<f64> {
+  v.as_f64().filter(|x| x.is_finite())
+}
+
+/// Normalise `sym` to title case and validate against the periodic table,
+/// returning the bare element symbol. Ghost syntax (`Gh(O)`, `@O`; see
+/// `split_ghost`) and then a trailing label of digits and/or Greek letters
+/// (`C1`, `Oα`, `H23`) are stripped first.
 fn normalize_element(sym: &str) -> Result<String, InputError> {
-  if sym.is_empty() {
+  let base = split_ghost(sym).0.trim_end_matches(|c: char| {
+    c.is_ascii_digit() || ('\u{0370}'..='\u{03FF}').contains(&c)
+  });
+  if base.is_empty() {
     return Err(InputError::InvalidElement(sym.to_string()));
   }
-  let mut chars = sym.chars();
+  let mut chars = base.chars();
   let normalized = match chars.next() {
     Some(c) => {
       let upper: String = c.to_uppercase().collect();
@@ -209,25 +1231,59 @@ fn normalize_element(sym: &str) -> Result<String, InputError> {
     }
     Option::None => return Err(InputError::InvalidElement(sym.to_string())),
   };
-  if ELEMENTS.contains(&normalized.as_str()) {
+  if atomic_number(&normalized).is_some() {
     Ok(normalized)
   } else {
     Err(InputError::InvalidElement(sym.to_string()))
   }
 }
 
-fn parse_driver(m: &serde_yaml::Mapping) -> Result<Driver, InputError> {
+/// Splits the ghost-atom syntax `Gh(X)` (any case) or `@X` off `sym`,
+/// returning the inner symbol and whether it was a ghost.
+fn split_ghost(sym: &str) -> (&str, bool) {
+  if let Some(inner) = sym.strip_prefix('@') {
+    return (inner, true);
+  }
+  match sym.get(..3) {
+    Some(p) if p.eq_ignore_ascii_case("gh(") && sym.ends_with(')') => (&sym[3..sym.len() - 1], true),
+    _ => (sym, false),
+  }
+}
+
+/// `split_ghost(label).1` for every label.
+fn ghost_flags(labels: &[String]) -> Vec<bool> {
+  labels.iter().map(|l| split_ghost(l).1).collect()
+}
+
+/// Reads `driver` as either a single name or a non-empty sequence of names.
+fn parse_drivers(m: &serde_yaml::Mapping) -> Result<Vec<Driver>, InputError> {
   let v = map_get(m, "driver")
     .ok_or_else(|| InputError::MissingField("driver".to_string()))?;
+  let Some(seq) = v.as_sequence() else {
+    return Ok(vec![parse_driver(v)?]);
+  };
+  if seq.is_empty() {
+    return Err(InputError::InvalidValue {
+      field: "driver".to_string(),
+      reason: "expected at least one driver".to_string(),
+    });
+  }
+  seq.iter().map(parse_driver).collect()
+}
+
+fn parse_driver(v: &serde_yaml::Value) -> Result<Driver, InputError> {
   let s = v.as_str().ok_or_else(|| InputError::InvalidValue {
     field: "driver".to_string(),
-    reason: "expected a string".to_string(),
+    reason: "expected a string or a sequence of strings".to_string(),
   })?;
   match s {
     "energy" => Ok(Driver::Energy),
     "gradient" => Ok(Driver::Gradient),
     "hessian" => Ok(Driver::Hessian),
     "md" => Ok(Driver::Md),
+    "frequency" => Ok(Driver::Frequency),
+    "optimize" => Ok(Driver::Optimize),
+    "analysis" => Ok(Driver::Analysis),
     other => Err(InputError::InvalidValue {
       field: "driver".to_string(),
       reason: format!("unrecognised driver {:?}", other),
@@ -235,7 +1291,10 @@ fn parse_driver(m: &serde_yaml::Mapping) -> Result<Driver, InputError> {
   }
 }
 
-fn parse_molecule(m: &serde_yaml::Mapping) -> Result<Molecule, InputError> {
+fn parse_molecule(
+  m: &serde_yaml::Mapping,
+  options: &ParseOptions,
+) -> Result<Molecule, InputError> {
   let v = map_get(m, "molecule")
     .ok_or_else(|| InputError::MissingField("molecule".to_string()))?;
   let mol_map = v.as_mapping().ok_or_else(|| InputError::InvalidValue {
@@ -243,16 +1302,16 @@ fn parse_molecule(m: &serde_yaml::Mapping) -> Result<Molecule, InputError> {
     reason: "expected a mapping".to_string(),
   })?;
 
-  let charge = if let Some(cv) = map_get(mol_map, "charge") {
-    cv.as_i64().ok_or_else(|| InputError::InvalidValue {
+  let mut charge = if let Some(cv) = map_get(mol_map, "charge") {
+    Some(cv.as_i64().ok_or_else(|| InputError::InvalidValue {
       field: "molecule.charge".to_string(),
       reason: "expected an integer".to_string(),
-    })? as i32
+    })? as i32)
   } else {
-    0
+    Option::None
   };
 
-  let multiplicity = if let Some(mv) = map_get(mol_map, "multiplicity") {
+  let mut multiplicity = if let Some(mv) = map_get(mol_map, "multiplicity") {
     let n = mv.as_i64().ok_or_else(|| InputError::InvalidValue {
       field: "molecule.multiplicity".to_string(),
       reason: "expected an integer".to_string(),
@@ -263,25 +1322,82 @@ fn parse_molecule(m: &serde_yaml::Mapping) -> Result<Molecule, InputError> {
         reason: format!("must be >= 1, got {}", n),
       });
     }
-    n as u32
+    Some(n as u32)
   } else {
-    1
+    Option::None
+  };
+
+  let n_electrons = parse_count(mol_map, "n_electrons")?;
+  let n_alpha_beta = match (parse_count(mol_map, "n_alpha")?, parse_count(mol_map, "n_beta")?) {
+    (Some(a), Some(b)) => Some((a, b)),
+    (Some(_), Option::None) => return Err(InputError::MissingField("molecule.n_beta".to_string())),
+    (Option::None, Some(_)) => return Err(InputError::MissingField("molecule.n_alpha".to_string())),
+    (Option::None, Option::None) => Option::None,
   };
 
   let units_factor = parse_units(mol_map)?;
+  let angle_units = parse_angle_units(mol_map)?;
 
   let has_symbols = map_get(mol_map, "symbols").is_some();
   let has_geometry = map_get(mol_map, "geometry").is_some();
   let has_zmatrix = map_get(mol_map, "z_matrix").is_some();
-
-  if has_zmatrix && (has_symbols || has_geometry) {
-    return Err(InputError::AmbiguousGeometry);
+  // `geometry_file` is an alias for `xyz_file`; giving both is ambiguous.
+  let xyz_file = map_get(mol_map, "xyz_file")
+    .map(|v| (v, "molecule.xyz_file"))
+    .or_else(|| map_get(mol_map, "geometry_file").map(|v| (v, "molecule.geometry_file")));
+  let pdb_file = map_get(mol_map, "pdb_file");
+  let trajectory_file = map_get(mol_map, "trajectory_file");
+
+  check_single_geometry_source(mol_map)?;
+
+  let lattice = parse_lattice(mol_map, units_factor)?;
+  if lattice.is_some() && has_zmatrix {
+    return Err(InputError::AmbiguousGeometry {
+      sources: vec!["z_matrix".to_string(), "lattice".to_string()],
+    });
+  }
+  let fractional = parse_fractional(mol_map)?;
+  if fractional
+    && (has_zmatrix || xyz_file.is_some() || pdb_file.is_some() || trajectory_file.is_some())
+  {
+    return Err(InputError::InvalidValue {
+      field: "molecule.coordinate_type".to_string(),
+      reason: "fractional coordinates need inline symbols/geometry".to_string(),
+    });
   }
 
-  let geometry = if has_zmatrix {
-    Geometry::ZMatrix(parse_zmatrix(mol_map, units_factor)?)
+  let mut trajectory = Option::None;
+  let geometry = if let Some(path) = trajectory_file {
+    let content = read_geometry_file(path, "molecule.trajectory_file")?;
+    if let Some((c, mult)) = content.lines().nth(1).and_then(xyz_comment_charge_mult) {
+      charge = charge.or(Some(c));
+      multiplicity = multiplicity.or(Some(mult));
+    }
+    let frames = parse_trajectory_str(&content, units_factor)?;
+    let first = frames[0].clone();
+    trajectory = Some(frames);
+    Geometry::Cartesian(first)
+  } else if let Some(path) = pdb_file {
+    Geometry::Cartesian(parse_pdb_str(&read_geometry_file(path, "molecule.pdb_file")?)?)
+  } else if let Some((path, field)) = xyz_file {
+    let content = read_geometry_file(path, field)?;
+    // YAML charge/multiplicity take precedence over the XYZ comment line.
+    if let Some((c, mult)) = content.lines().nth(1).and_then(xyz_comment_charge_mult) {
+      charge = charge.or(Some(c));
+      multiplicity = multiplicity.or(Some(mult));
+    }
+    Geometry::Cartesian(parse_xyz_str(&content, units_factor)?)
+  } else if has_zmatrix {
+    let zmat = parse_zmatrix(mol_map, units_factor, angle_units, options)?;
+    // Surfaces degenerate placements now rather than at first use.
+    zmatrix_to_cartesian(&zmat)?;
+    Geometry::ZMatrix(zmat)
+  } else if has_symbols && has_geometry && fractional {
+    let lattice = lattice
+      .ok_or_else(|| InputError::MissingField("molecule.lattice".to_string()))?;
+    Geometry::Cartesian(parse_cartesian(mol_map, 1.0, Some(&lattice))?)
   } else if has_symbols && has_geometry {
-    Geometry::Cartesian(parse_cartesian(mol_map, units_factor)?)
+    Geometry::Cartesian(parse_cartesian(mol_map, units_factor, Option::None)?)
   } else if has_symbols {
     return Err(InputError::MissingField("molecule.geometry".to_string()));
   } else if has_geometry {
@@ -290,13 +1406,144 @@ fn parse_molecule(m: &serde_yaml::Mapping) -> Result<Molecule, InputError> {
     return Err(InputError::MissingField("molecule.geometry".to_string()));
   };
 
-  Ok(Molecule { geometry, charge, multiplicity })
+  let mut molecule = Molecule {
+    geometry,
+    charge: charge.unwrap_or(0),
+    multiplicity: multiplicity.unwrap_or(1),
+    n_electrons,
+    n_alpha_beta,
+    lattice,
+    trajectory,
+    masses: Option::None,
+  };
+  molecule.masses = parse_masses(mol_map, molecule_symbols(&molecule).len())?;
+  // Rejects charge/multiplicity combinations no electron count can satisfy.
+  occupation_from_input(&molecule)?;
+  Ok(molecule)
 }
 
-/// Returns the coordinate conversion factor (raw → Bohr).
-fn parse_units(mol_map: &serde_yaml::Mapping) -> Result<f64, InputError> {
-  if let Some(u) = map_get(mol_map, "units") {
-    let s = u.as_str().ok_or_else(|| InputError::InvalidValue {
+/// Errors with `AmbiguousGeometry` if more than one geometry source is present.
+/// Inline Cartesian input counts once whether `symbols`, `geometry` or both
+/// are given.
+fn check_single_geometry_source(mol_map: &serde_yaml::Mapping) -> Result<(), InputError> {
+  let inline_cartesian =
+    map_get(mol_map, "symbols").is_some() || map_get(mol_map, "geometry").is_some();
+  let mut sources: Vec<String> = Vec::new();
+  if inline_cartesian {
+    sources.push("symbols/geometry".to_string());
+  }
+  for key in ["z_matrix", "xyz_file", "geometry_file", "pdb_file", "trajectory_file"] {
+    if map_get(mol_map, key).is_some() {
+      sources.push(key.to_string());
+    }
+  }
+  if sources.len() > 1 {
+    return Err(InputError::AmbiguousGeometry { sources });
+  }
+  Ok(())
+}
+
+/// Reads `molecule.lattice`, a flat list of 9 numbers (vectors a, b, c in the
+/// molecule's units), scaled to Bohr by `factor`.
+fn parse_lattice(
+  mol_map: &serde_yaml::Mapping,
+  factor: f64,
+) -> Result<Option<[[f64; 3]; 3]>, InputError> {
+  let Some(v) = map_get(mol_map, "lattice") else {
+    return Ok(Option::None);
+  };
+  let seq = v.as_sequence().ok_or_else(|| InputError::InvalidValue {
+    field: "molecule.lattice".to_string(),
+    reason: "expected a sequence of 9 numbers".to_string(),
+  })?;
+  if seq.len() != 9 {
+    return Err(InputError::LatticeMismatch { n_values: seq.len() });
+  }
+  let mut lattice = [[0.0; 3]; 3];
+  for (k, value) in seq.iter().enumerate() {
+    lattice[k / 3][k % 3] = yaml_f64(value).ok_or_else(|| InputError::InvalidValue {
+      field: "molecule.lattice".to_string(),
+      reason: "lattice components must be numbers".to_string(),
+    })? * factor;
+  }
+  Ok(Some(lattice))
+}
+
+/// Reads the optional non-negative integer `molecule.<key>`.
+fn parse_count(mol_map: &serde_yaml::Mapping, key: &str) -> Result<Option<usize>, InputError> {
+  let Some(v) = map_get(mol_map, key) else {
+    return Ok(Option::None);
+  };
+  let n = v.as_i64().ok_or_else(|| InputError::InvalidValue {
+    field: format!("molecule.{}", key),
+    reason: "expected an integer".to_string(),
+  })?;
+  if n < 0 {
+    return Err(InputError::InvalidValue {
+      field: format!("molecule.{}", key),
+      reason: format!("must be >= 0, got {}", n),
+    });
+  }
+  Ok(Some(n as usize))
+}
+
+/// Reads `molecule.masses`: one strictly positive mass (amu) per atom.
+fn parse_masses(
+  mol_map: &serde_yaml::Mapping,
+  n_atoms: usize,
+) -> Result<Option<Vec<f64>>, InputError> {
+  let Some(v) = map_get(mol_map, "masses") else {
+    return Ok(Option::None);
+  };
+  let invalid = |reason: String| InputError::InvalidValue {
+    field: "molecule.masses".to_string(),
+    reason,
+  };
+  let seq = v.as_sequence()
+    .ok_or_else(|| invalid("expected a sequence of numbers".to_string()))?;
+  if seq.len() != n_atoms {
+    return Err(invalid(format!("expected {} masses (one per atom), got {}", n_atoms, seq.len())));
+  }
+  let masses = seq
+    .iter()
+    .map(|m| yaml_f64(m).ok_or_else(|| invalid("masses must be finite numbers".to_string())))
+    .collect::<Result<Vec<f64>, _>>()?;
+  if let Some((i, m)) = masses.iter().enumerate().find(|(_, m)| **m <= 0.0) {
+    return Err(invalid(format!("mass of atom {} must be > 0, got {}", i + 1, m)));
+  }
+  Ok(Some(masses))
+}
+
+/// True when `molecule.coordinate_type` is `fractional`; `cartesian` or an
+/// absent key give false.
+fn parse_fractional(mol_map: &serde_yaml::Mapping) -> Result<bool, InputError> {
+  match map_get(mol_map, "coordinate_type").map(|v| v.as_str()) {
+    Option::None | Some(Some("cartesian")) => Ok(false),
+    Some(Some("fractional")) => Ok(true),
+    Some(other) => Err(InputError::InvalidValue {
+      field: "molecule.coordinate_type".to_string(),
+      reason: format!("expected cartesian or fractional, got {:?}", other.unwrap_or("")),
+    }),
+  }
+}
+
+/// Maps each position of `geom`, read as fractions (f_a, f_b, f_c) of the
+/// `lattice` vectors, to Cartesian Bohr f_a·a + f_b·b + f_c·c.
+fn fractional_to_cartesian(mut geom: CartesianGeometry, lattice: &[[f64; 3]; 3]) -> CartesianGeometry {
+  for i in 0..geom.symbols.len() {
+    let f = [geom.x[i], geom.y[i], geom.z[i]];
+    let r: [f64; 3] = std::array::from_fn(|k| (0..3).map(|v| f[v] * lattice[v][k]).sum());
+    geom.x[i] = r[0];
+    geom.y[i] = r[1];
+    geom.z[i] = r[2];
+  }
+  geom
+}
+
+/// Returns the coordinate conversion factor (raw → Bohr).
+fn parse_units(mol_map: &serde_yaml::Mapping) -> Result<f64, InputError> {
+  if let Some(u) = map_get(mol_map, "units") {
+    let s = u.as_str().ok_or_else(|| InputError::InvalidValue {
       field: "molecule.units".to_string(),
       reason: "expected a string".to_string(),
     })?;
@@ -313,9 +1560,39 @@ fn parse_units(mol_map: &serde_yaml::Mapping) -> Result<f64, InputError> {
   }
 }
 
+/// Units in which Z-matrix angles and dihedrals are written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AngleUnits {
+  Degrees,
+  Radians,
+}
+
+fn parse_angle_units(mol_map: &serde_yaml::Mapping) -> Result<AngleUnits, InputError> {
+  if let Some(u) = map_get(mol_map, "angle_units") {
+    let s = u.as_str().ok_or_else(|| InputError::InvalidValue {
+      field: "molecule.angle_units".to_string(),
+      reason: "expected a string".to_string(),
+    })?;
+    match s {
+      "degrees" => Ok(AngleUnits::Degrees),
+      "radians" => Ok(AngleUnits::Radians),
+      other => Err(InputError::InvalidValue {
+        field: "molecule.angle_units".to_string(),
+        reason: format!("unrecognised angle units {:?}", other),
+      }),
+    }
+  } else {
+    Ok(AngleUnits::Degrees)
+  }
+}
+
+/// Reads inline `symbols`/`geometry`, scaling coordinates by `factor` and, when
+/// `lattice` is given, reading them as fractions of it. Rejects atoms closer
+/// than [`COINCIDENT_ATOM_THRESHOLD`] Bohr after conversion.
 fn parse_cartesian(
   mol_map: &serde_yaml::Mapping,
   factor: f64,
+  lattice: Option<&[[f64; 3]; 3]>,
 ) -> Result<CartesianGeometry, InputError> {
   let sym_seq = map_get(mol_map, "symbols")
     .unwrap()
@@ -326,12 +1603,14 @@ fn parse_cartesian(
     })?;
 
   let mut symbols = Vec::with_capacity(sym_seq.len());
+  let mut labels = Vec::with_capacity(sym_seq.len());
   for sv in sym_seq {
     let s = sv.as_str().ok_or_else(|| InputError::InvalidValue {
       field: "molecule.symbols".to_string(),
       reason: "element symbols must be strings".to_string(),
     })?;
     symbols.push(normalize_element(s)?);
+    labels.push(s.to_string());
   }
 
   let geo_seq = map_get(mol_map, "geometry")
@@ -353,15 +1632,15 @@ fn parse_cartesian(
   let mut z = Vec::with_capacity(n_symbols);
 
   for chunk in geo_seq.chunks(3) {
-    let cx = chunk[0].as_f64().ok_or_else(|| InputError::InvalidValue {
+    let cx = yaml_f64(&chunk[0]).ok_or_else(|| InputError::InvalidValue {
       field: "molecule.geometry".to_string(),
       reason: "coordinates must be numbers".to_string(),
     })? * factor;
-    let cy = chunk[1].as_f64().ok_or_else(|| InputError::InvalidValue {
+    let cy = yaml_f64(&chunk[1]).ok_or_else(|| InputError::InvalidValue {
       field: "molecule.geometry".to_string(),
       reason: "coordinates must be numbers".to_string(),
     })? * factor;
-    let cz = chunk[2].as_f64().ok_or_else(|| InputError::InvalidValue {
+    let cz = yaml_f64(&chunk[2]).ok_or_else(|| InputError::InvalidValue {
       field: "molecule.geometry".to_string(),
       reason: "coordinates must be numbers".to_string(),
     })? * factor;
@@ -370,12 +1649,38 @@ fn parse_cartesian(
     z.push(cz);
   }
 
-  Ok(CartesianGeometry { symbols, x, y, z })
+  let is_ghost = ghost_flags(&labels);
+  let mut geom = CartesianGeometry { symbols, labels, x, y, z, is_ghost };
+  if let Some(lattice) = lattice {
+    geom = fractional_to_cartesian(geom, lattice);
+  }
+  check_coincident_atoms(&geom)?;
+  Ok(geom)
+}
+
+/// Errors with `CoincidentAtoms` for the first pair (in index order) closer
+/// than [`COINCIDENT_ATOM_THRESHOLD`].
+fn check_coincident_atoms(geom: &CartesianGeometry) -> Result<(), InputError> {
+  let n = geom.symbols.len();
+  for i in 0..n {
+    for j in i + 1..n {
+      let distance = ((geom.x[i] - geom.x[j]).powi(2)
+        + (geom.y[i] - geom.y[j]).powi(2)
+        + (geom.z[i] - geom.z[j]).powi(2))
+      .sqrt();
+      if distance < COINCIDENT_ATOM_THRESHOLD {
+        return Err(InputError::CoincidentAtoms { i: i + 1, j: j + 1, distance });
+      }
+    }
+  }
+  Ok(())
 }
 
 fn parse_zmatrix(
   mol_map: &serde_yaml::Mapping,
   factor: f64,
+  angle_units: AngleUnits,
+  options: &ParseOptions,
 ) -> Result<ZMatrixGeometry, InputError> {
   let rows = map_get(mol_map, "z_matrix")
     .unwrap()
@@ -389,117 +1694,187 @@ fn parse_zmatrix(
     return Err(InputError::MissingField("molecule.z_matrix".to_string()));
   }
 
-  let n = rows.len();
-  let mut symbols = Vec::with_capacity(n);
-  let mut bond_atoms = Vec::with_capacity(n);
-  let mut bond_lengths_bohr = Vec::with_capacity(n);
-  let mut angle_atoms = Vec::with_capacity(n);
-  let mut angles_deg = Vec::with_capacity(n);
-  let mut dihedral_atoms = Vec::with_capacity(n);
-  let mut dihedrals_deg = Vec::with_capacity(n);
+  let empty = serde_yaml::Mapping::new();
+  let variables = match map_get(mol_map, "variables") {
+    Some(v) => v.as_mapping().ok_or_else(|| InputError::InvalidValue {
+      field: "molecule.variables".to_string(),
+      reason: "expected a mapping of names to numbers".to_string(),
+    })?,
+    Option::None => &empty,
+  };
 
+  let n = rows.len();
+  let mut zmat = ZMatrixGeometry {
+    symbols: Vec::with_capacity(n),
+    labels: Vec::with_capacity(n),
+    bond_atoms: Vec::with_capacity(n),
+    bond_lengths_bohr: Vec::with_capacity(n),
+    angle_atoms: Vec::with_capacity(n),
+    angles_deg: Vec::with_capacity(n),
+    dihedral_atoms: Vec::with_capacity(n),
+    dihedrals_deg: Vec::with_capacity(n),
+    is_dummy: Vec::with_capacity(n),
+  };
   for (i, row_val) in rows.iter().enumerate() {
-    let row_map = row_val.as_mapping().ok_or_else(|| izm(i,
-      "each z_matrix entry must be a mapping"))?;
+    let row = parse_zmatrix_row(i, row_val, factor, angle_units, variables, options)?;
+    zmat.symbols.push(row.symbol);
+    zmat.labels.push(row.label);
+    zmat.bond_atoms.push(row.bond_atom);
+    zmat.bond_lengths_bohr.push(row.bond_length_bohr);
+    zmat.angle_atoms.push(row.angle_atom);
+    zmat.angles_deg.push(row.angle_deg);
+    zmat.dihedral_atoms.push(row.dihedral_atom);
+    zmat.dihedrals_deg.push(row.dihedral_deg);
+    zmat.is_dummy.push(row.is_dummy);
+  }
+  Ok(zmat)
+}
 
-    let sym_str = map_get(row_map, "symbol")
-      .ok_or_else(|| izm(i, "missing required field 'symbol'"))?
-      .as_str()
-      .ok_or_else(|| izm(i, "'symbol' must be a string"))?;
-
-    let sym = normalize_element(sym_str)
-      .map_err(|_| InputError::InvalidElement(sym_str.to_string()))?;
-    symbols.push(sym);
-
-    let has_bond_atom    = map_get(row_map, "bond_atom").is_some();
-    let has_bond_length  = map_get(row_map, "bond_length").is_some();
-    let has_angle_atom   = map_get(row_map, "angle_atom").is_some();
-    let has_angle        = map_get(row_map, "angle").is_some();
-    let has_dihedral_atom = map_get(row_map, "dihedral_atom").is_some();
-    let has_dihedral     = map_get(row_map, "dihedral").is_some();
-
-    match i {
-      0 => {
-        if has_bond_atom || has_bond_length || has_angle_atom
-          || has_angle || has_dihedral_atom || has_dihedral
-        {
-          return Err(izm(0, "row 0 must only contain 'symbol'"));
-        }
-        bond_atoms.push(Option::None);
-        bond_lengths_bohr.push(Option::None);
-        angle_atoms.push(Option::None);
-        angles_deg.push(Option::None);
-        dihedral_atoms.push(Option::None);
-        dihedrals_deg.push(Option::None);
-      }
-      1 => {
-        if has_angle_atom || has_angle || has_dihedral_atom || has_dihedral {
-          return Err(izm(1, "row 1 must not contain angle or dihedral fields"));
-        }
-        if !has_bond_atom { return Err(izm(1, "missing required field 'bond_atom'")); }
-        if !has_bond_length { return Err(izm(1, "missing required field 'bond_length'")); }
-        let ba = zmat_ref_idx(row_map, "bond_atom", 1)?;
-        let bl = zmat_bond_length(row_map, 1, factor)?;
-        bond_atoms.push(Some(ba));
-        bond_lengths_bohr.push(Some(bl));
-        angle_atoms.push(Option::None);
-        angles_deg.push(Option::None);
-        dihedral_atoms.push(Option::None);
-        dihedrals_deg.push(Option::None);
+/// One parsed z-matrix row; reference fields are `None` where the row index
+/// does not allow them.
+struct ZMatrixRow {
+  symbol: String,
+  label: String,
+  bond_atom: Option<usize>,
+  bond_length_bohr: Option<f64>,
+  angle_atom: Option<usize>,
+  angle_deg: Option<f64>,
+  dihedral_atom: Option<usize>,
+  dihedral_deg: Option<f64>,
+  is_dummy: bool,
+}
+
+/// Parses z-matrix row `i` (0-based). Depends on earlier rows only through
+/// the row index, so each row can be checked on its own.
+fn parse_zmatrix_row(
+  i: usize,
+  row_val: &serde_yaml::Value,
+  factor: f64,
+  angle_units: AngleUnits,
+  variables: &serde_yaml::Mapping,
+  options: &ParseOptions,
+) -> Result<ZMatrixRow, InputError> {
+  let row_map = row_val.as_mapping().ok_or_else(|| izm(i,
+    "each z_matrix entry must be a mapping"))?;
+
+  let sym_str = map_get(row_map, "symbol")
+    .ok_or_else(|| izm(i, "missing required field 'symbol'"))?
+    .as_str()
+    .ok_or_else(|| izm(i, "'symbol' must be a string"))?;
+
+  let is_dummy = is_dummy_symbol(sym_str);
+  let sym = if is_dummy {
+    "X".to_string()
+  } else {
+    normalize_element(sym_str).map_err(|_| InputError::InvalidElement(sym_str.to_string()))?
+  };
+  let mut row = ZMatrixRow {
+    symbol: sym,
+    label: sym_str.to_string(),
+    is_dummy,
+    bond_atom: Option::None,
+    bond_length_bohr: Option::None,
+    angle_atom: Option::None,
+    angle_deg: Option::None,
+    dihedral_atom: Option::None,
+    dihedral_deg: Option::None,
+  };
+
+  let has_bond_atom    = map_get(row_map, "bond_atom").is_some();
+  let has_bond_length  = map_get(row_map, "bond_length").is_some();
+  let has_angle_atom   = map_get(row_map, "angle_atom").is_some();
+  let has_angle        = map_get(row_map, "angle").is_some();
+  let has_dihedral_atom = map_get(row_map, "dihedral_atom").is_some();
+  let has_dihedral     = map_get(row_map, "dihedral").is_some();
+
+  match i {
+    0 => {
+      if has_bond_atom || has_bond_length || has_angle_atom
+        || has_angle || has_dihedral_atom || has_dihedral
+      {
+        return Err(izm(0, "row 0 must only contain 'symbol'"));
       }
-      2 => {
-        if has_dihedral_atom || has_dihedral {
-          return Err(izm(2, "row 2 must not contain dihedral fields"));
-        }
-        if !has_bond_atom   { return Err(izm(2, "missing required field 'bond_atom'")); }
-        if !has_bond_length { return Err(izm(2, "missing required field 'bond_length'")); }
-        if !has_angle_atom  { return Err(izm(2, "missing required field 'angle_atom'")); }
-        if !has_angle       { return Err(izm(2, "missing required field 'angle'")); }
-        let ba  = zmat_ref_idx(row_map, "bond_atom", 2)?;
-        let bl  = zmat_bond_length(row_map, 2, factor)?;
-        let aa  = zmat_ref_idx(row_map, "angle_atom", 2)?;
-        let ang = zmat_angle(row_map, 2)?;
-        check_distinct(2, ba, Some(aa), Option::None)?;
-        bond_atoms.push(Some(ba));
-        bond_lengths_bohr.push(Some(bl));
-        angle_atoms.push(Some(aa));
-        angles_deg.push(Some(ang));
-        dihedral_atoms.push(Option::None);
-        dihedrals_deg.push(Option::None);
+    }
+    1 => {
+      if has_angle_atom || has_angle || has_dihedral_atom || has_dihedral {
+        return Err(izm(1, "row 1 must not contain angle or dihedral fields"));
       }
-      _ => {
-        if !has_bond_atom     { return Err(izm(i, "missing required field 'bond_atom'")); }
-        if !has_bond_length   { return Err(izm(i, "missing required field 'bond_length'")); }
-        if !has_angle_atom    { return Err(izm(i, "missing required field 'angle_atom'")); }
-        if !has_angle         { return Err(izm(i, "missing required field 'angle'")); }
-        if !has_dihedral_atom { return Err(izm(i, "missing required field 'dihedral_atom'")); }
-        if !has_dihedral      { return Err(izm(i, "missing required field 'dihedral'")); }
-        let ba  = zmat_ref_idx(row_map, "bond_atom", i)?;
-        let bl  = zmat_bond_length(row_map, i, factor)?;
-        let aa  = zmat_ref_idx(row_map, "angle_atom", i)?;
-        let ang = zmat_angle(row_map, i)?;
-        let da  = zmat_ref_idx(row_map, "dihedral_atom", i)?;
-        let dih = zmat_dihedral(row_map, i)?;
-        check_distinct(i, ba, Some(aa), Some(da))?;
-        bond_atoms.push(Some(ba));
-        bond_lengths_bohr.push(Some(bl));
-        angle_atoms.push(Some(aa));
-        angles_deg.push(Some(ang));
-        dihedral_atoms.push(Some(da));
-        dihedrals_deg.push(Some(dih));
+      if !has_bond_atom { return Err(izm(1, "missing required field 'bond_atom'")); }
+      if !has_bond_length { return Err(izm(1, "missing required field 'bond_length'")); }
+      row.bond_atom = Some(zmat_ref_idx(row_map, "bond_atom", 1)?);
+      row.bond_length_bohr = Some(zmat_bond_length(row_map, 1, factor, variables)?);
+    }
+    2 => {
+      if has_dihedral_atom || has_dihedral {
+        return Err(izm(2, "row 2 must not contain dihedral fields"));
       }
+      if !has_bond_atom   { return Err(izm(2, "missing required field 'bond_atom'")); }
+      if !has_bond_length { return Err(izm(2, "missing required field 'bond_length'")); }
+      if !has_angle_atom  { return Err(izm(2, "missing required field 'angle_atom'")); }
+      if !has_angle       { return Err(izm(2, "missing required field 'angle'")); }
+      let ba  = zmat_ref_idx(row_map, "bond_atom", 2)?;
+      let bl  = zmat_bond_length(row_map, 2, factor, variables)?;
+      let aa  = zmat_ref_idx(row_map, "angle_atom", 2)?;
+      let ang = zmat_angle(row_map, 2, angle_units, variables)?;
+      check_distinct(2, ba, Some(aa), Option::None)?;
+      row.bond_atom = Some(ba);
+      row.bond_length_bohr = Some(bl);
+      row.angle_atom = Some(aa);
+      row.angle_deg = Some(ang);
+    }
+    _ => {
+      if !has_bond_atom     { return Err(izm(i, "missing required field 'bond_atom'")); }
+      if !has_bond_length   { return Err(izm(i, "missing required field 'bond_length'")); }
+      if !has_angle_atom    { return Err(izm(i, "missing required field 'angle_atom'")); }
+      if !has_angle         { return Err(izm(i, "missing required field 'angle'")); }
+      if !has_dihedral_atom { return Err(izm(i, "missing required field 'dihedral_atom'")); }
+      if !has_dihedral      { return Err(izm(i, "missing required field 'dihedral'")); }
+      let ba  = zmat_ref_idx(row_map, "bond_atom", i)?;
+      let bl  = zmat_bond_length(row_map, i, factor, variables)?;
+      let aa  = zmat_ref_idx(row_map, "angle_atom", i)?;
+      let ang = zmat_angle(row_map, i, angle_units, variables)?;
+      let da  = zmat_ref_idx(row_map, "dihedral_atom", i)?;
+      let dih = zmat_dihedral(row_map, i, angle_units, options.wrap_dihedrals, variables)?;
+      check_distinct(i, ba, Some(aa), Some(da))?;
+      row.bond_atom = Some(ba);
+      row.bond_length_bohr = Some(bl);
+      row.angle_atom = Some(aa);
+      row.angle_deg = Some(ang);
+      row.dihedral_atom = Some(da);
+      row.dihedral_deg = Some(dih);
     }
   }
+  Ok(row)
+}
 
-  Ok(ZMatrixGeometry {
-    symbols,
-    bond_atoms,
-    bond_lengths_bohr,
-    angle_atoms,
-    angles_deg,
-    dihedral_atoms,
-    dihedrals_deg,
-  })
+/// Every per-row error in `molecule.z_matrix`, for [`validate_input_str`].
+/// Empty when there is no z-matrix sequence; invalid units fall back to the
+/// defaults so the rows are still checked.
+fn zmatrix_row_errors(m: &serde_yaml::Mapping, options: &ParseOptions) -> Vec<InputError> {
+  let Some(mol_map) = map_get(m, "molecule").and_then(|v| v.as_mapping()) else {
+    return Vec::new();
+  };
+  let Some(rows) = map_get(mol_map, "z_matrix").and_then(|v| v.as_sequence()) else {
+    return Vec::new();
+  };
+  let factor = parse_units(mol_map).unwrap_or(ANGSTROM_TO_BOHR);
+  let angle_units = parse_angle_units(mol_map).unwrap_or(AngleUnits::Degrees);
+  let empty = serde_yaml::Mapping::new();
+  let variables = map_get(mol_map, "variables").and_then(|v| v.as_mapping()).unwrap_or(&empty);
+  rows
+    .iter()
+    .enumerate()
+    .filter_map(|(i, row)| {
+      parse_zmatrix_row(i, row, factor, angle_units, variables, options).err()
+    })
+    .collect()
+}
+
+/// True for a Z-matrix dummy atom: `X` or `Xx` in any case, optionally
+/// followed by a numeric label (`X1`).
+fn is_dummy_symbol(sym: &str) -> bool {
+  let base = sym.trim_end_matches(|c: char| c.is_ascii_digit());
+  base.eq_ignore_ascii_case("x") || base.eq_ignore_ascii_case("xx")
 }
 
 /// Parse a 1-based Z-matrix reference index that must refer to a preceding row.
@@ -521,40 +1896,102 @@ fn zmat_ref_idx(
   Ok(idx_i as usize)
 }
 
+/// Read a numeric Z-matrix field that may instead name an entry of the
+/// `variables` table. The value is returned in input units.
+fn zmat_number(
+  row_map: &serde_yaml::Mapping,
+  field: &str,
+  row: usize,
+  variables: &serde_yaml::Mapping,
+) -> Result<f64, InputError> {
+  let v = map_get(row_map, field).unwrap();
+  if let Some(name) = v.as_str() {
+    let value = map_get(variables, name)
+      .ok_or_else(|| izm(row, &format!("'{}' refers to undefined variable {:?}", field, name)))?;
+    return yaml_f64(value)
+      .ok_or_else(|| izm(row, &format!("variable {:?} must be a number", name)));
+  }
+  yaml_f64(v)
+    .ok_or_else(|| izm(row, &format!("'{}' must be a number", field)))
+}
+
 fn zmat_bond_length(
   row_map: &serde_yaml::Mapping,
   row: usize,
   factor: f64,
+  variables: &serde_yaml::Mapping,
 ) -> Result<f64, InputError> {
-  let v = map_get(row_map, "bond_length").unwrap();
-  let bl = v.as_f64()
-    .ok_or_else(|| izm(row, "'bond_length' must be a number"))?;
+  let bl = zmat_number(row_map, "bond_length", row, variables)?;
   if bl <= 0.0 {
     return Err(izm(row, &format!("'bond_length' must be > 0, got {}", bl)));
   }
   Ok(bl * factor)
 }
 
-fn zmat_angle(row_map: &serde_yaml::Mapping, row: usize) -> Result<f64, InputError> {
-  let v = map_get(row_map, "angle").unwrap();
-  let a = v.as_f64()
-    .ok_or_else(|| izm(row, "'angle' must be a number"))?;
-  if a <= 0.0 || a >= 180.0 {
-    return Err(izm(row, &format!("'angle' must satisfy 0 < angle < 180, got {}", a)));
+/// Parse a bond angle and return it in degrees. The range check is applied in
+/// the input units: 0 < angle < 180 for degrees, 0 < angle < π for radians.
+fn zmat_angle(
+  row_map: &serde_yaml::Mapping,
+  row: usize,
+  units: AngleUnits,
+  variables: &serde_yaml::Mapping,
+) -> Result<f64, InputError> {
+  let a = zmat_number(row_map, "angle", row, variables)?;
+  match units {
+    AngleUnits::Degrees => {
+      if a <= 0.0 || a >= 180.0 {
+        return Err(izm(row, &format!("'angle' must satisfy 0 < angle < 180, got {}", a)));
+      }
+      Ok(a)
+    }
+    AngleUnits::Radians => {
+      if a <= 0.0 || a >= std::f64::consts::PI {
+        return Err(izm(row, &format!("'angle' must satisfy 0 < angle < π, got {}", a)));
+      }
+      Ok(a.to_degrees())
+    }
   }
-  Ok(a)
 }
 
-fn zmat_dihedral(row_map: &serde_yaml::Mapping, row: usize) -> Result<f64, InputError> {
-  let v = map_get(row_map, "dihedral").unwrap();
-  let d = v.as_f64()
-    .ok_or_else(|| izm(row, "'dihedral' must be a number"))?;
-  if d < -180.0 || d > 180.0 {
-    return Err(izm(row, &format!(
-      "'dihedral' must satisfy -180 <= dihedral <= 180, got {}", d
-    )));
+/// Parse a dihedral angle and return it in degrees. The range check is applied
+/// in the input units: [-180, 180] for degrees, [-π, π] for radians. With
+/// `wrap`, out-of-range values are first brought into range modulo one turn.
+fn zmat_dihedral(
+  row_map: &serde_yaml::Mapping,
+  row: usize,
+  units: AngleUnits,
+  wrap: bool,
+  variables: &serde_yaml::Mapping,
+) -> Result<f64, InputError> {
+  let mut d = zmat_number(row_map, "dihedral", row, variables)?;
+  if wrap && d.is_finite() {
+    let half_turn = match units {
+      AngleUnits::Degrees => 180.0,
+      AngleUnits::Radians => std::f64::consts::PI,
+    };
+    if !(-half_turn..=half_turn).contains(&d) {
+      d = (d + half_turn).rem_euclid(2.0 * half_turn) - half_turn;
+    }
+  }
+  match units {
+    AngleUnits::Degrees => {
+      if !(-180.0..=180.0).contains(&d) {
+        return Err(izm(row, &format!(
+          "'dihedral' must satisfy -180 <= dihedral <= 180, got {}", d
+        )));
+      }
+      Ok(d)
+    }
+    AngleUnits::Radians => {
+      let pi = std::f64::consts::PI;
+      if !(-pi..=pi).contains(&d) {
+        return Err(izm(row, &format!(
+          "'dihedral' must satisfy -π <= dihedral <= π, got {}", d
+        )));
+      }
+      Ok(d.to_degrees())
+    }
   }
-  Ok(d)
 }
 
 /// Verify that bond_atom, angle_atom, and dihedral_atom are mutually distinct.
@@ -586,12 +2023,76 @@ fn check_distinct(
   Ok(())
 }
 
+/// Reads the geometry file named by the string value `v` of `field`.
+fn read_geometry_file(v: &serde_yaml::Value, field: &str) -> Result<String, InputError> {
+  let path = v.as_str().ok_or_else(|| InputError::InvalidValue {
+    field: field.to_string(),
+    reason: "expected a string".to_string(),
+  })?;
+  std::fs::read_to_string(path)
+    .map_err(|e| InputError::IoError(format!("{}: {}", path, e)))
+}
+
+/// Convenience constructor for `InvalidXyz`.
+fn ixyz(line: usize, reason: &str) -> InputError {
+  InputError::InvalidXyz { line, reason: reason.to_string() }
+}
+
+/// Conversion factor from a `units=` token in an XYZ comment line, if present
+/// and recognised.
+fn xyz_comment_units(comment: &str) -> Option<f64> {
+  comment.split_whitespace().find_map(|tok| {
+    match tok.strip_prefix("units=")?.to_lowercase().as_str() {
+      "bohr" => Some(1.0),
+      "angstrom" => Some(ANGSTROM_TO_BOHR),
+      _ => Option::None,
+    }
+  })
+}
+
+/// `(charge, multiplicity)` from a comment line that starts with two integers,
+/// e.g. `-1 2`. Returns `None` for any other comment.
+fn xyz_comment_charge_mult(comment: &str) -> Option<(i32, u32)> {
+  let mut tokens = comment.split_whitespace();
+  let charge = tokens.next()?.parse::<i32>().ok()?;
+  let mult = tokens.next()?.parse::<u32>().ok()?;
+  if mult < 1 {
+    return Option::None;
+  }
+  Some((charge, mult))
+}
+
 /// Convenience constructor for `InvalidZMatrix`.
 fn izm(row: usize, reason: &str) -> InputError {
   InputError::InvalidZMatrix { row, reason: reason.to_string() }
 }
 
-fn parse_model(m: &serde_yaml::Mapping) -> Result<Model, InputError> {
+/// The `field` value of Z-matrix row `row`, which must be present.
+fn zmat_value(value: Option<f64>, row: usize, field: &str) -> Result<f64, InputError> {
+  value.ok_or_else(|| izm(row, &format!("missing required field '{}'", field)))
+}
+
+/// Position of the atom that `field` of Z-matrix row `row` refers to (1-based),
+/// which must be one of the rows already placed in `pos`.
+fn zmat_reference(
+  pos: &[[f64; 3]],
+  reference: Option<usize>,
+  row: usize,
+  field: &str,
+) -> Result<[f64; 3], InputError> {
+  let idx = reference.ok_or_else(|| izm(row, &format!("missing required field '{}'", field)))?;
+  if idx == 0 || idx > pos.len() {
+    return Err(izm(row, &format!(
+      "'{}' = {} is out of range; must be 1 to {}",
+      field, idx, pos.len()
+    )));
+  }
+  Ok(pos[idx - 1])
+}
+
+/// `molecule` is `None` only when validating an input whose molecule block is
+/// itself invalid; checks that need it are then skipped.
+fn parse_model(m: &serde_yaml::Mapping, molecule: Option<&Molecule>) -> Result<Model, InputError> {
   let v = map_get(m, "model")
     .ok_or_else(|| InputError::MissingField("model".to_string()))?;
   let model_map = v.as_mapping().ok_or_else(|| InputError::InvalidValue {
@@ -613,6 +2114,7 @@ fn parse_model(m: &serde_yaml::Mapping) -> Result<Model, InputError> {
       reason: "must not be empty".to_string(),
     });
   }
+  let method_spec = parse_method_spec(&method)?;
 
   let basis = map_get(model_map, "basis")
     .ok_or_else(|| InputError::MissingField("model.basis".to_string()))?
@@ -629,89 +2131,492 @@ fn parse_model(m: &serde_yaml::Mapping) -> Result<Model, InputError> {
     });
   }
 
-  Ok(Model { method, basis })
+  let basis_file = match map_get(model_map, "basis_file") {
+    Some(bv) => {
+      let path = bv.as_str().ok_or_else(|| InputError::InvalidValue {
+        field: "model.basis_file".to_string(),
+        reason: "expected a string".to_string(),
+      })?;
+      if path.is_empty() {
+        return Err(InputError::InvalidValue {
+          field: "model.basis_file".to_string(),
+          reason: "must not be empty".to_string(),
+        });
+      }
+      Some(path.to_string())
+    }
+    Option::None => Option::None,
+  };
+
+  let basis_overrides = match map_get(model_map, "basis_overrides") {
+    Some(ov) => parse_basis_overrides(ov)?,
+    Option::None => BTreeMap::new(),
+  };
+
+  let reference = parse_reference(model_map, molecule)?;
+
+  Ok(Model { method, method_spec, basis, basis_file, basis_overrides, reference })
 }
 
-fn parse_keywords(v: &serde_yaml::Value) -> Result<MdKeywords, InputError> {
-  let kw_map = v.as_mapping().ok_or_else(|| InputError::InvalidValue {
-    field: "keywords".to_string(),
-    reason: "expected a mapping".to_string(),
-  })?;
+/// Parses `model.basis_overrides`, a mapping of element symbols to non-empty
+/// basis names. Symbols are normalised to title case and must be real elements.
+fn parse_basis_overrides(v: &serde_yaml::Value) -> Result<BTreeMap<String, String>, InputError> {
+  let invalid = |reason: &str| InputError::InvalidValue {
+    field: "model.basis_overrides".to_string(),
+    reason: reason.to_string(),
+  };
+  let map = v.as_mapping().ok_or_else(|| invalid("expected a mapping of elements to basis names"))?;
+  let mut overrides = BTreeMap::new();
+  for (key, value) in map {
+    let sym = key.as_str().ok_or_else(|| invalid("element keys must be strings"))?;
+    let element = normalize_element(sym)
+      .ok()
+      .filter(|_| !split_ghost(sym).1)
+      .ok_or_else(|| InputError::InvalidElement(sym.to_string()))?;
+    let basis = value
+      .as_str()
+      .filter(|b| !b.is_empty())
+      .ok_or_else(|| invalid(&format!("basis for {} must be a non-empty string", element)))?;
+    if overrides.insert(element.clone(), basis.to_string()).is_some() {
+      return Err(invalid(&format!("{} is listed more than once", element)));
+    }
+  }
+  Ok(overrides)
+}
 
-  let timestep_fs = {
-    let tv = map_get(kw_map, "timestep_fs")
-      .ok_or_else(|| InputError::MissingField("keywords.timestep_fs".to_string()))?;
-    let t = tv.as_f64().ok_or_else(|| InputError::InvalidValue {
-      field: "keywords.timestep_fs".to_string(),
-      reason: "expected a number".to_string(),
-    })?;
-    if t <= 0.0 {
+/// Parses `model.reference`, defaulting to RHF for singlets and UHF otherwise.
+/// An explicit `rhf` requires a singlet with an even electron count.
+fn parse_reference(
+  model_map: &serde_yaml::Mapping,
+  molecule: Option<&Molecule>,
+) -> Result<Reference, InputError> {
+  let Some(rv) = map_get(model_map, "reference") else {
+    let open_shell = molecule.is_some_and(is_open_shell);
+    return Ok(if open_shell { Reference::Uhf } else { Reference::Rhf });
+  };
+  let s = rv.as_str().ok_or_else(|| InputError::InvalidValue {
+    field: "model.reference".to_string(),
+    reason: "expected a string".to_string(),
+  })?;
+  let reference = match s {
+    "rhf" => Reference::Rhf,
+    "uhf" => Reference::Uhf,
+    "rohf" => Reference::Rohf,
+    other => return Err(InputError::InvalidValue {
+      field: "model.reference".to_string(),
+      reason: format!("unrecognised reference {:?}", other),
+    }),
+  };
+  if reference == Reference::Rhf
+    && let Some(molecule) = molecule
+  {
+    if let Some((n_alpha, n_beta)) = molecule.n_alpha_beta
+      && n_alpha != n_beta
+    {
       return Err(InputError::InvalidValue {
-        field: "keywords.timestep_fs".to_string(),
-        reason: format!("must be > 0, got {}", t),
+        field: "model.reference".to_string(),
+        reason: format!(
+          "rhf requires a closed shell, got n_alpha {} and n_beta {}",
+          n_alpha, n_beta
+        ),
       });
     }
-    t
+    let n = electron_count(molecule)?;
+    if molecule.n_alpha_beta.is_none() && (!n.is_multiple_of(2) || molecule.multiplicity != 1) {
+      return Err(InputError::InvalidValue {
+        field: "model.reference".to_string(),
+        reason: format!(
+          "rhf requires a closed shell, got {} electrons with multiplicity {}",
+          n, molecule.multiplicity
+        ),
+      });
+    }
+  }
+  Ok(reference)
+}
+
+/// Open shell when explicit `n_alpha`/`n_beta` differ or, without them, when
+/// the multiplicity is not 1.
+fn is_open_shell(molecule: &Molecule) -> bool {
+  match molecule.n_alpha_beta {
+    Some((n_alpha, n_beta)) => n_alpha != n_beta,
+    Option::None => molecule.multiplicity != 1,
+  }
+}
+
+/// Dispersion corrections recognised as a `-suffix` on the method name.
+const DISPERSION_SUFFIXES: &[&str] = &["d2", "d3", "d3bj", "d3(bj)", "d3zero", "d4"];
+
+/// Split a method string such as `b3lyp-d3/def2-svp` into its components.
+/// A `-suffix` that is not a known dispersion correction stays part of the base.
+fn parse_method_spec(method: &str) -> Result<MethodSpec, InputError> {
+  let (name, embedded_basis) = match method.split_once('/') {
+    Some((name, basis)) => {
+      if basis.is_empty() {
+        return Err(InputError::InvalidValue {
+          field: "model.method".to_string(),
+          reason: "basis after '/' must not be empty".to_string(),
+        });
+      }
+      (name, Some(basis.to_string()))
+    }
+    Option::None => (method, Option::None),
   };
 
-  let n_steps = {
-    let nv = map_get(kw_map, "n_steps")
-      .ok_or_else(|| InputError::MissingField("keywords.n_steps".to_string()))?;
-    let n = nv.as_i64().ok_or_else(|| InputError::InvalidValue {
-      field: "keywords.n_steps".to_string(),
+  let (base, dispersion) = match name.rsplit_once('-') {
+    Some((base, suffix))
+      if !base.is_empty()
+        && DISPERSION_SUFFIXES.contains(&suffix.to_lowercase().as_str()) =>
+    {
+      (base, Some(suffix.to_string()))
+    }
+    _ => (name, Option::None),
+  };
+
+  if base.is_empty() {
+    return Err(InputError::InvalidValue {
+      field: "model.method".to_string(),
+      reason: format!("no method name in {:?}", method),
+    });
+  }
+
+  Ok(MethodSpec { base: base.to_string(), dispersion, embedded_basis })
+}
+
+/// Renamed keywords as `(old, new)` pairs. The old name is still accepted
+/// with a warning.
+const DEPRECATED_KEYWORDS: &[(&str, &str)] = &[("temperature", "temperature_k")];
+
+/// Accepted spellings of a keyword as `(canonical, aliases)`. Aliases are
+/// equally valid and produce no warning, but at most one spelling may be given.
+const KEYWORD_ALIASES: &[(&str, &[&str])] = &[
+  ("max_iterations", &["max_iter", "max_scf_cycles"]),
+];
+
+/// Returns a copy of `kw_map` with deprecated keys and aliases renamed to their
+/// canonical names, pushing one warning per deprecated key. Supplying more
+/// than one name for the same keyword is an error.
+fn resolve_keyword_names(
+  kw_map: &serde_yaml::Mapping,
+  warnings: &mut Vec<String>,
+) -> Result<serde_yaml::Mapping, InputError> {
+  let mut resolved = kw_map.clone();
+  for &(canonical, aliases) in KEYWORD_ALIASES {
+    let present: Vec<&str> = std::iter::once(canonical)
+      .chain(aliases.iter().copied())
+      .filter(|name| resolved.contains_key(*name))
+      .collect();
+    if present.len() > 1 {
+      return Err(InputError::InvalidValue {
+        field: format!("keywords.{}", canonical),
+        reason: format!("given under more than one name: {}", present.join(", ")),
+      });
+    }
+    if let Some(&alias) = present.first().filter(|&&name| name != canonical) {
+      let v = resolved.remove(alias).unwrap();
+      resolved.insert(serde_yaml::Value::String(canonical.to_string()), v);
+    }
+  }
+  for &(old, new) in DEPRECATED_KEYWORDS {
+    if let Some(v) = resolved.remove(old) {
+      if resolved.contains_key(new) {
+        return Err(InputError::InvalidValue {
+          field: format!("keywords.{}", old),
+          reason: format!("cannot be combined with its replacement {:?}", new),
+        });
+      }
+      resolved.insert(serde_yaml::Value::String(new.to_string()), v);
+      warnings.push(format!(
+        "keywords.{} is deprecated; use keywords.{} instead", old, new
+      ));
+    }
+  }
+  Ok(resolved)
+}
+
+/// Parses the SCF controls from the optional `keywords` block, applying
+/// defaults for anything absent.
+fn parse_scf_keywords(kw_map: Option<&serde_yaml::Mapping>) -> Result<ScfKeywords, InputError> {
+  let empty = serde_yaml::Mapping::new();
+  let kw_map = kw_map.unwrap_or(&empty);
+
+  let max_iterations = if let Some(mv) = map_get(kw_map, "max_iterations") {
+    let n = mv.as_i64().ok_or_else(|| InputError::InvalidValue {
+      field: "keywords.max_iterations".to_string(),
       reason: "expected an integer".to_string(),
     })?;
     if n <= 0 {
       return Err(InputError::InvalidValue {
-        field: "keywords.n_steps".to_string(),
+        field: "keywords.max_iterations".to_string(),
         reason: format!("must be > 0, got {}", n),
       });
     }
     n as usize
+  } else {
+    128
+  };
+
+  let integral_threshold = if let Some(tv) = map_get(kw_map, "integral_threshold") {
+    let t = tv.as_f64().ok_or_else(|| InputError::InvalidValue {
+      field: "keywords.integral_threshold".to_string(),
+      reason: "expected a number".to_string(),
+    })?;
+    if !(t > 0.0 && t < 1.0) {
+      return Err(InputError::InvalidValue {
+        field: "keywords.integral_threshold".to_string(),
+        reason: format!("must be in (0, 1), got {}", t),
+      });
+    }
+    t
+  } else {
+    1e-12
+  };
+
+  let energy_tol = parse_scf_tolerance(kw_map, "energy_tol", 1e-8)?;
+  let density_tol = parse_scf_tolerance(kw_map, "density_tol", 1e-6)?;
+
+  let diis = match map_get(kw_map, "diis") {
+    Some(v) => v.as_bool().ok_or_else(|| InputError::InvalidValue {
+      field: "keywords.diis".to_string(),
+      reason: "expected a boolean".to_string(),
+    })?,
+    Option::None => true,
+  };
+
+  Ok(ScfKeywords { max_iterations, integral_threshold, energy_tol, density_tol, diis })
+}
+
+/// Reads the SCF convergence threshold `keywords.<name>`, which must be > 0.
+fn parse_scf_tolerance(
+  kw_map: &serde_yaml::Mapping,
+  name: &str,
+  default: f64,
+) -> Result<f64, InputError> {
+  let Some(v) = map_get(kw_map, name) else {
+    return Ok(default);
   };
+  let field = format!("keywords.{}", name);
+  let t = v.as_f64().ok_or_else(|| InputError::InvalidValue {
+    field: field.clone(),
+    reason: "expected a number".to_string(),
+  })?;
+  if t <= 0.0 {
+    return Err(InputError::InvalidValue { field, reason: format!("must be > 0, got {}", t) });
+  }
+  Ok(t)
+}
+
+/// Parses the thermochemistry conditions from the optional `keywords` block.
+fn parse_freq_keywords(kw_map: Option<&serde_yaml::Mapping>) -> Result<FreqKeywords, InputError> {
+  let empty = serde_yaml::Mapping::new();
+  let kw_map = kw_map.unwrap_or(&empty);
 
   let temperature_k = if let Some(tv) = map_get(kw_map, "temperature_k") {
     let t = tv.as_f64().ok_or_else(|| InputError::InvalidValue {
       field: "keywords.temperature_k".to_string(),
       reason: "expected a number".to_string(),
     })?;
-    if t < 0.0 {
+    if t <= 0.0 {
       return Err(InputError::InvalidValue {
         field: "keywords.temperature_k".to_string(),
-        reason: format!("must be >= 0, got {}", t),
+        reason: format!("must be > 0, got {}", t),
       });
     }
     t
   } else {
-    0.0
+    298.15
   };
 
-  let thermostat = if let Some(tv) = map_get(kw_map, "thermostat") {
-    let s = tv.as_str().ok_or_else(|| InputError::InvalidValue {
-      field: "keywords.thermostat".to_string(),
-      reason: "expected a string".to_string(),
+  let pressure_atm = if let Some(pv) = map_get(kw_map, "pressure_atm") {
+    let p = pv.as_f64().ok_or_else(|| InputError::InvalidValue {
+      field: "keywords.pressure_atm".to_string(),
+      reason: "expected a number".to_string(),
     })?;
-    match s {
-      "none" => Thermostat::None,
-      "velocity_rescaling" => Thermostat::VelocityRescaling,
-      other => return Err(InputError::InvalidValue {
-        field: "keywords.thermostat".to_string(),
-        reason: format!("unrecognised thermostat {:?}", other),
-      }),
+    if p <= 0.0 {
+      return Err(InputError::InvalidValue {
+        field: "keywords.pressure_atm".to_string(),
+        reason: format!("must be > 0, got {}", p),
+      });
     }
+    p
   } else {
-    Thermostat::None
+    1.0
   };
 
-  Ok(MdKeywords { timestep_fs, n_steps, temperature_k, thermostat })
+  Ok(FreqKeywords { temperature_k, pressure_atm })
 }
 
-// ── Tests ─────────────────────────────────────────────────────────────────────
-
-#[cfg(test)]
-mod tests {
-  use super::*;
-  use std::io::Write;
+/// Parses `keywords.thermostat`: either a bare name (`none`,
+/// `velocity_rescaling`) or a mapping with a `type` key, which Nosé–Hoover
+/// requires for its `tau_fs`.
+fn parse_thermostat(v: &serde_yaml::Value) -> Result<Thermostat, InputError> {
+  let invalid = |reason: String| InputError::InvalidValue {
+    field: "keywords.thermostat".to_string(),
+    reason,
+  };
+  let (name, params) = match v {
+    serde_yaml::Value::String(s) => (s.as_str(), Option::None),
+    serde_yaml::Value::Mapping(m) => {
+      let name = map_get(m, "type")
+        .and_then(|t| t.as_str())
+        .ok_or_else(|| invalid("mapping form requires a string \"type\"".to_string()))?;
+      (name, Some(m))
+    }
+    _ => return Err(invalid("expected a string or a mapping".to_string())),
+  };
+  match name {
+    "none" => Ok(Thermostat::None),
+    "velocity_rescaling" => Ok(Thermostat::VelocityRescaling),
+    "nose_hoover" => {
+      let tau_fs = params
+        .and_then(|m| map_get(m, "tau_fs"))
+        .ok_or_else(|| invalid("nose_hoover requires tau_fs".to_string()))?
+        .as_f64()
+        .ok_or_else(|| invalid("tau_fs must be a number".to_string()))?;
+      if tau_fs.is_nan() || tau_fs <= 0.0 {
+        return Err(invalid(format!("tau_fs must be > 0, got {}", tau_fs)));
+      }
+      Ok(Thermostat::NoseHoover { tau_fs })
+    }
+    other => Err(invalid(format!("unrecognised thermostat {:?}", other))),
+  }
+}
+
+/// Parses the optimization controls from the optional `keywords` block.
+fn parse_opt_keywords(kw_map: Option<&serde_yaml::Mapping>) -> Result<OptKeywords, InputError> {
+  let empty = serde_yaml::Mapping::new();
+  let kw_map = kw_map.unwrap_or(&empty);
+
+  let max_steps = if let Some(nv) = map_get(kw_map, "max_steps") {
+    let n = nv.as_i64().ok_or_else(|| InputError::InvalidValue {
+      field: "keywords.max_steps".to_string(),
+      reason: "expected an integer".to_string(),
+    })?;
+    if n <= 0 {
+      return Err(InputError::InvalidValue {
+        field: "keywords.max_steps".to_string(),
+        reason: format!("must be > 0, got {}", n),
+      });
+    }
+    n as usize
+  } else {
+    100
+  };
+
+  let mut thresholds = [("gradient_threshold", 4.5e-4), ("step_threshold", 1.8e-3)];
+  for (name, value) in thresholds.iter_mut() {
+    if let Some(tv) = map_get(kw_map, name) {
+      let t = tv.as_f64().ok_or_else(|| InputError::InvalidValue {
+        field: format!("keywords.{}", name),
+        reason: "expected a number".to_string(),
+      })?;
+      if t.is_nan() || t <= 0.0 {
+        return Err(InputError::InvalidValue {
+          field: format!("keywords.{}", name),
+          reason: format!("must be > 0, got {}", t),
+        });
+      }
+      *value = t;
+    }
+  }
+
+  Ok(OptKeywords {
+    max_steps,
+    gradient_threshold: thresholds[0].1,
+    step_threshold: thresholds[1].1,
+  })
+}
+
+/// Parses the MD keywords; `velocities` must have 3 × `n_atoms` entries and
+/// are scaled by `units_factor` (raw length unit → Bohr).
+fn parse_keywords(
+  kw_map: &serde_yaml::Mapping,
+  n_atoms: usize,
+  units_factor: f64,
+) -> Result<MdKeywords, InputError> {
+  let timestep_fs = {
+    let tv = map_get(kw_map, "timestep_fs")
+      .ok_or_else(|| InputError::MissingField("keywords.timestep_fs".to_string()))?;
+    let t = tv.as_f64().ok_or_else(|| InputError::InvalidValue {
+      field: "keywords.timestep_fs".to_string(),
+      reason: "expected a number".to_string(),
+    })?;
+    if t <= 0.0 {
+      return Err(InputError::InvalidValue {
+        field: "keywords.timestep_fs".to_string(),
+        reason: format!("must be > 0, got {}", t),
+      });
+    }
+    t
+  };
+
+  let n_steps = {
+    let nv = map_get(kw_map, "n_steps")
+      .ok_or_else(|| InputError::MissingField("keywords.n_steps".to_string()))?;
+    let n = nv.as_i64().ok_or_else(|| InputError::InvalidValue {
+      field: "keywords.n_steps".to_string(),
+      reason: "expected an integer".to_string(),
+    })?;
+    if n <= 0 {
+      return Err(InputError::InvalidValue {
+        field: "keywords.n_steps".to_string(),
+        reason: format!("must be > 0, got {}", n),
+      });
+    }
+    n as usize
+  };
+
+  let temperature_k = if let Some(tv) = map_get(kw_map, "temperature_k") {
+    let t = tv.as_f64().ok_or_else(|| InputError::InvalidValue {
+      field: "keywords.temperature_k".to_string(),
+      reason: "expected a number".to_string(),
+    })?;
+    if t < 0.0 {
+      return Err(InputError::InvalidValue {
+        field: "keywords.temperature_k".to_string(),
+        reason: format!("must be >= 0, got {}", t),
+      });
+    }
+    t
+  } else {
+    0.0
+  };
+
+  let thermostat = match map_get(kw_map, "thermostat") {
+    Some(tv) => parse_thermostat(tv)?,
+    Option::None => Thermostat::None,
+  };
+
+  let velocities = if let Some(vv) = map_get(kw_map, "velocities") {
+    let seq = vv.as_sequence().ok_or_else(|| InputError::InvalidValue {
+      field: "keywords.velocities".to_string(),
+      reason: "expected a sequence".to_string(),
+    })?;
+    if seq.len() != 3 * n_atoms {
+      return Err(InputError::VelocityMismatch { n_atoms, n_velocities: seq.len() });
+    }
+    let mut velocities = Vec::with_capacity(seq.len());
+    for v in seq {
+      let v = v.as_f64().ok_or_else(|| InputError::InvalidValue {
+        field: "keywords.velocities".to_string(),
+        reason: "velocities must be numbers".to_string(),
+      })?;
+      velocities.push(v * units_factor);
+    }
+    Some(velocities)
+  } else {
+    Option::None
+  };
+
+  Ok(MdKeywords { timestep_fs, n_steps, temperature_k, thermostat, velocities })
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::Write;
 
   const BOHR: f64 = ANGSTROM_TO_BOHR;
 
@@ -724,7 +2629,7 @@ mod tests {
   fn energy_yaml() -> &'static str {
     "driver: energy\n\
      molecule:\n\
-     \x20 symbols: [H]\n\
+     \x20 symbols: [He]\n\
      \x20 geometry: [0.0, 0.0, 0.0]\n\
      model:\n\
      \x20 method: hf\n\
@@ -734,7 +2639,7 @@ mod tests {
   fn md_yaml() -> &'static str {
     "driver: md\n\
      molecule:\n\
-     \x20 symbols: [H]\n\
+     \x20 symbols: [He]\n\
      \x20 geometry: [0.0, 0.0, 0.0]\n\
      model:\n\
      \x20 method: hf\n\
@@ -774,12 +2679,12 @@ mod tests {
   #[test]
   fn test_minimal_cartesian_energy() {
     let r = parse_input_str(energy_yaml()).unwrap();
-    assert_eq!(r.driver, Driver::Energy);
+    assert_eq!(r.drivers, vec![Driver::Energy]);
     assert_eq!(r.keywords, Option::None);
     assert_eq!(r.molecule.charge, 0);
     assert_eq!(r.molecule.multiplicity, 1);
     match &r.molecule.geometry {
-      Geometry::Cartesian(c) => assert_eq!(c.symbols, vec!["H"]),
+      Geometry::Cartesian(c) => assert_eq!(c.symbols, vec!["He"]),
       _ => panic!("expected Cartesian"),
     }
   }
@@ -802,7 +2707,7 @@ mod tests {
       \x20 temperature_k: 300.0\n\
       \x20 thermostat: velocity_rescaling\n";
     let r = parse_input_str(yaml).unwrap();
-    assert_eq!(r.driver, Driver::Md);
+    assert_eq!(r.drivers, vec![Driver::Md]);
     assert_eq!(r.molecule.charge, -1);
     assert_eq!(r.molecule.multiplicity, 2);
     let kw = r.keywords.unwrap();
@@ -856,7 +2761,7 @@ mod tests {
   fn test_missing_units_defaults_to_angstrom() {
     let yaml = "driver: energy\n\
       molecule:\n\
-      \x20 symbols: [H]\n\
+      \x20 symbols: [He]\n\
       \x20 geometry: [1.0, 0.0, 0.0]\n\
       model:\n\
       \x20 method: hf\n\
@@ -873,7 +2778,8 @@ mod tests {
     let yaml = "driver: energy\n\
       molecule:\n\
       \x20 symbols: [o, H, FE]\n\
-      \x20 geometry: [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]\n\
+      \x20 geometry: [0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 2.0]\n\
+      \x20 multiplicity: 2\n\
       model:\n\
       \x20 method: hf\n\
       \x20 basis: sto-3g\n";
@@ -890,14 +2796,14 @@ mod tests {
   fn test_driver_gradient_accepted() {
     let yaml = energy_yaml().replace("energy", "gradient");
     let r = parse_input_str(&yaml).unwrap();
-    assert_eq!(r.driver, Driver::Gradient);
+    assert_eq!(r.drivers, vec![Driver::Gradient]);
   }
 
   #[test]
   fn test_driver_hessian_accepted() {
     let yaml = energy_yaml().replace("energy", "hessian");
     let r = parse_input_str(&yaml).unwrap();
-    assert_eq!(r.driver, Driver::Hessian);
+    assert_eq!(r.drivers, vec![Driver::Hessian]);
   }
 
   #[test]
@@ -908,11 +2814,48 @@ mod tests {
     assert_eq!(kw.thermostat, Thermostat::None);
   }
 
+  #[test]
+  fn test_plain_method_has_no_components() {
+    let r = parse_input_str(energy_yaml()).unwrap();
+    assert_eq!(r.model.method, "hf");
+    assert_eq!(r.model.method_spec, MethodSpec {
+      base: "hf".to_string(),
+      dispersion: Option::None,
+      embedded_basis: Option::None,
+    });
+  }
+
+  #[test]
+  fn test_method_with_dispersion_suffix() {
+    let yaml = energy_yaml().replace("method: hf", "method: b3lyp-d3");
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(r.model.method, "b3lyp-d3");
+    assert_eq!(r.model.method_spec.base, "b3lyp");
+    assert_eq!(r.model.method_spec.dispersion.as_deref(), Some("d3"));
+    assert_eq!(r.model.method_spec.embedded_basis, Option::None);
+  }
+
+  #[test]
+  fn test_method_with_embedded_basis() {
+    let yaml = energy_yaml().replace("method: hf", "method: hf/6-31g");
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(r.model.method_spec.base, "hf");
+    assert_eq!(r.model.method_spec.embedded_basis.as_deref(), Some("6-31g"));
+  }
+
+  #[test]
+  fn test_method_unknown_suffix_stays_in_base() {
+    let yaml = energy_yaml().replace("method: hf", "method: wb97x-v");
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(r.model.method_spec.base, "wb97x-v");
+    assert_eq!(r.model.method_spec.dispersion, Option::None);
+  }
+
   #[test]
   fn test_keywords_ignored_for_non_md_driver() {
     let yaml = "driver: energy\n\
       molecule:\n\
-      \x20 symbols: [H]\n\
+      \x20 symbols: [He]\n\
       \x20 geometry: [0.0, 0.0, 0.0]\n\
       model:\n\
       \x20 method: hf\n\
@@ -921,7 +2864,7 @@ mod tests {
       \x20 timestep_fs: 0.5\n\
       \x20 n_steps: 100\n";
     let r = parse_input_str(yaml).unwrap();
-    assert_eq!(r.driver, Driver::Energy);
+    assert_eq!(r.drivers, vec![Driver::Energy]);
     assert_eq!(r.keywords, Option::None);
   }
 
@@ -957,6 +2900,7 @@ mod tests {
       \x20     bond_atom: 1\n\
       \x20     bond_length: 0.9572\n\
       \x20 units: angstrom\n\
+      \x20 multiplicity: 2\n\
       model:\n\
       \x20 method: hf\n\
       \x20 basis: sto-3g\n";
@@ -1021,6 +2965,7 @@ mod tests {
       \x20     bond_atom: 1\n\
       \x20     bond_length: 1.0\n\
       \x20 units: angstrom\n\
+      \x20 multiplicity: 2\n\
       model:\n\
       \x20 method: hf\n\
       \x20 basis: sto-3g\n";
@@ -1033,6 +2978,45 @@ mod tests {
     }
   }
 
+  #[test]
+  fn test_zmatrix_angle_in_radians_stored_as_degrees() {
+    let yaml = "driver: energy\n\
+      molecule:\n\
+      \x20 angle_units: radians\n\
+      \x20 z_matrix:\n\
+      \x20   - symbol: O\n\
+      \x20   - symbol: H\n\
+      \x20     bond_atom: 1\n\
+      \x20     bond_length: 0.96\n\
+      \x20   - symbol: H\n\
+      \x20     bond_atom: 1\n\
+      \x20     bond_length: 0.96\n\
+      \x20     angle_atom: 2\n\
+      \x20     angle: 1.5707963267948966\n\
+      model:\n\
+      \x20 method: hf\n\
+      \x20 basis: sto-3g\n";
+    let r = parse_input_str(yaml).unwrap();
+    match &r.molecule.geometry {
+      Geometry::ZMatrix(z) => assert!(approx(z.angles_deg[2].unwrap(), 90.0)),
+      _ => panic!("expected ZMatrix"),
+    }
+  }
+
+  #[test]
+  fn test_zmatrix_dihedral_in_radians_stored_as_degrees() {
+    let yaml = zmat_energy_yaml()
+      .replace("molecule:\n", "molecule:\n  angle_units: radians\n")
+      .replace("angle: 104.5", "angle: 1.8238691")
+      .replace("angle: 109.5", "angle: 1.9111355")
+      .replace("dihedral: 120.0", "dihedral: -1.5707963267948966");
+    let r = parse_input_str(&yaml).unwrap();
+    match &r.molecule.geometry {
+      Geometry::ZMatrix(z) => assert!(approx(z.dihedrals_deg[3].unwrap(), -90.0)),
+      _ => panic!("expected ZMatrix"),
+    }
+  }
+
   // ── File I/O ────────────────────────────────────────────────────────────────
 
   #[test]
@@ -1064,7 +3048,7 @@ mod tests {
   #[test]
   fn test_missing_driver() {
     let yaml = "molecule:\n\
-      \x20 symbols: [H]\n\
+      \x20 symbols: [He]\n\
       \x20 geometry: [0.0, 0.0, 0.0]\n\
       model:\n\
       \x20 method: hf\n\
@@ -1091,7 +3075,7 @@ mod tests {
   fn test_missing_model() {
     let yaml = "driver: energy\n\
       molecule:\n\
-      \x20 symbols: [H]\n\
+      \x20 symbols: [He]\n\
       \x20 geometry: [0.0, 0.0, 0.0]\n";
     assert_eq!(
       parse_input_str(yaml),
@@ -1103,7 +3087,7 @@ mod tests {
   fn test_missing_model_method() {
     let yaml = "driver: energy\n\
       molecule:\n\
-      \x20 symbols: [H]\n\
+      \x20 symbols: [He]\n\
       \x20 geometry: [0.0, 0.0, 0.0]\n\
       model:\n\
       \x20 basis: sto-3g\n";
@@ -1117,7 +3101,7 @@ mod tests {
   fn test_missing_model_basis() {
     let yaml = "driver: energy\n\
       molecule:\n\
-      \x20 symbols: [H]\n\
+      \x20 symbols: [He]\n\
       \x20 geometry: [0.0, 0.0, 0.0]\n\
       model:\n\
       \x20 method: hf\n";
@@ -1131,7 +3115,7 @@ mod tests {
   fn test_missing_keywords_block_for_md() {
     let yaml = "driver: md\n\
       molecule:\n\
-      \x20 symbols: [H]\n\
+      \x20 symbols: [He]\n\
       \x20 geometry: [0.0, 0.0, 0.0]\n\
       model:\n\
       \x20 method: hf\n\
@@ -1146,7 +3130,7 @@ mod tests {
   fn test_missing_keywords_timestep_fs() {
     let yaml = "driver: md\n\
       molecule:\n\
-      \x20 symbols: [H]\n\
+      \x20 symbols: [He]\n\
       \x20 geometry: [0.0, 0.0, 0.0]\n\
       model:\n\
       \x20 method: hf\n\
@@ -1163,7 +3147,7 @@ mod tests {
   fn test_missing_keywords_n_steps() {
     let yaml = "driver: md\n\
       molecule:\n\
-      \x20 symbols: [H]\n\
+      \x20 symbols: [He]\n\
       \x20 geometry: [0.0, 0.0, 0.0]\n\
       model:\n\
       \x20 method: hf\n\
@@ -1180,7 +3164,7 @@ mod tests {
   fn test_missing_geometry_only_symbols_present() {
     let yaml = "driver: energy\n\
       molecule:\n\
-      \x20 symbols: [H]\n\
+      \x20 symbols: [He]\n\
       model:\n\
       \x20 method: hf\n\
       \x20 basis: sto-3g\n";
@@ -1238,7 +3222,7 @@ mod tests {
   fn test_unknown_top_level_key() {
     let yaml = "driver: energy\n\
       molecule:\n\
-      \x20 symbols: [H]\n\
+      \x20 symbols: [He]\n\
       \x20 geometry: [0.0, 0.0, 0.0]\n\
       model:\n\
       \x20 method: hf\n\
@@ -1254,7 +3238,55 @@ mod tests {
 
   #[test]
   fn test_unrecognised_driver() {
-    let yaml = energy_yaml().replace("energy", "optimize");
+    let yaml = energy_yaml().replace("energy", "irc");
+    assert!(matches!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue { field, .. }) if field == "driver"
+    ));
+  }
+
+  #[test]
+  fn test_driver_sequence_optimize_then_frequency() {
+    let yaml = energy_yaml().replace("driver: energy", "driver: [optimize, frequency]");
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(r.drivers, vec![Driver::Optimize, Driver::Frequency]);
+    assert!(r.opt.is_some());
+    assert!(r.freq.is_some());
+    assert!(r.scf.is_some());
+    assert!(r.keywords.is_none());
+  }
+
+  #[test]
+  fn test_driver_sequence_missing_md_keywords() {
+    let yaml = energy_yaml().replace("driver: energy", "driver: [optimize, md]");
+    assert_eq!(
+      parse_input_str(&yaml),
+      Err(InputError::MissingField("keywords".to_string()))
+    );
+  }
+
+  #[test]
+  fn test_driver_sequence_with_md_keywords() {
+    let yaml = md_yaml().replace("driver: md", "driver: [energy, md]");
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(r.drivers, vec![Driver::Energy, Driver::Md]);
+    assert_eq!(r.keywords.unwrap().n_steps, 100);
+    assert!(r.scf.is_some());
+  }
+
+  #[test]
+  fn test_driver_sequence_empty_rejected() {
+    let yaml = energy_yaml().replace("driver: energy", "driver: []");
+    assert!(matches!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue { field, reason })
+        if field == "driver" && reason.contains("at least one")
+    ));
+  }
+
+  #[test]
+  fn test_driver_sequence_unrecognised_entry() {
+    let yaml = energy_yaml().replace("driver: energy", "driver: [energy, irc]");
     assert!(matches!(
       parse_input_str(&yaml),
       Err(InputError::InvalidValue { field, .. }) if field == "driver"
@@ -1265,7 +3297,7 @@ mod tests {
   fn test_unrecognised_units() {
     let yaml = "driver: energy\n\
       molecule:\n\
-      \x20 symbols: [H]\n\
+      \x20 symbols: [He]\n\
       \x20 geometry: [0.0, 0.0, 0.0]\n\
       \x20 units: nanometer\n\
       model:\n\
@@ -1281,7 +3313,7 @@ mod tests {
   fn test_empty_method() {
     let yaml = "driver: energy\n\
       molecule:\n\
-      \x20 symbols: [H]\n\
+      \x20 symbols: [He]\n\
       \x20 geometry: [0.0, 0.0, 0.0]\n\
       model:\n\
       \x20 method: \"\"\n\
@@ -1296,7 +3328,7 @@ mod tests {
   fn test_empty_basis() {
     let yaml = "driver: energy\n\
       molecule:\n\
-      \x20 symbols: [H]\n\
+      \x20 symbols: [He]\n\
       \x20 geometry: [0.0, 0.0, 0.0]\n\
       model:\n\
       \x20 method: hf\n\
@@ -1356,7 +3388,7 @@ mod tests {
   fn test_multiplicity_zero() {
     let yaml = "driver: energy\n\
       molecule:\n\
-      \x20 symbols: [H]\n\
+      \x20 symbols: [He]\n\
       \x20 geometry: [0.0, 0.0, 0.0]\n\
       \x20 multiplicity: 0\n\
       model:\n\
@@ -1374,14 +3406,19 @@ mod tests {
   fn test_ambiguous_geometry() {
     let yaml = "driver: energy\n\
       molecule:\n\
-      \x20 symbols: [H]\n\
+      \x20 symbols: [He]\n\
       \x20 geometry: [0.0, 0.0, 0.0]\n\
       \x20 z_matrix:\n\
       \x20   - symbol: H\n\
       model:\n\
       \x20 method: hf\n\
       \x20 basis: sto-3g\n";
-    assert_eq!(parse_input_str(yaml), Err(InputError::AmbiguousGeometry));
+    assert_eq!(
+      parse_input_str(yaml),
+      Err(InputError::AmbiguousGeometry {
+        sources: vec!["symbols/geometry".to_string(), "z_matrix".to_string()],
+      })
+    );
   }
 
   #[test]
@@ -1736,6 +3773,27 @@ mod tests {
     ));
   }
 
+  #[test]
+  fn test_zmat_angle_out_of_range_in_radians() {
+    // 104.5 is a valid angle in degrees but exceeds π when read as radians.
+    let yaml = zmat_energy_yaml()
+      .replace("molecule:\n", "molecule:\n  angle_units: radians\n");
+    assert!(matches!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidZMatrix { row: 2, .. })
+    ));
+  }
+
+  #[test]
+  fn test_unrecognised_angle_units() {
+    let yaml = zmat_energy_yaml()
+      .replace("molecule:\n", "molecule:\n  angle_units: gradians\n");
+    assert!(matches!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue { field, .. }) if field == "molecule.angle_units"
+    ));
+  }
+
   #[test]
   fn test_zmat_error_identifies_correct_row() {
     // 5-atom z-matrix where only row 4 has an invalid bond_length.
@@ -1773,4 +3831,2325 @@ mod tests {
       Err(InputError::InvalidZMatrix { row: 4, .. })
     ));
   }
+
+  // ── Electron occupation ──────────────────────────────────────────────────────
+
+  #[test]
+  fn test_electron_count_includes_charge() {
+    let yaml = zmat_energy_yaml().replace("molecule:\n", "molecule:\n  charge: 1\n  multiplicity: 2\n");
+    let r = parse_input_str(&yaml).unwrap();
+    // O + 2 H + C = 8 + 2 + 6 = 16 electrons, minus one for the cation.
+    assert_eq!(electron_count(&r.molecule).unwrap(), 15);
+  }
+
+  #[test]
+  fn test_occupation_closed_shell() {
+    let r = parse_input_str(zmat_energy_yaml()).unwrap();
+    assert_eq!(occupation_from_input(&r.molecule).unwrap(), (8, 8));
+  }
+
+  #[test]
+  fn test_occupation_neutral_triplet() {
+    let yaml = zmat_energy_yaml().replace("molecule:\n", "molecule:\n  multiplicity: 3\n");
+    let r = parse_input_str(&yaml).unwrap();
+    let (n_alpha, n_beta) = occupation_from_input(&r.molecule).unwrap();
+    assert_eq!(n_alpha - n_beta, 2);
+    assert_eq!(n_alpha + n_beta, 16);
+  }
+
+  #[test]
+  fn test_occupation_inconsistent_multiplicity() {
+    // Two electrons cannot be a doublet.
+    let mut r = parse_input_str(energy_yaml()).unwrap();
+    r.molecule.multiplicity = 2;
+    assert!(matches!(
+      occupation_from_input(&r.molecule),
+      Err(InputError::InvalidValue { field, .. }) if field == "molecule.multiplicity"
+    ));
+  }
+
+  #[test]
+  fn test_occupation_too_many_unpaired() {
+    let mut r = parse_input_str(energy_yaml()).unwrap();
+    r.molecule.multiplicity = 5;
+    assert!(occupation_from_input(&r.molecule).is_err());
+  }
+
+  fn h2_yaml(extra: &str) -> String {
+    format!(
+      "driver: energy\nmolecule:\n  symbols: [H, H]\n  geometry: [0.0, 0.0, 0.0, 0.0, 0.0, 0.74]\n{}\
+       model:\n  method: hf\n  basis: sto-3g\n",
+      extra
+    )
+  }
+
+  #[test]
+  fn test_h2_singlet_accepted() {
+    let r = parse_input_str(&h2_yaml("")).unwrap();
+    assert_eq!(occupation_from_input(&r.molecule).unwrap(), (1, 1));
+  }
+
+  #[test]
+  fn test_h2_doublet_rejected_at_parse() {
+    let err = parse_input_str(&h2_yaml("  multiplicity: 2\n")).unwrap_err();
+    assert!(matches!(
+      &err,
+      InputError::InvalidValue { field, .. } if field == "molecule.multiplicity"
+    ));
+    assert!(err.to_string().contains("multiplicity 2 is inconsistent with 2 electrons"));
+  }
+
+  #[test]
+  fn test_cation_doublet_accepted() {
+    let r = parse_input_str(&h2_yaml("  charge: 1\n  multiplicity: 2\n")).unwrap();
+    assert_eq!(occupation_from_input(&r.molecule).unwrap(), (1, 0));
+  }
+
+  #[test]
+  fn test_cation_singlet_rejected_at_parse() {
+    assert!(matches!(
+      parse_input_str(&h2_yaml("  charge: 1\n")),
+      Err(InputError::InvalidValue { field, .. }) if field == "molecule.multiplicity"
+    ));
+  }
+
+  fn oh_yaml(extra: &str) -> String {
+    format!(
+      "driver: energy\nmolecule:\n  symbols: [O, H]\n  geometry: [0.0, 0.0, 0.0, 0.0, 0.0, 0.97]\n{}\
+       model:\n  method: hf\n  basis: sto-3g\n",
+      extra
+    )
+  }
+
+  #[test]
+  fn test_electron_counts_water() {
+    let r = parse_input_str(&water_md_yaml()).unwrap();
+    assert_eq!(r.electron_counts().unwrap(), (5, 5));
+  }
+
+  #[test]
+  fn test_electron_counts_hydroxyl_radical_and_anion() {
+    let radical = parse_input_str(&oh_yaml("  multiplicity: 2\n")).unwrap();
+    assert_eq!(radical.electron_counts().unwrap(), (5, 4));
+    let anion = parse_input_str(&oh_yaml("  charge: -1\n")).unwrap();
+    assert_eq!(anion.electron_counts().unwrap(), (5, 5));
+  }
+
+  #[test]
+  fn test_electron_counts_inconsistent() {
+    // The parser rejects this combination; build it by hand.
+    let mut r = parse_input_str(&oh_yaml("  multiplicity: 2\n")).unwrap();
+    r.molecule.multiplicity = 1;
+    assert!(matches!(
+      r.electron_counts(),
+      Err(InputError::InvalidValue { field, .. }) if field == "molecule.multiplicity"
+    ));
+    r.molecule.multiplicity = 12;
+    assert!(r.electron_counts().is_err());
+  }
+
+  #[test]
+  fn test_ideal_s_squared_triplet() {
+    assert_eq!(ideal_s_squared(5, 3), 2.0);
+  }
+
+  #[test]
+  fn test_ideal_s_squared_singlet_doublet_and_symmetry() {
+    assert_eq!(ideal_s_squared(4, 4), 0.0);
+    assert_eq!(ideal_s_squared(1, 0), 0.75);
+    assert_eq!(ideal_s_squared(0, 1), 0.75);
+  }
+
+  // ── XYZ files ────────────────────────────────────────────────────────────────
+
+  const WATER_XYZ: &str = "3\nwater\nO 0.0 0.0 0.0\nH 0.0 0.0 0.96\nH 0.93 0.0 -0.24\n";
+
+  #[test]
+  fn test_xyz_str_angstrom_default() {
+    let g = parse_xyz_str(WATER_XYZ, BOHR).unwrap();
+    assert_eq!(g.symbols, vec!["O", "H", "H"]);
+    assert!(approx(g.z[1], 0.96 * BOHR));
+    assert!(approx(g.x[2], 0.93 * BOHR));
+  }
+
+  #[test]
+  fn test_xyz_str_comment_units_bohr_overrides() {
+    let xyz = "2\nLattice=\"10 0 0 0 10 0 0 0 10\" units=bohr\nH 0.0 0.0 0.0\nH 0.0 0.0 1.4\n";
+    let g = parse_xyz_str(xyz, BOHR).unwrap();
+    assert!(approx(g.z[1], 1.4));
+  }
+
+  #[test]
+  fn test_xyz_str_comment_units_angstrom_overrides() {
+    let xyz = "1\nunits=angstrom\nH 0.0 0.0 1.0\n";
+    let g = parse_xyz_str(xyz, 1.0).unwrap();
+    assert!(approx(g.z[0], BOHR));
+  }
+
+  #[test]
+  fn test_xyz_str_malformed_units_token_ignored() {
+    let xyz = "1\nunits=parsec\nH 0.0 0.0 1.0\n";
+    let g = parse_xyz_str(xyz, 1.0).unwrap();
+    assert!(approx(g.z[0], 1.0));
+  }
+
+  #[test]
+  fn test_xyz_str_bad_coordinate() {
+    let xyz = "1\n\nH 0.0 abc 1.0\n";
+    assert!(matches!(
+      parse_xyz_str(xyz, 1.0),
+      Err(InputError::InvalidXyz { line: 3, .. })
+    ));
+  }
+
+  #[test]
+  fn test_xyz_file_in_molecule_block() {
+    let mut f = tempfile::NamedTempFile::new().unwrap();
+    f.write_all(WATER_XYZ.as_bytes()).unwrap();
+    let yaml = format!(
+      "driver: energy\n\
+       molecule:\n\
+       \x20 xyz_file: {}\n\
+       model:\n\
+       \x20 method: hf\n\
+       \x20 basis: sto-3g\n",
+      f.path().display()
+    );
+    let r = parse_input_str(&yaml).unwrap();
+    match r.molecule.geometry {
+      Geometry::Cartesian(g) => assert_eq!(g.symbols.len(), 3),
+      _ => panic!("expected Cartesian geometry"),
+    }
+  }
+
+  #[test]
+  fn test_xyz_file_with_inline_geometry_is_ambiguous() {
+    let yaml = energy_yaml().replace("molecule:\n", "molecule:\n  xyz_file: mol.xyz\n");
+    assert!(matches!(
+      parse_input_str(&yaml),
+      Err(InputError::AmbiguousGeometry { .. })
+    ));
+  }
+
+  fn xyz_file_yaml(path: &Path, extra: &str) -> String {
+    format!(
+      "driver: energy\n\
+       molecule:\n\
+       \x20 xyz_file: {}\n{}\
+       model:\n\
+       \x20 method: hf\n\
+       \x20 basis: sto-3g\n",
+      path.display(),
+      extra
+    )
+  }
+
+  #[test]
+  fn test_xyz_comment_sets_charge_and_multiplicity() {
+    let mut f = tempfile::NamedTempFile::new().unwrap();
+    f.write_all(b"3\n-1 2\nO 0.0 0.0 0.0\nH 0.0 0.0 0.96\nH 0.93 0.0 -0.24\n").unwrap();
+    let r = parse_input_str(&xyz_file_yaml(f.path(), "")).unwrap();
+    assert_eq!(r.molecule.charge, -1);
+    assert_eq!(r.molecule.multiplicity, 2);
+  }
+
+  #[test]
+  fn test_yaml_overrides_xyz_comment_charge_and_multiplicity() {
+    let mut f = tempfile::NamedTempFile::new().unwrap();
+    f.write_all(b"3\n-1 2\nO 0.0 0.0 0.0\nH 0.0 0.0 0.96\nH 0.93 0.0 -0.24\n").unwrap();
+    let extra = "  charge: 1\n  multiplicity: 2\n";
+    let r = parse_input_str(&xyz_file_yaml(f.path(), extra)).unwrap();
+    assert_eq!(r.molecule.charge, 1);
+    assert_eq!(r.molecule.multiplicity, 2);
+  }
+
+  #[test]
+  fn test_xyz_comment_without_charge_pair_uses_defaults() {
+    let mut f = tempfile::NamedTempFile::new().unwrap();
+    f.write_all(WATER_XYZ.as_bytes()).unwrap();
+    let r = parse_input_str(&xyz_file_yaml(f.path(), "")).unwrap();
+    assert_eq!(r.molecule.charge, 0);
+    assert_eq!(r.molecule.multiplicity, 1);
+  }
+
+  // ── Warnings ─────────────────────────────────────────────────────────────────
+
+  #[test]
+  fn test_no_warnings_for_current_keywords() {
+    let (_, warnings) = parse_input_str_with_warnings(md_yaml()).unwrap();
+    assert!(warnings.is_empty());
+  }
+
+  #[test]
+  fn test_velocity_rescaling_at_zero_kelvin_warns() {
+    let yaml = format!("{}  thermostat: velocity_rescaling\n  temperature_k: 0\n", md_yaml());
+    let (r, warnings) = parse_input_str_with_warnings(&yaml).unwrap();
+    assert_eq!(r.keywords.unwrap().thermostat, Thermostat::VelocityRescaling);
+    assert_eq!(
+      warnings,
+      vec!["temperature_k is 0 with a velocity_rescaling thermostat — no rescaling will occur"]
+    );
+    assert!(parse_input_str(&yaml).is_ok());
+  }
+
+  #[test]
+  fn test_velocity_rescaling_at_finite_temperature_does_not_warn() {
+    let yaml = format!("{}  thermostat: velocity_rescaling\n  temperature_k: 300\n", md_yaml());
+    assert!(parse_input_str_with_warnings(&yaml).unwrap().1.is_empty());
+  }
+
+  #[test]
+  fn test_deprecated_keyword_honored_with_one_warning() {
+    let yaml = format!("{}  temperature: 300.0\n", md_yaml());
+    let (r, warnings) = parse_input_str_with_warnings(&yaml).unwrap();
+    assert_eq!(r.keywords.unwrap().temperature_k, 300.0);
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("temperature_k"));
+  }
+
+  #[test]
+  fn test_deprecated_and_current_keyword_together_rejected() {
+    let yaml = format!("{}  temperature: 300.0\n  temperature_k: 310.0\n", md_yaml());
+    assert!(matches!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue { field, .. }) if field == "keywords.temperature"
+    ));
+  }
+
+  // ── Center of charge ─────────────────────────────────────────────────────────
+
+  #[test]
+  fn test_center_of_charge_heteronuclear_diatomic() {
+    let g = CartesianGeometry {
+      symbols: vec!["H".to_string(), "F".to_string()],
+      labels: vec!["H".to_string(), "F".to_string()],
+      x: vec![0.0, 0.0],
+      y: vec![0.0, 0.0],
+      z: vec![0.0, 1.7],
+      is_ghost: vec![false; 2],
+    };
+    let (x, y, z) = center_of_charge(&g);
+    assert!(approx(x, 0.0) && approx(y, 0.0));
+    // (1·0 + 9·1.7) / 10, much closer to F than to H.
+    assert!(approx(z, 1.53));
+    assert!(z > 0.85);
+  }
+
+  #[test]
+  fn test_center_of_charge_homonuclear_midpoint() {
+    let g = CartesianGeometry {
+      symbols: vec!["N".to_string(), "N".to_string()],
+      labels: vec!["N".to_string(), "N".to_string()],
+      x: vec![-1.0, 1.0],
+      y: vec![2.0, 2.0],
+      z: vec![0.0, 0.0],
+      is_ghost: vec![false; 2],
+    };
+    let (x, y, z) = center_of_charge(&g);
+    assert!(approx(x, 0.0) && approx(y, 2.0) && approx(z, 0.0));
+  }
+
+  // ── Explicit electron count ──────────────────────────────────────────────────
+
+  #[test]
+  fn test_explicit_n_electrons_drives_occupation() {
+    // 16 electrons from the nuclei, but an ECP-style count of 10 is requested.
+    let yaml = zmat_energy_yaml().replace("molecule:\n", "molecule:\n  n_electrons: 10\n");
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(r.molecule.n_electrons, Some(10));
+    assert_eq!(electron_count(&r.molecule).unwrap(), 10);
+    assert_eq!(occupation_from_input(&r.molecule).unwrap(), (5, 5));
+  }
+
+  #[test]
+  fn test_explicit_n_electrons_parity_mismatch() {
+    let yaml = zmat_energy_yaml().replace("molecule:\n", "molecule:\n  n_electrons: 9\n");
+    assert!(matches!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue { field, .. }) if field == "molecule.multiplicity"
+    ));
+  }
+
+  #[test]
+  fn test_explicit_spin_counts_override_multiplicity() {
+    // Multiplicity defaults to 1, but both He electrons are placed in alpha.
+    let yaml = energy_yaml().replace("molecule:\n", "molecule:\n  n_alpha: 2\n  n_beta: 0\n");
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(r.molecule.n_alpha_beta, Some((2, 0)));
+    assert_eq!(occupation_from_input(&r.molecule).unwrap(), (2, 0));
+    assert_eq!(r.model.reference, Reference::Uhf);
+  }
+
+  #[test]
+  fn test_explicit_unequal_spin_counts_reject_rhf() {
+    let yaml = energy_yaml()
+      .replace("molecule:\n", "molecule:\n  n_alpha: 2\n  n_beta: 0\n")
+      .replace("  basis: sto-3g\n", "  basis: sto-3g\n  reference: rhf\n");
+    assert_eq!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue {
+        field: "model.reference".to_string(),
+        reason: "rhf requires a closed shell, got n_alpha 2 and n_beta 0".to_string(),
+      })
+    );
+  }
+
+  #[test]
+  fn test_explicit_equal_spin_counts_default_to_rhf() {
+    // Equal counts are closed shell even when the multiplicity says otherwise.
+    let yaml = zmat_energy_yaml()
+      .replace("molecule:\n", "molecule:\n  multiplicity: 3\n  n_alpha: 8\n  n_beta: 8\n");
+    assert_eq!(parse_input_str(&yaml).unwrap().model.reference, Reference::Rhf);
+  }
+
+  #[test]
+  fn test_explicit_spin_counts_follow_n_electrons() {
+    let yaml = zmat_energy_yaml()
+      .replace("molecule:\n", "molecule:\n  n_electrons: 10\n  n_alpha: 6\n  n_beta: 4\n");
+    assert_eq!(parse_input_str(&yaml).unwrap().electron_counts().unwrap(), (6, 4));
+  }
+
+  #[test]
+  fn test_explicit_spin_counts_sum_mismatch_rejected() {
+    let yaml = energy_yaml().replace("molecule:\n", "molecule:\n  n_alpha: 2\n  n_beta: 1\n");
+    assert_eq!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue {
+        field: "molecule.n_alpha".to_string(),
+        reason: "n_alpha + n_beta = 2 + 1 does not match 2 electrons".to_string(),
+      })
+    );
+  }
+
+  #[test]
+  fn test_explicit_spin_counts_need_both() {
+    let yaml = energy_yaml().replace("molecule:\n", "molecule:\n  n_alpha: 1\n");
+    assert_eq!(
+      parse_input_str(&yaml),
+      Err(InputError::MissingField("molecule.n_beta".to_string()))
+    );
+    let yaml = energy_yaml().replace("molecule:\n", "molecule:\n  n_beta: 1\n");
+    assert_eq!(
+      parse_input_str(&yaml),
+      Err(InputError::MissingField("molecule.n_alpha".to_string()))
+    );
+  }
+
+  #[test]
+  fn test_negative_spin_count_rejected() {
+    let yaml = energy_yaml().replace("molecule:\n", "molecule:\n  n_alpha: -1\n  n_beta: 3\n");
+    assert!(matches!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue { field, .. }) if field == "molecule.n_alpha"
+    ));
+  }
+
+  #[test]
+  fn test_negative_n_electrons_rejected() {
+    let yaml = energy_yaml().replace("molecule:\n", "molecule:\n  n_electrons: -2\n");
+    assert!(matches!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue { field, .. }) if field == "molecule.n_electrons"
+    ));
+  }
+
+  // ── Nuclei list ──────────────────────────────────────────────────────────────
+
+  #[test]
+  fn test_nuclei_list_cartesian_water() {
+    let yaml = "driver: energy\n\
+      molecule:\n\
+      \x20 symbols: [O, H, H]\n\
+      \x20 geometry: [0.0, 0.0, 0.0,  0.0, 0.0, 0.96,  0.93, 0.0, -0.24]\n\
+      model:\n\
+      \x20 method: hf\n\
+      \x20 basis: sto-3g\n";
+    let r = parse_input_str(yaml).unwrap();
+    let nuclei = nuclei_list(&r.molecule, None);
+    assert_eq!(nuclei.len(), 3);
+    assert_eq!(nuclei[0].0, 8.0);
+    assert_eq!(nuclei[1].0, 1.0);
+    assert_eq!(nuclei[2].0, 1.0);
+    assert!(approx(nuclei[1].3, 0.96 * BOHR));
+    assert!(approx(nuclei[2].1, 0.93 * BOHR));
+    assert!(approx(nuclei[2].3, -0.24 * BOHR));
+  }
+
+  #[test]
+  fn test_nuclei_list_zmatrix_converted_to_bohr() {
+    let r = parse_input_str(zmat_energy_yaml()).unwrap();
+    let nuclei = nuclei_list(&r.molecule, None);
+    assert_eq!(nuclei.len(), 4);
+    assert_eq!(nuclei[3].0, 6.0);
+    // Second atom sits on +z at the O–H bond length.
+    assert!(approx(nuclei[1].3, 0.96 * BOHR));
+    let (_, x, y, z) = nuclei[3];
+    assert!(approx((x * x + y * y + z * z).sqrt(), 1.5 * BOHR));
+  }
+
+  #[test]
+  fn test_nuclei_list_subtracts_ecp_core() {
+    let r = parse_input_str(zmat_energy_yaml()).unwrap();
+    let core = HashMap::from([(3, 2)]);
+    let nuclei = nuclei_list(&r.molecule, Some(&core));
+    assert_eq!(nuclei.iter().map(|n| n.0).collect::<Vec<_>>(), vec![8.0, 1.0, 1.0, 4.0]);
+  }
+
+  // ── Parse options ────────────────────────────────────────────────────────────
+
+  fn zmat_with_dihedral(d: &str) -> String {
+    zmat_energy_yaml().replace("dihedral: 120.0", &format!("dihedral: {}", d))
+  }
+
+  #[test]
+  fn test_wrap_dihedrals_270_becomes_minus_90() {
+    let opts = ParseOptions { wrap_dihedrals: true };
+    let r = parse_input_str_with_options(&zmat_with_dihedral("270.0"), &opts).unwrap();
+    match r.molecule.geometry {
+      Geometry::ZMatrix(g) => assert!(approx(g.dihedrals_deg[3].unwrap(), -90.0)),
+      _ => panic!("expected Z-matrix geometry"),
+    }
+  }
+
+  #[test]
+  fn test_wrap_dihedrals_negative_out_of_range() {
+    let opts = ParseOptions { wrap_dihedrals: true };
+    let r = parse_input_str_with_options(&zmat_with_dihedral("-540.0"), &opts).unwrap();
+    match r.molecule.geometry {
+      Geometry::ZMatrix(g) => assert!(approx(g.dihedrals_deg[3].unwrap(), -180.0)),
+      _ => panic!("expected Z-matrix geometry"),
+    }
+  }
+
+  #[test]
+  fn test_strict_dihedrals_reject_270() {
+    let yaml = zmat_with_dihedral("270.0");
+    let opts = ParseOptions::default();
+    assert!(matches!(
+      parse_input_str_with_options(&yaml, &opts),
+      Err(InputError::InvalidZMatrix { row: 3, .. })
+    ));
+    assert!(parse_input_str(&yaml).is_err());
+  }
+
+  // ── Unique elements ──────────────────────────────────────────────────────────
+
+  #[test]
+  fn test_unique_elements_first_occurrence_order() {
+    let symbols = ["O", "H", "H", "C", "H"];
+    let g = CartesianGeometry {
+      symbols: symbols.iter().map(|s| s.to_string()).collect(),
+      labels: symbols.iter().map(|s| s.to_string()).collect(),
+      x: vec![0.0; 5],
+      y: vec![0.0; 5],
+      z: vec![0.0; 5],
+      is_ghost: vec![false; 5],
+    };
+    assert_eq!(g.unique_elements(), vec!["O", "H", "C"]);
+  }
+
+  // ── Atomic masses ────────────────────────────────────────────────────────────
+
+  #[test]
+  fn test_atomic_mass_common_elements() {
+    assert!((atomic_mass("H").unwrap() - 1.008).abs() < 1e-3);
+    assert!((atomic_mass("C").unwrap() - 12.011).abs() < 1e-3);
+    assert_eq!(atomic_mass("fe"), atomic_mass("Fe"));
+  }
+
+  #[test]
+  fn test_atomic_mass_unknown_symbol() {
+    assert_eq!(atomic_mass("Xx"), None);
+    assert_eq!(atomic_mass(""), None);
+  }
+
+  #[test]
+  fn test_atomic_mass_table_covers_every_element() {
+    assert_eq!(ATOMIC_MASSES.len(), ELEMENTS.len());
+    assert!(ATOMIC_MASSES.iter().all(|&m| m > 0.0));
+  }
+
+  // ── Atomic numbers ───────────────────────────────────────────────────────────
+
+  #[test]
+  fn test_atomic_numbers_water() {
+    let r = parse_input_str(&water_md_yaml()).unwrap();
+    let Geometry::Cartesian(g) = &r.molecule.geometry else { panic!("expected Cartesian") };
+    assert_eq!(g.atomic_numbers(), vec![8, 1, 1]);
+  }
+
+  #[test]
+  fn test_atomic_numbers_ghost_is_zero() {
+    let symbols = ["O", "O", "H"];
+    let g = CartesianGeometry {
+      symbols: symbols.iter().map(|s| s.to_string()).collect(),
+      labels: symbols.iter().map(|s| s.to_string()).collect(),
+      x: vec![0.0; 3],
+      y: vec![0.0; 3],
+      z: vec![0.0; 3],
+      is_ghost: vec![false, true, false],
+    };
+    assert_eq!(g.atomic_numbers(), vec![8, 0, 1]);
+  }
+
+  // ── SCF reference ────────────────────────────────────────────────────────────
+
+  #[test]
+  fn test_reference_defaults_follow_multiplicity() {
+    let r = parse_input_str(zmat_energy_yaml()).unwrap();
+    assert_eq!(r.model.reference, Reference::Rhf);
+    let yaml = energy_yaml()
+      .replace("[He]", "[H]")
+      .replace("molecule:\n", "molecule:\n  multiplicity: 2\n");
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(r.model.reference, Reference::Uhf);
+  }
+
+  #[test]
+  fn test_explicit_uhf_on_singlet() {
+    let yaml = zmat_energy_yaml().replace("  basis: sto-3g\n", "  basis: sto-3g\n  reference: uhf\n");
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(r.molecule.multiplicity, 1);
+    assert_eq!(r.model.reference, Reference::Uhf);
+  }
+
+  #[test]
+  fn test_rhf_on_doublet_rejected() {
+    let yaml = energy_yaml()
+      .replace("[He]", "[H]")
+      .replace("molecule:\n", "molecule:\n  multiplicity: 2\n")
+      .replace("  basis: sto-3g\n", "  basis: sto-3g\n  reference: rhf\n");
+    assert!(matches!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue { field, .. }) if field == "model.reference"
+    ));
+  }
+
+  #[test]
+  fn test_basis_file_parsed() {
+    let yaml = energy_yaml().replace("  basis: sto-3g\n", "  basis: sto-3g\n  basis_file: local/basis.json\n");
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(r.model.basis_file.as_deref(), Some("local/basis.json"));
+    assert_eq!(parse_input_str(energy_yaml()).unwrap().model.basis_file, None);
+  }
+
+  #[test]
+  fn test_basis_overrides_parsed() {
+    let yaml = energy_yaml().replace(
+      "  basis: sto-3g\n",
+      "  basis: def2-svp\n  basis_overrides: {fe: def2-tzvp, Cu: def2-qzvp}\n",
+    );
+    let r = parse_input_str(&yaml).unwrap();
+    let expected: BTreeMap<String, String> = [
+      ("Cu".to_string(), "def2-qzvp".to_string()),
+      ("Fe".to_string(), "def2-tzvp".to_string()),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(r.model.basis_overrides, expected);
+    assert_eq!(r.model.basis, "def2-svp");
+    assert!(parse_input_str(energy_yaml()).unwrap().model.basis_overrides.is_empty());
+  }
+
+  #[test]
+  fn test_basis_overrides_unknown_element() {
+    let yaml = energy_yaml()
+      .replace("  basis: sto-3g\n", "  basis: sto-3g\n  basis_overrides: {Xx: def2-svp}\n");
+    assert_eq!(parse_input_str(&yaml), Err(InputError::InvalidElement("Xx".to_string())));
+  }
+
+  #[test]
+  fn test_basis_overrides_reject_ghost_keys() {
+    let yaml = energy_yaml()
+      .replace("  basis: sto-3g\n", "  basis: sto-3g\n  basis_overrides: {\"@O\": def2-svp}\n");
+    assert_eq!(parse_input_str(&yaml), Err(InputError::InvalidElement("@O".to_string())));
+  }
+
+  #[test]
+  fn test_basis_overrides_invalid_values() {
+    for value in ["def2-svp", "{Fe: \"\"}", "{Fe: [a]}", "{Fe: a, fe: b}"] {
+      let yaml = energy_yaml()
+        .replace("  basis: sto-3g\n", &format!("  basis: sto-3g\n  basis_overrides: {}\n", value));
+      assert!(
+        matches!(
+          parse_input_str(&yaml),
+          Err(InputError::InvalidValue { ref field, .. }) if field == "model.basis_overrides"
+        ),
+        "value {}",
+        value
+      );
+    }
+  }
+
+  #[test]
+  fn test_basis_file_must_be_nonempty_string() {
+    for value in ["[a, b]", "\"\""] {
+      let yaml = energy_yaml()
+        .replace("  basis: sto-3g\n", &format!("  basis: sto-3g\n  basis_file: {}\n", value));
+      assert!(matches!(
+        parse_input_str(&yaml),
+        Err(InputError::InvalidValue { field, .. }) if field == "model.basis_file"
+      ));
+    }
+  }
+
+  #[test]
+  fn test_unrecognised_reference() {
+    let yaml = energy_yaml().replace("  basis: sto-3g\n", "  basis: sto-3g\n  reference: gvb\n");
+    assert!(matches!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue { field, .. }) if field == "model.reference"
+    ));
+  }
+
+  // ── SCF keywords ─────────────────────────────────────────────────────────────
+
+  fn energy_with_keywords(kw: &str) -> String {
+    format!("{}keywords:\n{}", energy_yaml(), kw)
+  }
+
+  #[test]
+  fn test_scf_defaults_without_keywords() {
+    let r = parse_input_str(energy_yaml()).unwrap();
+    assert_eq!(
+      r.scf,
+      Some(ScfKeywords {
+        max_iterations: 128,
+        integral_threshold: 1e-12,
+        energy_tol: 1e-8,
+        density_tol: 1e-6,
+        diis: true,
+      })
+    );
+  }
+
+  #[test]
+  fn test_scf_absent_for_md_driver() {
+    let r = parse_input_str(md_yaml()).unwrap();
+    assert_eq!(r.scf, Option::None);
+  }
+
+  #[test]
+  fn test_scf_iteration_aliases_populate_same_field() {
+    for name in ["max_iterations", "max_iter", "max_scf_cycles"] {
+      let yaml = energy_with_keywords(&format!("  {}: 50\n", name));
+      let (r, warnings) = parse_input_str_with_warnings(&yaml).unwrap();
+      assert_eq!(r.scf.unwrap().max_iterations, 50, "alias {}", name);
+      assert!(warnings.is_empty());
+    }
+  }
+
+  #[test]
+  fn test_scf_conflicting_aliases_rejected() {
+    let yaml = energy_with_keywords("  max_iter: 50\n  max_scf_cycles: 60\n");
+    assert!(matches!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue { field, .. }) if field == "keywords.max_iterations"
+    ));
+  }
+
+  #[test]
+  fn test_scf_zero_iterations_rejected() {
+    let yaml = energy_with_keywords("  max_iterations: 0\n");
+    assert!(parse_input_str(&yaml).is_err());
+  }
+
+  #[test]
+  fn test_scf_integral_threshold_parsed() {
+    let yaml = energy_with_keywords("  integral_threshold: 1.0e-10\n");
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(r.scf.unwrap().integral_threshold, 1e-10);
+  }
+
+  #[test]
+  fn test_scf_integral_threshold_out_of_range() {
+    for bad in ["0.0", "1.0", "-1.0e-8", "2.5"] {
+      let yaml = energy_with_keywords(&format!("  integral_threshold: {}\n", bad));
+      assert!(
+        matches!(
+          parse_input_str(&yaml),
+          Err(InputError::InvalidValue { ref field, .. }) if field == "keywords.integral_threshold"
+        ),
+        "threshold {}", bad
+      );
+    }
+  }
+
+  #[test]
+  fn test_scf_integral_threshold_not_a_number() {
+    let yaml = energy_with_keywords("  integral_threshold: tight\n");
+    assert!(matches!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue { field, .. }) if field == "keywords.integral_threshold"
+    ));
+  }
+
+  #[test]
+  fn test_scf_convergence_keywords_parsed() {
+    let yaml = energy_with_keywords("  energy_tol: 1.0e-10\n  density_tol: 1.0e-7\n  diis: false\n");
+    let scf = parse_input_str(&yaml).unwrap().scf.unwrap();
+    assert_eq!((scf.energy_tol, scf.density_tol, scf.diis), (1e-10, 1e-7, false));
+  }
+
+  #[test]
+  fn test_scf_negative_energy_tol_rejected() {
+    let yaml = energy_with_keywords("  energy_tol: -1.0e-8\n");
+    assert_eq!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue {
+        field: "keywords.energy_tol".to_string(),
+        reason: "must be > 0, got -0.00000001".to_string(),
+      })
+    );
+  }
+
+  #[test]
+  fn test_scf_zero_density_tol_rejected() {
+    let yaml = energy_with_keywords("  density_tol: 0.0\n");
+    assert!(matches!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue { field, .. }) if field == "keywords.density_tol"
+    ));
+  }
+
+  #[test]
+  fn test_scf_diis_must_be_boolean() {
+    let yaml = energy_with_keywords("  diis: maybe\n");
+    assert!(matches!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue { field, .. }) if field == "keywords.diis"
+    ));
+  }
+
+  // ── PDB files ────────────────────────────────────────────────────────────────
+
+  const WATER_PDB: &str = "\
+HEADER    WATER
+MODEL        1
+ATOM      1  O   HOH A   1       0.000   0.000   0.000  1.00  0.00           O
+HETATM    2  H1  HOH A   1       0.000   0.000   0.960  1.00  0.00
+ATOM      3  H2  HOH A   1       0.930   0.000  -0.240  1.00  0.00           H
+ENDMDL
+MODEL        2
+ATOM      1  O   HOH A   1       5.000   5.000   5.000  1.00  0.00           O
+ENDMDL
+END
+";
+
+  #[test]
+  fn test_pdb_str_first_model() {
+    let g = parse_pdb_str(WATER_PDB).unwrap();
+    assert_eq!(g.symbols, vec!["O", "H", "H"]);
+    assert!(approx(g.z[1], 0.96 * BOHR));
+    assert!(approx(g.x[2], 0.93 * BOHR));
+    assert!(approx(g.z[2], -0.24 * BOHR));
+  }
+
+  #[test]
+  fn test_pdb_str_malformed_atom_record() {
+    let pdb = "ATOM      1  O   HOH A   1       0.000   abc     0.000  1.00  0.00           O\n";
+    assert!(matches!(
+      parse_pdb_str(pdb),
+      Err(InputError::PdbParse { line: 1, .. })
+    ));
+  }
+
+  #[test]
+  fn test_pdb_str_without_atoms() {
+    assert!(matches!(
+      parse_pdb_str("HEADER    WATER\nEND\n"),
+      Err(InputError::InvalidValue { ref field, .. }) if field == "molecule.pdb_file"
+    ));
+  }
+
+  /// A HETATM record with atom `name` and no element column.
+  fn hetatm_without_element(name: &str) -> String {
+    format!("HETATM    1 {:<4} HET A   1       1.000   2.000   3.000  1.00  0.00\n", name)
+  }
+
+  #[test]
+  fn test_pdb_str_two_letter_ions_from_atom_name() {
+    for (name, element) in [("FE", "Fe"), ("CL", "Cl"), ("ZN", "Zn"), ("MG", "Mg")] {
+      let g = parse_pdb_str(&hetatm_without_element(name)).unwrap();
+      assert_eq!(g.symbols, vec![element]);
+      assert!(approx(g.y[0], 2.0 * BOHR));
+    }
+  }
+
+  #[test]
+  fn test_pdb_str_one_letter_element_after_blank_or_digit() {
+    for (name, element) in [(" CA", "C"), ("1HB", "H"), (" N", "N")] {
+      let g = parse_pdb_str(&hetatm_without_element(name)).unwrap();
+      assert_eq!(g.symbols, vec![element], "{:?}", name);
+    }
+  }
+
+  #[test]
+  fn test_pdb_file_in_molecule_block() {
+    let mut f = tempfile::NamedTempFile::new().unwrap();
+    f.write_all(WATER_PDB.as_bytes()).unwrap();
+    let yaml = energy_yaml().replace(
+      "  symbols: [He]\n  geometry: [0.0, 0.0, 0.0]\n",
+      &format!("  pdb_file: {}\n", f.path().display()),
+    );
+    let r = parse_input_str(&yaml).unwrap();
+    match r.molecule.geometry {
+      Geometry::Cartesian(g) => assert_eq!(g.symbols.len(), 3),
+      _ => panic!("expected Cartesian geometry"),
+    }
+  }
+
+  // ── Frequency keywords ───────────────────────────────────────────────────────
+
+  #[test]
+  fn test_freq_keyword_defaults() {
+    let yaml = energy_yaml().replace("driver: energy", "driver: frequency");
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(r.drivers, vec![Driver::Frequency]);
+    assert_eq!(r.freq, Some(FreqKeywords { temperature_k: 298.15, pressure_atm: 1.0 }));
+    assert!(r.scf.is_some());
+  }
+
+  #[test]
+  fn test_freq_keywords_explicit() {
+    let yaml = format!(
+      "{}keywords:\n  temperature_k: 350.0\n  pressure_atm: 2.5\n",
+      energy_yaml().replace("driver: energy", "driver: frequency")
+    );
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(r.freq, Some(FreqKeywords { temperature_k: 350.0, pressure_atm: 2.5 }));
+  }
+
+  #[test]
+  fn test_freq_zero_pressure_rejected() {
+    let yaml = format!(
+      "{}keywords:\n  pressure_atm: 0.0\n",
+      energy_yaml().replace("driver: energy", "driver: frequency")
+    );
+    assert!(matches!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue { field, .. }) if field == "keywords.pressure_atm"
+    ));
+  }
+
+  #[test]
+  fn test_freq_absent_for_other_drivers() {
+    assert_eq!(parse_input_str(energy_yaml()).unwrap().freq, Option::None);
+  }
+
+  // ── Geometry scaling ─────────────────────────────────────────────────────────
+
+  fn diatomic(a: &str, b: &str, r: f64) -> CartesianGeometry {
+    CartesianGeometry {
+      symbols: vec![a.to_string(), b.to_string()],
+      labels: vec![a.to_string(), b.to_string()],
+      x: vec![0.3, 0.3],
+      y: vec![-0.2, -0.2],
+      z: vec![1.0, 1.0 + r],
+      is_ghost: vec![false; 2],
+    }
+  }
+
+  #[test]
+  fn test_scale_h2_doubles_bond_keeps_com() {
+    let h2 = diatomic("H", "H", 1.4);
+    let scaled = scale_about_com(&h2, 2.0).unwrap();
+    assert!(approx(scaled.z[1] - scaled.z[0], 2.8));
+    let (x0, y0, z0) = center_of_mass(&h2);
+    let (x1, y1, z1) = center_of_mass(&scaled);
+    assert!(approx(x0, x1) && approx(y0, y1) && approx(z0, z1));
+  }
+
+  #[test]
+  fn test_scale_heteronuclear_about_com_not_midpoint() {
+    let co = diatomic("C", "O", 2.0);
+    let scaled = scale_about_com(&co, 0.5).unwrap();
+    let (_, _, z0) = center_of_mass(&co);
+    let (_, _, z1) = center_of_mass(&scaled);
+    assert!(approx(z0, z1));
+    // The lighter carbon moves further than the oxygen.
+    assert!((scaled.z[0] - co.z[0]).abs() > (scaled.z[1] - co.z[1]).abs());
+  }
+
+  #[test]
+  fn test_scale_rejects_non_positive_factor() {
+    let h2 = diatomic("H", "H", 1.4);
+    for f in [0.0, -1.0, f64::NAN] {
+      assert!(matches!(
+        scale_about_com(&h2, f),
+        Err(InputError::InvalidValue { field, .. }) if field == "factor"
+      ));
+    }
+  }
+
+  // ── Z-matrix to Cartesian ────────────────────────────────────────────────────
+
+  fn zmat_cartesian(yaml: &str) -> CartesianGeometry {
+    match parse_input_str(yaml).unwrap().molecule.geometry {
+      Geometry::ZMatrix(g) => zmatrix_to_cartesian(&g).unwrap(),
+      _ => panic!("expected Z-matrix geometry"),
+    }
+  }
+
+  fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-6
+  }
+
+  #[test]
+  fn test_zmatrix_to_cartesian_one_atom() {
+    let yaml = "driver: energy\n\
+      molecule:\n\
+      \x20 z_matrix:\n\
+      \x20   - symbol: O\n\
+      model:\n\
+      \x20 method: hf\n\
+      \x20 basis: sto-3g\n";
+    let g = zmat_cartesian(yaml);
+    assert_eq!(g.symbols, vec!["O"]);
+    assert_eq!((g.x[0], g.y[0], g.z[0]), (0.0, 0.0, 0.0));
+  }
+
+  #[test]
+  fn test_zmatrix_to_cartesian_two_atoms() {
+    let yaml = "driver: energy\n\
+      molecule:\n\
+      \x20 units: bohr\n\
+      \x20 z_matrix:\n\
+      \x20   - symbol: H\n\
+      \x20   - symbol: H\n\
+      \x20     bond_atom: 1\n\
+      \x20     bond_length: 1.4\n\
+      model:\n\
+      \x20 method: hf\n\
+      \x20 basis: sto-3g\n";
+    let g = zmat_cartesian(yaml);
+    assert_eq!((g.x[1], g.y[1], g.z[1]), (0.0, 0.0, 1.4));
+  }
+
+  #[test]
+  fn test_zmatrix_to_cartesian_water() {
+    let yaml = "driver: energy\n\
+      molecule:\n\
+      \x20 z_matrix:\n\
+      \x20   - symbol: O\n\
+      \x20   - symbol: H\n\
+      \x20     bond_atom: 1\n\
+      \x20     bond_length: 0.96\n\
+      \x20   - symbol: H\n\
+      \x20     bond_atom: 1\n\
+      \x20     bond_length: 0.96\n\
+      \x20     angle_atom: 2\n\
+      \x20     angle: 104.5\n\
+      model:\n\
+      \x20 method: hf\n\
+      \x20 basis: sto-3g\n";
+    let g = zmat_cartesian(yaml);
+    // r = 0.96 Å in Bohr; H2 = (r sin 104.5°, 0, r cos 104.5°).
+    let expected = [
+      (0.0, 0.0, 0.0),
+      (0.0, 0.0, 1.814136949),
+      (1.756352407, 0.0, -0.454223617),
+    ];
+    for (i, &(x, y, z)) in expected.iter().enumerate() {
+      assert!(close(g.x[i], x) && close(g.y[i], y) && close(g.z[i], z), "atom {}", i);
+    }
+  }
+
+  #[test]
+  fn test_zmatrix_to_cartesian_reproduces_internal_coordinates() {
+    let g = zmat_cartesian(zmat_energy_yaml());
+    let p = |i: usize| [g.x[i], g.y[i], g.z[i]];
+    let dot = |a: [f64; 3], b: [f64; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+    let (a, b, c, d) = (p(2), p(1), p(0), p(3));
+
+    let cd = sub(d, c);
+    assert!(close(dot(cd, cd).sqrt(), 1.5 * BOHR));
+
+    let cb = sub(b, c);
+    let angle = (dot(cd, cb) / (dot(cd, cd) * dot(cb, cb)).sqrt()).acos().to_degrees();
+    assert!(close(angle, 109.5));
+
+    // Dihedral a–b–c–d.
+    let b1 = sub(b, a);
+    let b2 = sub(c, b);
+    let b3 = sub(d, c);
+    let y = dot(b2, b2).sqrt() * dot(b1, cross(b2, b3));
+    let x = dot(cross(b1, b2), cross(b2, b3));
+    assert!(close(y.atan2(x).to_degrees(), 120.0));
+  }
+
+  // C on the origin, O on ±x and H on +z; `extra_h` adds a row referencing
+  // the collinear O–C–O.
+  fn linear_zmatrix_yaml(extra_h: &str) -> String {
+    format!(
+      "driver: energy\nmolecule:\n  units: bohr\n  z_matrix:\n\
+       \x20   - symbol: C\n\
+       \x20   - symbol: H\n\
+       \x20     bond_atom: 1\n\
+       \x20     bond_length: 2.0\n\
+       \x20   - symbol: O\n\
+       \x20     bond_atom: 1\n\
+       \x20     bond_length: 2.2\n\
+       \x20     angle_atom: 2\n\
+       \x20     angle: 90.0\n\
+       \x20   - symbol: O\n\
+       \x20     bond_atom: 1\n\
+       \x20     bond_length: 2.2\n\
+       \x20     angle_atom: 2\n\
+       \x20     angle: 90.0\n\
+       \x20     dihedral_atom: 3\n\
+       \x20     dihedral: 180.0\n\
+       \x20   - symbol: H\n\
+       \x20     bond_atom: 1\n\
+       \x20     bond_length: 2.0\n\
+       \x20     angle_atom: 3\n\
+       \x20     angle: 90.0\n\
+       \x20     dihedral_atom: {}\n\
+       \x20     dihedral: 90.0\n\
+       model:\n  method: hf\n  basis: sto-3g\n",
+      extra_h
+    )
+  }
+
+  #[test]
+  fn test_zmatrix_to_cartesian_rejects_missing_or_bad_entries() {
+    let Geometry::ZMatrix(zmat) = parse_input_str(zmat_energy_yaml()).unwrap().molecule.geometry
+    else {
+      panic!("expected Z-matrix geometry");
+    };
+    let mut missing = zmat.clone();
+    missing.angles_deg[2] = None;
+    assert_eq!(
+      zmatrix_to_cartesian(&missing),
+      Err(InputError::InvalidZMatrix { row: 2, reason: "missing required field 'angle'".to_string() })
+    );
+    let mut forward = zmat.clone();
+    forward.dihedral_atoms[3] = Some(4);
+    assert_eq!(
+      zmatrix_to_cartesian(&forward),
+      Err(InputError::InvalidZMatrix {
+        row: 3,
+        reason: "'dihedral_atom' = 4 is out of range; must be 1 to 3".to_string(),
+      })
+    );
+  }
+
+  #[test]
+  fn test_zmatrix_collinear_dihedral_references_rejected() {
+    // Dihedral 4–3–1: O(−x), O(+x) and C(origin) lie on one line.
+    assert_eq!(
+      parse_input_str(&linear_zmatrix_yaml("4")),
+      Err(InputError::InvalidZMatrix {
+        row: 4,
+        reason: "dihedral, angle and bond reference atoms are collinear".to_string(),
+      })
+    );
+  }
+
+  #[test]
+  fn test_zmatrix_non_collinear_dihedral_references_accepted() {
+    // Dihedral 2–3–1: H(+z), O(+x) and C(origin) span a plane.
+    let g = zmat_cartesian(&linear_zmatrix_yaml("2"));
+    assert!(g.x.iter().chain(&g.y).chain(&g.z).all(|c| c.is_finite()));
+    // The new H is perpendicular to both the C–O axis and the first C–H bond.
+    assert!(close(g.x[4], 0.0) && close(g.y[4].abs(), 2.0) && close(g.z[4], 0.0));
+  }
+
+  fn dummy_co2_yaml(dummy: &str) -> String {
+    format!(
+      "driver: energy\nmolecule:\n  units: bohr\n  z_matrix:\n\
+       \x20   - symbol: C\n\
+       \x20   - symbol: {}\n\
+       \x20     bond_atom: 1\n\
+       \x20     bond_length: 1.0\n\
+       \x20   - symbol: O\n\
+       \x20     bond_atom: 1\n\
+       \x20     bond_length: 2.2\n\
+       \x20     angle_atom: 2\n\
+       \x20     angle: 90.0\n\
+       \x20   - symbol: O\n\
+       \x20     bond_atom: 1\n\
+       \x20     bond_length: 2.2\n\
+       \x20     angle_atom: 2\n\
+       \x20     angle: 90.0\n\
+       \x20     dihedral_atom: 3\n\
+       \x20     dihedral: 180.0\n\
+       model:\n  method: hf\n  basis: sto-3g\n",
+      dummy
+    )
+  }
+
+  #[test]
+  fn test_zmatrix_dummy_atom_defines_linear_molecule() {
+    // The dummy sits on the axis perpendicular to O=C=O, giving both O rows a
+    // well-defined 90° angle.
+    let r = parse_input_str(&dummy_co2_yaml("X")).unwrap();
+    let Geometry::ZMatrix(z) = &r.molecule.geometry else { panic!("expected Z-matrix") };
+    assert_eq!(z.is_dummy, vec![false, true, false, false]);
+    assert_eq!(z.symbols[1], "X");
+
+    let g = zmatrix_to_cartesian(z).unwrap();
+    assert_eq!(g.symbols, vec!["C", "O", "O"]);
+    assert_eq!(g.labels, vec!["C", "O", "O"]);
+    let expected = [(0.0, 0.0, 0.0), (2.2, 0.0, 0.0), (-2.2, 0.0, 0.0)];
+    for (i, &(x, y, z)) in expected.iter().enumerate() {
+      assert!(close(g.x[i], x) && close(g.y[i], y) && close(g.z[i], z), "atom {}", i);
+    }
+    // Dummies carry no charge and count as no atom.
+    assert_eq!(electron_count(&r.molecule).unwrap(), 6 + 8 + 8);
+    assert_eq!(n_basis_functions(&r.molecule, "sto-3g"), 15);
+  }
+
+  #[test]
+  fn test_zmatrix_dummy_spellings() {
+    for dummy in ["X", "x", "Xx", "XX", "X1"] {
+      let r = parse_input_str(&dummy_co2_yaml(dummy)).unwrap();
+      let Geometry::ZMatrix(z) = &r.molecule.geometry else { panic!("expected Z-matrix") };
+      assert!(z.is_dummy[1], "{}", dummy);
+    }
+  }
+
+  #[test]
+  fn test_zmatrix_without_dummies() {
+    let r = parse_input_str(zmat_energy_yaml()).unwrap();
+    let Geometry::ZMatrix(z) = &r.molecule.geometry else { panic!("expected Z-matrix") };
+    assert!(z.is_dummy.iter().all(|d| !d));
+    assert_eq!(zmatrix_to_cartesian(z).unwrap().symbols.len(), z.symbols.len());
+  }
+
+  #[test]
+  fn test_dummy_symbol_rejected_in_cartesian_input() {
+    let yaml = energy_yaml().replace("[He]", "[X]");
+    assert_eq!(parse_input_str(&yaml), Err(InputError::InvalidElement("X".to_string())));
+  }
+
+  // ── Atom labels ──────────────────────────────────────────────────────────────
+
+  #[test]
+  fn test_labelled_symbol_resolves_and_keeps_label() {
+    let yaml = "driver: energy\n\
+      molecule:\n\
+      \x20 symbols: [C1, H23, Oα]\n\
+      \x20 geometry: [0.0, 0.0, 0.0,  0.0, 0.0, 1.0,  0.0, 1.0, 0.0]\n\
+      \x20 multiplicity: 2\n\
+      model:\n\
+      \x20 method: hf\n\
+      \x20 basis: sto-3g\n";
+    let r = parse_input_str(yaml).unwrap();
+    match r.molecule.geometry {
+      Geometry::Cartesian(g) => {
+        assert_eq!(g.symbols, vec!["C", "H", "O"]);
+        assert_eq!(g.labels, vec!["C1", "H23", "Oα"]);
+      }
+      _ => panic!("expected Cartesian geometry"),
+    }
+  }
+
+  #[test]
+  fn test_labelled_non_element_rejected() {
+    let yaml = energy_yaml().replace("symbols: [He]", "symbols: [Xy1]");
+    assert_eq!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidElement("Xy1".to_string()))
+    );
+  }
+
+  #[test]
+  fn test_bare_label_rejected() {
+    let yaml = energy_yaml().replace("symbols: [He]", "symbols: [\"12\"]");
+    assert_eq!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidElement("12".to_string()))
+    );
+  }
+
+  #[test]
+  fn test_zmatrix_labels_preserved() {
+    let yaml = zmat_energy_yaml().replace("symbol: C", "symbol: C7");
+    let r = parse_input_str(&yaml).unwrap();
+    match r.molecule.geometry {
+      Geometry::ZMatrix(g) => {
+        assert_eq!(g.symbols[3], "C");
+        assert_eq!(g.labels, vec!["O", "H", "H", "C7"]);
+      }
+      _ => panic!("expected Z-matrix geometry"),
+    }
+  }
+
+  // ── Geometry source conflicts ────────────────────────────────────────────────
+
+  #[test]
+  fn test_every_pair_of_geometry_sources_conflicts() {
+    let sources = [
+      ("symbols/geometry", "  symbols: [He]\n  geometry: [0.0, 0.0, 0.0]\n"),
+      ("z_matrix", "  z_matrix:\n    - symbol: H\n"),
+      ("xyz_file", "  xyz_file: mol.xyz\n"),
+      ("pdb_file", "  pdb_file: mol.pdb\n"),
+    ];
+    for (i, (name_a, block_a)) in sources.iter().enumerate() {
+      for (name_b, block_b) in &sources[i + 1..] {
+        let yaml = format!(
+          "driver: energy\nmolecule:\n{}{}model:\n  method: hf\n  basis: sto-3g\n",
+          block_a, block_b
+        );
+        assert_eq!(
+          parse_input_str(&yaml),
+          Err(InputError::AmbiguousGeometry {
+            sources: vec![name_a.to_string(), name_b.to_string()],
+          }),
+          "{} + {}", name_a, name_b
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn test_partial_inline_cartesian_conflicts_with_file() {
+    let yaml = "driver: energy\n\
+      molecule:\n\
+      \x20 symbols: [He]\n\
+      \x20 pdb_file: mol.pdb\n\
+      model:\n\
+      \x20 method: hf\n\
+      \x20 basis: sto-3g\n";
+    let err = parse_input_str(yaml).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "molecule block specifies more than one geometry source: symbols/geometry, pdb_file"
+    );
+  }
+
+  // ── Cost tier ────────────────────────────────────────────────────────────────
+
+  fn carbon_chain_yaml(n: usize, basis: &str) -> String {
+    let symbols = vec!["C"; n].join(", ");
+    let coords: Vec<String> = (0..n).map(|i| format!("0.0, 0.0, {:.1}", 1.5 * i as f64)).collect();
+    format!(
+      "driver: energy\nmolecule:\n  symbols: [{}]\n  geometry: [{}]\n  multiplicity: 1\n\
+       model:\n  method: hf\n  basis: {}\n",
+      symbols, coords.join(", "), basis
+    )
+  }
+
+  fn water_md_yaml() -> String {
+    md_yaml().replace(
+      "  symbols: [He]\n  geometry: [0.0, 0.0, 0.0]\n",
+      "  symbols: [O, H, H]\n  geometry: [0.0, 0.0, 0.0, 0.0, 1.4, 1.1, 0.0, -1.4, 1.1]\n",
+    )
+  }
+
+  #[test]
+  fn test_n_basis_functions_minimal_and_split_valence() {
+    let r = parse_input_str(&water_md_yaml()).unwrap();
+    // Water: O (5) + 2 H (1 each).
+    assert_eq!(n_basis_functions(&r.molecule, "sto-3g"), 7);
+    assert_eq!(n_basis_functions(&r.molecule, "6-31G"), 14);
+    assert_eq!(n_basis_functions(&r.molecule, "cc-pVTZ"), 21);
+    assert_eq!(n_basis_functions(&r.molecule, "cc-pvqz"), 28);
+  }
+
+  #[test]
+  fn test_cost_tier_small_md() {
+    // 100-step MD of a 3-atom molecule.
+    let r = parse_input_str(&water_md_yaml()).unwrap();
+    assert_eq!(r.keywords.as_ref().unwrap().n_steps, 100);
+    assert_eq!(cost_tier(&r), CostTier::Small);
+  }
+
+  #[test]
+  fn test_cost_tier_large_single_point() {
+    let r = parse_input_str(&carbon_chain_yaml(60, "cc-pvdz")).unwrap();
+    assert_eq!(cost_tier(&r), CostTier::Large);
+  }
+
+  #[test]
+  fn test_cost_tier_medium_single_point() {
+    let r = parse_input_str(&carbon_chain_yaml(20, "sto-3g")).unwrap();
+    assert_eq!(cost_tier(&r), CostTier::Medium);
+  }
+
+  #[test]
+  fn test_cost_tier_md_scales_with_steps() {
+    let yaml = water_md_yaml().replace("n_steps: 100", "n_steps: 1000000");
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(cost_tier(&r), CostTier::Medium);
+  }
+
+  #[test]
+  fn test_cost_tier_sums_driver_sequence() {
+    // 50 basis functions: one energy is Small, a 100-step optimization is not.
+    let yaml = carbon_chain_yaml(10, "sto-3g");
+    assert_eq!(cost_tier(&parse_input_str(&yaml).unwrap()), CostTier::Small);
+    let yaml = yaml.replace("driver: energy", "driver: [energy, optimize]");
+    assert_eq!(cost_tier(&parse_input_str(&yaml).unwrap()), CostTier::Medium);
+  }
+
+  // ── Clone and serde ──────────────────────────────────────────────────────────
+
+  #[test]
+  fn test_cloned_input_equals_original() {
+    let r = parse_input_str(&water_md_yaml()).unwrap();
+    let c = r.clone();
+    assert_eq!(c, r);
+    assert_eq!(c.keywords.as_ref().unwrap().thermostat, r.keywords.as_ref().unwrap().thermostat);
+  }
+
+  #[test]
+  fn test_data_carrying_enums_clone() {
+    let zmat = parse_input_str(zmat_energy_yaml()).unwrap().molecule.geometry;
+    assert_eq!(zmat.clone(), zmat);
+    let thermostat = Thermostat::VelocityRescaling;
+    assert_eq!(thermostat.clone(), Thermostat::VelocityRescaling);
+  }
+
+  #[test]
+  fn test_input_serializes_to_stable_json() {
+    let r = parse_input_str(energy_yaml()).unwrap();
+    let json = serde_json::to_string(&r).unwrap();
+    assert_eq!(
+      json,
+      "{\"molecule\":{\"geometry\":{\"Cartesian\":{\"symbols\":[\"He\"],\"labels\":[\"He\"],\
+       \"x\":[0.0],\"y\":[0.0],\"z\":[0.0],\"is_ghost\":[false]}},\"charge\":0,\
+       \"multiplicity\":1,\"n_electrons\":null,\"n_alpha_beta\":null,\"lattice\":null,\
+       \"trajectory\":null,\
+       \"masses\":null},\"model\":{\"method\":\"hf\",\
+       \"method_spec\":{\"base\":\"hf\",\"dispersion\":null,\"embedded_basis\":null},\
+       \"basis\":\"sto-3g\",\"basis_file\":null,\"basis_overrides\":{},\"reference\":\"Rhf\"},\
+       \"drivers\":[\"Energy\"],\"keywords\":null,\"scf\":{\"max_iterations\":128,\
+       \"integral_threshold\":1e-12,\"energy_tol\":1e-8,\"density_tol\":1e-6,\"diis\":true},\"freq\":null,\"opt\":null,\"point_charges\":[],\"metadata\":null,\
+       \"comment\":null}"
+    );
+    // Serializing the clone gives byte-identical output.
+    assert_eq!(serde_json::to_string(&r.clone()).unwrap(), json);
+  }
+
+  #[test]
+  fn test_input_json_round_trip() {
+    let r = parse_input_str(md_yaml()).unwrap();
+    let json = serde_json::to_string(&r).unwrap();
+    let back: SimulationInput = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, r);
+  }
+
+  // ── Nuclear repulsion ────────────────────────────────────────────────────────
+
+  #[test]
+  fn test_nuclear_repulsion_h2() {
+    assert!(approx(nuclear_repulsion(&diatomic("H", "H", 1.4)), 1.0 / 1.4));
+  }
+
+  #[test]
+  fn test_nuclear_repulsion_single_atom_is_zero() {
+    let r = parse_input_str(energy_yaml()).unwrap();
+    let Geometry::Cartesian(g) = r.molecule.geometry else { panic!("expected Cartesian") };
+    assert_eq!(nuclear_repulsion(&g), 0.0);
+  }
+
+  #[test]
+  fn test_nuclear_repulsion_uses_atomic_numbers() {
+    // HF: 1 · 9 / 1.7.
+    assert!(approx(nuclear_repulsion(&diatomic("H", "F", 1.7)), 9.0 / 1.7));
+  }
+
+  #[test]
+  fn test_nuclear_repulsion_sums_all_pairs() {
+    let g = CartesianGeometry {
+      symbols: vec!["He".to_string(); 3],
+      labels: vec!["He".to_string(); 3],
+      x: vec![0.0, 2.0, 0.0],
+      y: vec![0.0, 0.0, 2.0],
+      z: vec![0.0, 0.0, 0.0],
+      is_ghost: vec![false; 3],
+    };
+    // Two legs of 2 Bohr and a hypotenuse of 2√2, each pair Z_i Z_j = 4.
+    let expected = 4.0 / 2.0 + 4.0 / 2.0 + 4.0 / (2.0 * 2.0_f64.sqrt());
+    assert!(approx(nuclear_repulsion(&g), expected));
+  }
+
+  // ── MD velocities ────────────────────────────────────────────────────────────
+
+  #[test]
+  fn test_md_velocities_absent_by_default() {
+    let r = parse_input_str(md_yaml()).unwrap();
+    assert_eq!(r.keywords.unwrap().velocities, Option::None);
+  }
+
+  #[test]
+  fn test_md_velocities_parsed_and_converted() {
+    let yaml = format!(
+      "{}  velocities: [0.0, 0.0, 0.001, 0.0, 0.002, 0.0, -0.001, 0.0, 0.0]\n",
+      water_md_yaml()
+    );
+    let r = parse_input_str(&yaml).unwrap();
+    let v = r.keywords.unwrap().velocities.unwrap();
+    assert_eq!(v.len(), 9);
+    // Default units are Ångström, so Å/fs → Bohr/fs.
+    assert!(approx(v[2], 0.001 * BOHR));
+    assert!(approx(v[4], 0.002 * BOHR));
+    assert!(approx(v[6], -0.001 * BOHR));
+  }
+
+  #[test]
+  fn test_md_velocities_bohr_units_unscaled() {
+    let yaml = format!(
+      "{}  velocities: [0.01, 0.02, 0.03]\n",
+      md_yaml().replace("molecule:\n", "molecule:\n  units: bohr\n")
+    );
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(r.keywords.unwrap().velocities, Some(vec![0.01, 0.02, 0.03]));
+  }
+
+  #[test]
+  fn test_md_velocities_length_mismatch() {
+    let yaml = format!("{}  velocities: [0.0, 0.0, 0.0, 0.0, 0.0, 0.0]\n", water_md_yaml());
+    let err = parse_input_str(&yaml).unwrap_err();
+    assert_eq!(err, InputError::VelocityMismatch { n_atoms: 3, n_velocities: 6 });
+    assert_eq!(
+      err.to_string(),
+      "keywords.velocities has 6 components but expected 9 (3 × 3)"
+    );
+  }
+
+  #[test]
+  fn test_md_velocities_must_be_numbers() {
+    let yaml = format!("{}  velocities: [0.0, fast, 0.0]\n", md_yaml());
+    assert!(matches!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue { field, .. }) if field == "keywords.velocities"
+    ));
+  }
+
+  // ── Nosé–Hoover thermostat ───────────────────────────────────────────────────
+
+  #[test]
+  fn test_thermostat_nose_hoover_mapping() {
+    let yaml = format!("{}  thermostat: {{type: nose_hoover, tau_fs: 50.0}}\n", md_yaml());
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(r.keywords.unwrap().thermostat, Thermostat::NoseHoover { tau_fs: 50.0 });
+  }
+
+  #[test]
+  fn test_thermostat_bare_strings_still_accepted() {
+    for (name, expected) in [
+      ("none", Thermostat::None),
+      ("velocity_rescaling", Thermostat::VelocityRescaling),
+    ] {
+      let yaml = format!("{}  thermostat: {}\n", md_yaml(), name);
+      assert_eq!(parse_input_str(&yaml).unwrap().keywords.unwrap().thermostat, expected);
+    }
+  }
+
+  #[test]
+  fn test_thermostat_mapping_form_for_existing_types() {
+    let yaml = format!("{}  thermostat:\n    type: velocity_rescaling\n", md_yaml());
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(r.keywords.unwrap().thermostat, Thermostat::VelocityRescaling);
+  }
+
+  #[test]
+  fn test_thermostat_nose_hoover_non_positive_tau() {
+    for tau in ["0.0", "-10.0"] {
+      let yaml = format!("{}  thermostat: {{type: nose_hoover, tau_fs: {}}}\n", md_yaml(), tau);
+      assert!(
+        matches!(
+          parse_input_str(&yaml),
+          Err(InputError::InvalidValue { ref field, .. }) if field == "keywords.thermostat"
+        ),
+        "tau {}", tau
+      );
+    }
+  }
+
+  #[test]
+  fn test_thermostat_nose_hoover_requires_tau() {
+    for form in ["nose_hoover", "{type: nose_hoover}"] {
+      let yaml = format!("{}  thermostat: {}\n", md_yaml(), form);
+      assert!(matches!(
+        parse_input_str(&yaml),
+        Err(InputError::InvalidValue { field, .. }) if field == "keywords.thermostat"
+      ));
+    }
+  }
+
+  // ── Optimize driver ──────────────────────────────────────────────────────────
+
+  #[test]
+  fn test_optimize_without_keywords_uses_defaults() {
+    let yaml = energy_yaml().replace("energy", "optimize");
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(r.drivers, vec![Driver::Optimize]);
+    assert_eq!(
+      r.opt,
+      Some(OptKeywords { max_steps: 100, gradient_threshold: 4.5e-4, step_threshold: 1.8e-3 })
+    );
+    assert!(r.scf.is_some());
+    assert_eq!(r.keywords, Option::None);
+  }
+
+  #[test]
+  fn test_optimize_with_keywords() {
+    let yaml = energy_with_keywords(
+      "  max_steps: 50\n  gradient_threshold: 1.0e-5\n  step_threshold: 4.0e-5\n",
+    )
+    .replace("energy", "optimize");
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(
+      r.opt,
+      Some(OptKeywords { max_steps: 50, gradient_threshold: 1e-5, step_threshold: 4e-5 })
+    );
+  }
+
+  #[test]
+  fn test_optimize_rejects_non_positive_values() {
+    for kw in ["  max_steps: 0\n", "  gradient_threshold: 0.0\n", "  step_threshold: -1.0\n"] {
+      let yaml = energy_with_keywords(kw).replace("energy", "optimize");
+      assert!(matches!(
+        parse_input_str(&yaml),
+        Err(InputError::InvalidValue { .. })
+      ), "{}", kw);
+    }
+  }
+
+  #[test]
+  fn test_opt_absent_for_other_drivers() {
+    assert_eq!(parse_input_str(energy_yaml()).unwrap().opt, Option::None);
+  }
+
+  // ── Z-matrix variables ───────────────────────────────────────────────────────
+
+  fn zmat_with_variables(vars: &str) -> String {
+    format!(
+      "driver: energy\n\
+       molecule:\n\
+       \x20 z_matrix:\n\
+       \x20   - symbol: O\n\
+       \x20   - symbol: H\n\
+       \x20     bond_atom: 1\n\
+       \x20     bond_length: B1\n\
+       \x20   - symbol: H\n\
+       \x20     bond_atom: 1\n\
+       \x20     bond_length: B1\n\
+       \x20     angle_atom: 2\n\
+       \x20     angle: A1\n\
+       \x20 variables:\n{}\
+       model:\n\
+       \x20 method: hf\n\
+       \x20 basis: sto-3g\n",
+      vars
+    )
+  }
+
+  #[test]
+  fn test_zmatrix_variables_resolve() {
+    let yaml = zmat_with_variables("    B1: 0.96\n    A1: 104.5\n");
+    let r = parse_input_str(&yaml).unwrap();
+    let Geometry::ZMatrix(z) = r.molecule.geometry else { panic!("expected ZMatrix") };
+    assert!(approx(z.bond_lengths_bohr[1].unwrap(), 0.96 * BOHR));
+    assert!(approx(z.bond_lengths_bohr[2].unwrap(), 0.96 * BOHR));
+    assert!(approx(z.angles_deg[2].unwrap(), 104.5));
+  }
+
+  #[test]
+  fn test_zmatrix_variables_match_literal_values() {
+    let with_vars = parse_input_str(&zmat_with_variables("    B1: 0.96\n    A1: 104.5\n")).unwrap();
+    let literal = zmat_with_variables("    B1: 0.96\n    A1: 104.5\n")
+      .replace("bond_length: B1", "bond_length: 0.96")
+      .replace("angle: A1", "angle: 104.5");
+    assert_eq!(with_vars.molecule, parse_input_str(&literal).unwrap().molecule);
+  }
+
+  #[test]
+  fn test_zmatrix_undefined_variable() {
+    let yaml = zmat_with_variables("    B1: 0.96\n");
+    match parse_input_str(&yaml) {
+      Err(InputError::InvalidZMatrix { row, reason }) => {
+        assert_eq!(row, 2);
+        assert!(reason.contains("\"A1\""), "{}", reason);
+      }
+      other => panic!("expected InvalidZMatrix, got {:?}", other),
+    }
+  }
+
+  #[test]
+  fn test_zmatrix_variable_range_checked() {
+    let yaml = zmat_with_variables("    B1: -0.96\n    A1: 104.5\n");
+    assert!(matches!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidZMatrix { row: 1, .. })
+    ));
+  }
+
+  // ── geometry_file ────────────────────────────────────────────────────────────
+
+  fn geometry_file_yaml(path: &Path) -> String {
+    xyz_file_yaml(path, "").replace("xyz_file:", "geometry_file:")
+  }
+
+  #[test]
+  fn test_geometry_file_reads_xyz() {
+    let mut f = tempfile::NamedTempFile::new().unwrap();
+    f.write_all(WATER_XYZ.as_bytes()).unwrap();
+    let r = parse_input_str(&geometry_file_yaml(f.path())).unwrap();
+    let Geometry::Cartesian(g) = r.molecule.geometry else { panic!("expected Cartesian") };
+    assert_eq!(g.symbols, vec!["O", "H", "H"]);
+    // Rows are in Ångström.
+    assert!(approx(g.z[1], 0.96 * BOHR));
+  }
+
+  #[test]
+  fn test_geometry_file_atom_count_mismatch() {
+    let mut f = tempfile::NamedTempFile::new().unwrap();
+    f.write_all(WATER_XYZ.replacen("3", "4", 1).as_bytes()).unwrap();
+    assert!(matches!(
+      parse_input_str(&geometry_file_yaml(f.path())),
+      Err(InputError::InvalidXyz { .. })
+    ));
+  }
+
+  #[test]
+  fn test_geometry_file_missing_is_io_error() {
+    let yaml = geometry_file_yaml(Path::new("/nonexistent/electron/mol.xyz"));
+    assert!(matches!(parse_input_str(&yaml), Err(InputError::IoError(_))));
+  }
+
+  #[test]
+  fn test_geometry_file_conflicts_with_inline_and_xyz_file() {
+    let yaml = energy_yaml().replace("molecule:\n", "molecule:\n  geometry_file: mol.xyz\n");
+    assert_eq!(
+      parse_input_str(&yaml),
+      Err(InputError::AmbiguousGeometry {
+        sources: vec!["symbols/geometry".to_string(), "geometry_file".to_string()],
+      })
+    );
+    let yaml = geometry_file_yaml(Path::new("a.xyz")).replace(
+      "molecule:\n",
+      "molecule:\n  xyz_file: b.xyz\n",
+    );
+    assert_eq!(
+      parse_input_str(&yaml),
+      Err(InputError::AmbiguousGeometry {
+        sources: vec!["xyz_file".to_string(), "geometry_file".to_string()],
+      })
+    );
+  }
+
+  // ── Validate-only mode ───────────────────────────────────────────────────────
+
+  #[test]
+  fn test_validate_reports_unknown_key_and_empty_method() {
+    let yaml = energy_yaml().replace("method: hf", "method: \"\"") + "extra_key: oops\n";
+    assert_eq!(
+      validate_input_str(&yaml),
+      vec![
+        InputError::UnknownField("extra_key".to_string()),
+        InputError::InvalidValue {
+          field: "model.method".to_string(),
+          reason: "must not be empty".to_string(),
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_validate_valid_input_is_empty() {
+    assert_eq!(validate_input_str(energy_yaml()), vec![]);
+    assert_eq!(validate_input_str(md_yaml()), vec![]);
+    assert_eq!(validate_input_str(zmat_energy_yaml()), vec![]);
+  }
+
+  #[test]
+  fn test_validate_reports_each_bad_zmatrix_row() {
+    let yaml = zmat_energy_yaml()
+      .replacen("bond_length: 0.96", "bond_length: -1.0", 1)
+      .replace("dihedral_atom: 3", "dihedral_atom: 9");
+    let errors = validate_input_str(&yaml);
+    let rows: Vec<usize> = errors
+      .iter()
+      .filter_map(|e| match e {
+        InputError::InvalidZMatrix { row, .. } => Some(*row),
+        _ => Option::None,
+      })
+      .collect();
+    assert_eq!(rows, vec![1, 3], "errors were {:?}", errors);
+    assert_eq!(errors.len(), 2);
+  }
+
+  #[test]
+  fn test_validate_model_checked_despite_bad_molecule() {
+    let yaml = "driver: energy\n\
+      molecule:\n\
+      \x20 symbols: [He]\n\
+      model:\n\
+      \x20 method: hf\n\
+      \x20 reference: rhf\n";
+    assert_eq!(
+      validate_input_str(yaml),
+      vec![
+        InputError::MissingField("molecule.geometry".to_string()),
+        InputError::MissingField("model.basis".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_validate_yaml_error_short_circuits() {
+    let errors = validate_input_str("driver: [energy\nextra: 1\n");
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(errors[0], InputError::InvalidYaml(_)));
+  }
+
+  #[test]
+  fn test_validate_matches_parse_for_keyword_errors() {
+    let yaml = md_yaml().replace("n_steps: 100", "n_steps: -5");
+    let first = parse_input_str(&yaml).unwrap_err();
+    assert_eq!(validate_input_str(&yaml), vec![first]);
+  }
+
+  // ── Ghost atoms ──────────────────────────────────────────────────────────────
+
+  fn ghost_water_yaml(ghost: &str) -> String {
+    format!(
+      "driver: energy\n\
+       molecule:\n\
+       \x20 symbols: [\"{}\", H, H]\n\
+       \x20 geometry: [0.0, 0.0, 0.0, 0.0, 1.4, 1.1, 0.0, -1.4, 1.1]\n\
+       \x20 units: bohr\n\
+       model:\n\
+       \x20 method: hf\n\
+       \x20 basis: sto-3g\n",
+      ghost
+    )
+  }
+
+  #[test]
+  fn test_ghost_oxygen_parsed() {
+    for ghost in ["Gh(O)", "gh(o)", "@O"] {
+      let r = parse_input_str(&ghost_water_yaml(ghost)).unwrap();
+      match &r.molecule.geometry {
+        Geometry::Cartesian(g) => {
+          assert_eq!(g.symbols, vec!["O", "H", "H"], "{}", ghost);
+          assert_eq!(g.labels[0], ghost);
+          assert_eq!(g.is_ghost, vec![true, false, false]);
+          assert_eq!(g.atomic_numbers(), vec![0, 1, 1]);
+          assert_eq!(g.unique_elements(), vec!["O", "H"]);
+        }
+        other => panic!("expected Cartesian, got {:?}", other),
+      }
+    }
+  }
+
+  #[test]
+  fn test_ghost_has_no_charge_or_electrons() {
+    let r = parse_input_str(&ghost_water_yaml("Gh(O)")).unwrap();
+    assert_eq!(electron_count(&r.molecule), Ok(2));
+    assert_eq!(r.electron_counts(), Ok((1, 1)));
+    let nuclei = nuclei_list(&r.molecule, None);
+    assert_eq!(nuclei.iter().map(|n| n.0).collect::<Vec<_>>(), vec![1.0, 1.0]);
+    let Geometry::Cartesian(g) = &r.molecule.geometry else { unreachable!() };
+    // Only the H–H pair repels: 1 / 2.8.
+    assert!(approx(nuclear_repulsion(g), 1.0 / 2.8));
+    assert!(approx(center_of_charge(g).2, 1.1));
+  }
+
+  #[test]
+  fn test_ghost_keeps_basis_functions() {
+    let ghost = parse_input_str(&ghost_water_yaml("Gh(O)")).unwrap();
+    let real = parse_input_str(&ghost_water_yaml("O")).unwrap();
+    assert_eq!(n_basis_functions(&ghost.molecule, "sto-3g"), 7);
+    assert_eq!(n_basis_functions(&real.molecule, "sto-3g"), 7);
+  }
+
+  #[test]
+  fn test_ghost_in_xyz_and_zmatrix() {
+    let g = parse_xyz_str("2\n\n@He 0 0 0\nHe 0 0 1\n", 1.0).unwrap();
+    assert_eq!(g.is_ghost, vec![true, false]);
+    assert_eq!(g.atomic_numbers(), vec![0, 2]);
+
+    let yaml = zmat_energy_yaml().replacen("symbol: C", "symbol: Gh(C)", 1);
+    let r = parse_input_str(&yaml).unwrap();
+    // O + 2 H; the ghost carbon adds no electrons.
+    assert_eq!(electron_count(&r.molecule), Ok(10));
+    let Geometry::ZMatrix(z) = &r.molecule.geometry else { unreachable!() };
+    assert_eq!(zmatrix_to_cartesian(z).unwrap().is_ghost, vec![false, false, false, true]);
+  }
+
+  #[test]
+  fn test_malformed_ghost_rejected() {
+    for bad in ["Gh(Xx)", "Gh()", "@"] {
+      assert!(
+        matches!(parse_input_str(&ghost_water_yaml(bad)), Err(InputError::InvalidElement(_))),
+        "{}",
+        bad
+      );
+    }
+  }
+
+  // ── Lattice vectors ──────────────────────────────────────────────────────────
+
+  #[test]
+  fn test_cubic_lattice_parsed_in_bohr() {
+    let yaml = energy_yaml().replace(
+      "  geometry: [0.0, 0.0, 0.0]\n",
+      "  geometry: [0.0, 0.0, 0.0]\n  lattice: [5.0, 0, 0, 0, 5.0, 0, 0, 0, 5.0]\n",
+    );
+    let r = parse_input_str(&yaml).unwrap();
+    let l = r.molecule.lattice.expect("lattice");
+    for (i, row) in l.iter().enumerate() {
+      for (j, &c) in row.iter().enumerate() {
+        assert!(approx(c, if i == j { 5.0 * BOHR } else { 0.0 }), "l[{}][{}] = {}", i, j, c);
+      }
+    }
+    assert_eq!(parse_input_str(energy_yaml()).unwrap().molecule.lattice, None);
+  }
+
+  #[test]
+  fn test_lattice_follows_bohr_units() {
+    let yaml = energy_yaml().replace(
+      "  geometry: [0.0, 0.0, 0.0]\n",
+      "  geometry: [0.0, 0.0, 0.0]\n  units: bohr\n  lattice: [1, 2, 3, 4, 5, 6, 7, 8, 9]\n",
+    );
+    let l = parse_input_str(&yaml).unwrap().molecule.lattice.unwrap();
+    assert_eq!(l, [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]]);
+  }
+
+  #[test]
+  fn test_lattice_wrong_length() {
+    let yaml = energy_yaml().replace(
+      "  geometry: [0.0, 0.0, 0.0]\n",
+      "  geometry: [0.0, 0.0, 0.0]\n  lattice: [5.0, 0, 0, 0, 5.0, 0, 0, 0]\n",
+    );
+    assert_eq!(parse_input_str(&yaml), Err(InputError::LatticeMismatch { n_values: 8 }));
+  }
+
+  #[test]
+  fn test_lattice_non_numeric() {
+    for value in ["cubic", "[a, 0, 0, 0, 1, 0, 0, 0, 1]"] {
+      let yaml = energy_yaml().replace(
+        "  geometry: [0.0, 0.0, 0.0]\n",
+        &format!("  geometry: [0.0, 0.0, 0.0]\n  lattice: {}\n", value),
+      );
+      assert!(
+        matches!(
+          parse_input_str(&yaml),
+          Err(InputError::InvalidValue { ref field, .. }) if field == "molecule.lattice"
+        ),
+        "{}",
+        value
+      );
+    }
+  }
+
+  #[test]
+  fn test_lattice_with_zmatrix_is_ambiguous() {
+    let yaml = zmat_energy_yaml()
+      .replace("molecule:\n", "molecule:\n  lattice: [5, 0, 0, 0, 5, 0, 0, 0, 5]\n");
+    assert_eq!(
+      parse_input_str(&yaml),
+      Err(InputError::AmbiguousGeometry {
+        sources: vec!["z_matrix".to_string(), "lattice".to_string()],
+      })
+    );
+  }
+
+  // ── Fractional coordinates ───────────────────────────────────────────────────
+
+  fn fractional_yaml(geometry: &str, lattice: Option<&str>) -> String {
+    let lattice = lattice.map(|l| format!("  lattice: {}\n", l)).unwrap_or_default();
+    format!(
+      "driver: energy\n\
+       molecule:\n\
+       \x20 symbols: [He]\n\
+       \x20 geometry: {}\n\
+       \x20 units: bohr\n\
+       \x20 coordinate_type: fractional\n\
+       {}\
+       model:\n\
+       \x20 method: hf\n\
+       \x20 basis: sto-3g\n",
+      geometry, lattice
+    )
+  }
+
+  fn single_position(r: &SimulationInput) -> (f64, f64, f64) {
+    match &r.molecule.geometry {
+      Geometry::Cartesian(g) => (g.x[0], g.y[0], g.z[0]),
+      other => panic!("expected Cartesian, got {:?}", other),
+    }
+  }
+
+  #[test]
+  fn test_fractional_center_of_cubic_cell() {
+    let yaml = fractional_yaml("[0.5, 0.5, 0.5]", Some("[10, 0, 0, 0, 10, 0, 0, 0, 10]"));
+    let (x, y, z) = single_position(&parse_input_str(&yaml).unwrap());
+    assert!(approx(x, 5.0) && approx(y, 5.0) && approx(z, 5.0), "({}, {}, {})", x, y, z);
+  }
+
+  #[test]
+  fn test_fractional_uses_lattice_units() {
+    let yaml = fractional_yaml("[0.5, 0.5, 0.5]", Some("[10, 0, 0, 0, 10, 0, 0, 0, 10]"))
+      .replace("units: bohr", "units: angstrom");
+    let (x, _, _) = single_position(&parse_input_str(&yaml).unwrap());
+    assert!(approx(x, 5.0 * BOHR));
+  }
+
+  #[test]
+  fn test_fractional_non_orthogonal_cell() {
+    // Hexagonal-like cell: a = (4, 0, 0), b = (2, 3, 0), c = (0, 0, 6).
+    let yaml = fractional_yaml("[0.25, 0.5, 1.0]", Some("[4, 0, 0, 2, 3, 0, 0, 0, 6]"));
+    let (x, y, z) = single_position(&parse_input_str(&yaml).unwrap());
+    assert!(approx(x, 2.0) && approx(y, 1.5) && approx(z, 6.0), "({}, {}, {})", x, y, z);
+  }
+
+  #[test]
+  fn test_fractional_without_lattice_rejected() {
+    assert_eq!(
+      parse_input_str(&fractional_yaml("[0.5, 0.5, 0.5]", None)),
+      Err(InputError::MissingField("molecule.lattice".to_string()))
+    );
+  }
+
+  #[test]
+  fn test_coordinate_type_values() {
+    let yaml = fractional_yaml("[0.5, 0.5, 0.5]", None);
+    let cartesian = parse_input_str(&yaml.replace("fractional", "cartesian")).unwrap();
+    assert_eq!(single_position(&cartesian), (0.5, 0.5, 0.5));
+    assert!(matches!(
+      parse_input_str(&yaml.replace("fractional", "crystal")),
+      Err(InputError::InvalidValue { ref field, .. }) if field == "molecule.coordinate_type"
+    ));
+  }
+
+  // ── Trajectories ─────────────────────────────────────────────────────────────
+
+  const TWO_FRAMES: &str = "2\nframe 1\nH 0.0 0.0 0.0\nH 0.0 0.0 0.74\n\
+                            2\nframe 2\nH 0.0 0.0 0.0\nH 0.0 0.0 0.80\n";
+
+  fn trajectory_yaml(path: &Path, driver: &str) -> String {
+    format!(
+      "driver: {}\nmolecule:\n  trajectory_file: {}\nmodel:\n  method: hf\n  basis: sto-3g\n",
+      driver,
+      path.display()
+    )
+  }
+
+  fn trajectory_file(content: &str) -> tempfile::NamedTempFile {
+    let mut f = tempfile::NamedTempFile::new().unwrap();
+    f.write_all(content.as_bytes()).unwrap();
+    f
+  }
+
+  #[test]
+  fn test_two_frame_trajectory() {
+    let f = trajectory_file(TWO_FRAMES);
+    let r = parse_input_str(&trajectory_yaml(f.path(), "analysis")).unwrap();
+    assert_eq!(r.drivers, vec![Driver::Analysis]);
+    assert!(r.scf.is_none());
+    let frames = r.molecule.trajectory.as_ref().unwrap();
+    assert_eq!(frames.len(), 2);
+    assert!(approx(frames[0].z[1], 0.74 * ANGSTROM_TO_BOHR));
+    assert!(approx(frames[1].z[1], 0.80 * ANGSTROM_TO_BOHR));
+    assert_eq!(r.molecule.geometry, Geometry::Cartesian(frames[0].clone()));
+  }
+
+  #[test]
+  fn test_trajectory_blank_line_between_frames() {
+    let frames = parse_trajectory_str(&TWO_FRAMES.replacen("2\nframe 2", "\n2\nframe 2", 1), 1.0);
+    assert_eq!(frames.unwrap().len(), 2);
+  }
+
+  #[test]
+  fn test_trajectory_inconsistent_atom_count_rejected() {
+    let content = format!("{}3\nframe 3\nH 0 0 0\nH 0 0 1\nH 0 0 2\n", TWO_FRAMES);
+    assert_eq!(
+      parse_trajectory_str(&content, 1.0),
+      Err(InputError::InvalidXyz {
+        line: 9,
+        reason: "frame 3 has 3 atoms but frame 1 has 2".to_string(),
+      })
+    );
+  }
+
+  #[test]
+  fn test_trajectory_inconsistent_symbols_rejected() {
+    let content = TWO_FRAMES.replace("H 0.0 0.0 0.80", "Li 0.0 0.0 0.80");
+    assert!(matches!(
+      parse_trajectory_str(&content, 1.0),
+      Err(InputError::InvalidXyz { line: 5, .. })
+    ));
+  }
+
+  #[test]
+  fn test_trajectory_requires_analysis_driver() {
+    let f = trajectory_file(TWO_FRAMES);
+    assert!(matches!(
+      parse_input_str(&trajectory_yaml(f.path(), "energy")),
+      Err(InputError::InvalidValue { ref field, .. }) if field == "driver"
+    ));
+    assert_eq!(
+      parse_input_str(&energy_yaml().replace("driver: energy", "driver: analysis")),
+      Err(InputError::MissingField("molecule.trajectory_file".to_string()))
+    );
+  }
+
+  // ── Coincident atoms ─────────────────────────────────────────────────────────
+
+  fn pair_yaml(geometry: &str, units: &str) -> String {
+    format!(
+      "driver: energy\nmolecule:\n  symbols: [H, H]\n  geometry: {}\n  units: {}\n\
+       model:\n  method: hf\n  basis: sto-3g\n",
+      geometry, units
+    )
+  }
+
+  #[test]
+  fn test_coincident_atoms_rejected() {
+    assert_eq!(
+      parse_input_str(&pair_yaml("[0.0, 0.0, 0.0, 0.0, 0.0, 0.0]", "bohr")),
+      Err(InputError::CoincidentAtoms { i: 1, j: 2, distance: 0.0 })
+    );
+  }
+
+  #[test]
+  fn test_normal_bond_length_accepted() {
+    assert!(parse_input_str(&pair_yaml("[0.0, 0.0, 0.0, 0.0, 0.0, 0.74]", "angstrom")).is_ok());
+  }
+
+  #[test]
+  fn test_coincidence_checked_after_unit_conversion() {
+    // 0.0006 Å is 0.0011 Bohr: above the threshold only once converted.
+    assert!(parse_input_str(&pair_yaml("[0.0, 0.0, 0.0, 0.0, 0.0, 0.0006]", "angstrom")).is_ok());
+    assert!(matches!(
+      parse_input_str(&pair_yaml("[0.0, 0.0, 0.0, 0.0, 0.0, 0.0006]", "bohr")),
+      Err(InputError::CoincidentAtoms { i: 1, j: 2, .. })
+    ));
+  }
+
+  // ── Isotope masses ───────────────────────────────────────────────────────────
+
+  fn water_masses_yaml(masses: &str) -> String {
+    water_md_yaml().replace("molecule:\n", &format!("molecule:\n  masses: {}\n", masses))
+  }
+
+  #[test]
+  fn test_masses_parsed() {
+    let r = parse_input_str(&water_masses_yaml("[15.995, 2.014, 2.014]")).unwrap();
+    assert_eq!(r.molecule.masses, Some(vec![15.995, 2.014, 2.014]));
+    assert_eq!(parse_input_str(&water_md_yaml()).unwrap().molecule.masses, None);
+  }
+
+  #[test]
+  fn test_masses_wrong_length_rejected() {
+    assert_eq!(
+      parse_input_str(&water_masses_yaml("[15.995, 2.014]")),
+      Err(InputError::InvalidValue {
+        field: "molecule.masses".to_string(),
+        reason: "expected 3 masses (one per atom), got 2".to_string(),
+      })
+    );
+  }
+
+  #[test]
+  fn test_non_positive_mass_rejected() {
+    for masses in ["[15.995, 0.0, 2.014]", "[15.995, -2.014, 2.014]"] {
+      assert!(matches!(
+        parse_input_str(&water_masses_yaml(masses)),
+        Err(InputError::InvalidValue { ref field, ref reason })
+          if field == "molecule.masses" && reason.starts_with("mass of atom 2")
+      ));
+    }
+  }
+
+  #[test]
+  fn test_non_finite_mass_rejected() {
+    for masses in ["[15.995, .nan, 2.014]", "[15.995, .inf, 2.014]"] {
+      assert_eq!(
+        parse_input_str(&water_masses_yaml(masses)),
+        Err(InputError::InvalidValue {
+          field: "molecule.masses".to_string(),
+          reason: "masses must be finite numbers".to_string(),
+        }),
+        "{}",
+        masses
+      );
+    }
+  }
+
+  // ── Metadata and comment ─────────────────────────────────────────────────────
+
+  #[test]
+  fn test_comment_string_accepted() {
+    let yaml = format!("comment: exported from another tool\n{}", energy_yaml());
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(r.comment.as_deref(), Some("exported from another tool"));
+    assert_eq!(r.metadata, None);
+  }
+
+  #[test]
+  fn test_metadata_mapping_preserved() {
+    let yaml = format!("{}metadata:\n  source: qcschema\n  tags: [a, b]\n", energy_yaml());
+    let r = parse_input_str(&yaml).unwrap();
+    let expected: serde_yaml::Value =
+      serde_yaml::from_str("source: qcschema\ntags: [a, b]\n").unwrap();
+    assert_eq!(r.metadata, Some(expected));
+  }
+
+  #[test]
+  fn test_metadata_must_be_mapping() {
+    let yaml = format!("{}metadata: [a, b]\n", energy_yaml());
+    assert!(matches!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue { ref field, .. }) if field == "metadata"
+    ));
+  }
+
+  #[test]
+  fn test_unknown_top_level_key_still_rejected() {
+    let yaml = format!("comment: hi\n{}foobar: 1\n", energy_yaml());
+    assert_eq!(parse_input_str(&yaml), Err(InputError::UnknownField("foobar".to_string())));
+  }
+
+  // ── Numeric coercion ─────────────────────────────────────────────────────────
+
+  fn cartesian_of(yaml: &str) -> CartesianGeometry {
+    match parse_input_str(yaml).unwrap().molecule.geometry {
+      Geometry::Cartesian(g) => g,
+      _ => panic!("expected Cartesian geometry"),
+    }
+  }
+
+  #[test]
+  fn test_integer_geometry_accepted() {
+    let g = cartesian_of(&h2_yaml("").replace("[0.0, 0.0, 0.0, 0.0, 0.0, 0.74]", "[0, 0, 0, 0, 0, 1]"));
+    assert_eq!(g.x, vec![0.0, 0.0]);
+    assert!(close(g.z[1], ANGSTROM_TO_BOHR));
+  }
+
+  #[test]
+  fn test_scientific_notation_geometry_accepted() {
+    let g = cartesian_of(
+      &h2_yaml("").replace("[0.0, 0.0, 0.0, 0.0, 0.0, 0.74]", "[1.0e-10, 0, 0, 0, 0, 7.4E-1]"),
+    );
+    assert!(close(g.x[0], 1.0e-10 * ANGSTROM_TO_BOHR));
+    assert!(close(g.z[1], 0.74 * ANGSTROM_TO_BOHR));
+  }
+
+  #[test]
+  fn test_quoted_numeric_coordinates_rejected() {
+    let yaml = h2_yaml("").replace("[0.0, 0.0, 0.0, 0.0, 0.0, 0.74]", "[0, 0, 0, 0, 0, \"1.4e0\"]");
+    assert_eq!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue {
+        field: "molecule.geometry".to_string(),
+        reason: "coordinates must be numbers".to_string(),
+      })
+    );
+  }
+
+  #[test]
+  fn test_non_numeric_and_non_finite_coordinates_rejected() {
+    for bad in ["abc", "\"inf\"", ".nan"] {
+      let yaml = h2_yaml("").replace("0.74]", &format!("{}]", bad));
+      assert_eq!(
+        parse_input_str(&yaml),
+        Err(InputError::InvalidValue {
+          field: "molecule.geometry".to_string(),
+          reason: "coordinates must be numbers".to_string(),
+        }),
+        "{}",
+        bad
+      );
+    }
+  }
+
+  #[test]
+  fn test_zmatrix_integer_and_exponent_bond_lengths_accepted() {
+    let yaml = zmat_energy_yaml().replacen("bond_length: 0.96", "bond_length: 1", 1)
+      .replacen("bond_length: 0.96", "bond_length: 9.6e-1", 1);
+    let z = match parse_input_str(&yaml).unwrap().molecule.geometry {
+      Geometry::ZMatrix(z) => z,
+      _ => panic!("expected Z-matrix geometry"),
+    };
+    assert!(close(z.bond_lengths_bohr[1].unwrap(), ANGSTROM_TO_BOHR));
+    assert!(close(z.bond_lengths_bohr[2].unwrap(), 0.96 * ANGSTROM_TO_BOHR));
+  }
+
+  #[test]
+  fn test_zmatrix_quoted_number_is_a_variable_name() {
+    let yaml = zmat_energy_yaml().replacen("bond_length: 0.96", "bond_length: \"0.96\"", 1);
+    let err = parse_input_str(&yaml).unwrap_err();
+    assert!(err.to_string().contains("undefined variable \"0.96\""), "{}", err);
+  }
+
+  // ── Point charges ────────────────────────────────────────────────────────────
+
+  fn point_charges_yaml(entries: &str) -> String {
+    format!(
+      "driver: energy\nmolecule:\n  symbols: [H, H]\n  geometry: [0.0, 0.0, 0.0, 0.0, 0.0, 1.4]\n\
+       \x20 units: bohr\nmodel:\n  method: hf\n  basis: sto-3g\npoint_charges:\n{}",
+      entries
+    )
+  }
+
+  #[test]
+  fn test_two_point_charges_parsed() {
+    let yaml = point_charges_yaml(
+      "  - charge: -0.8\n    position: [0.0, 0.0, 5.0]\n\
+       \x20 - {charge: 0.4, position: [1, 2, 3]}\n",
+    );
+    let r = parse_input_str(&yaml).unwrap();
+    assert_eq!(
+      r.point_charges,
+      vec![
+        PointCharge { charge: -0.8, position: [0.0, 0.0, 5.0] },
+        PointCharge { charge: 0.4, position: [1.0, 2.0, 3.0] },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_point_charges_use_molecule_units() {
+    let yaml = point_charges_yaml("  - {charge: 1, position: [0, 0, 1]}\n")
+      .replace("  units: bohr\n", "");
+    let r = parse_input_str(&yaml).unwrap();
+    assert!(close(r.point_charges[0].position[2], ANGSTROM_TO_BOHR));
+  }
+
+  #[test]
+  fn test_point_charges_absent_is_empty() {
+    assert!(parse_input_str(energy_yaml()).unwrap().point_charges.is_empty());
+  }
+
+  #[test]
+  fn test_point_charge_two_element_position_rejected() {
+    let yaml = point_charges_yaml(
+      "  - {charge: 1.0, position: [0, 0, 5]}\n  - {charge: 1.0, position: [0, 0]}\n",
+    );
+    let expected = || InputError::InvalidValue {
+      field: "point_charges".to_string(),
+      reason: "point charge 2 needs a position of 3 numbers".to_string(),
+    };
+    assert_eq!(parse_input_str(&yaml), Err(expected()));
+    assert_eq!(validate_input_str(&yaml), vec![expected()]);
+  }
+
+  #[test]
+  fn test_point_charge_non_numeric_charge_rejected() {
+    let yaml = point_charges_yaml("  - {charge: big, position: [0, 0, 5]}\n");
+    assert_eq!(
+      parse_input_str(&yaml),
+      Err(InputError::InvalidValue {
+        field: "point_charges".to_string(),
+        reason: "point charge 1 needs a numeric charge".to_string(),
+      })
+    );
+  }
+
+  #[test]
+  fn test_nuclear_point_charge_energy() {
+    let g = diatomic("H", "H", 1.4);
+    let pcs = [PointCharge { charge: -0.5, position: [0.3, -0.2, 4.4] }];
+    // In line with the bond, 3.4 and 2.0 Bohr from the two nuclei.
+    assert!(approx(nuclear_point_charge_energy(&g, &pcs), -0.5 / 3.4 - 0.5 / 2.0));
+    assert_eq!(nuclear_point_charge_energy(&g, &[]), 0.0);
+  }
 }
@@ -1,47 +1,323 @@
 mod basis;
+mod elements;
 mod guess;
 mod input;
+mod molden;
 mod orbital;
+mod util;
 
+use std::io::Read;
 use std::path::Path;
 use std::process;
+use std::time::Instant;
 
-use input::{parse_input, Driver, Geometry};
+use faer::Mat;
+
+use input::{
+  parse_input, parse_input_str, validate_input_str, zmatrix_to_cartesian, Driver, Geometry, InputError,
+  SimulationInput,
+};
+use orbital::{
+  init_basis_with_file, init_basis_with_overrides, one_electron_integrals, OneElectronIntegrals,
+};
+
+/// Usage message for a malformed command line, covering every flag and the
+/// `fetch` form.
+const USAGE: &str = concat!(
+  "usage: electron [--basis NAME] [--dump-matrices DIR] [--timing] [--format text|json]\n",
+  "                [--check] <input-file | ->\n",
+  "       electron fetch <basis> <element>...",
+);
 
 /// Core CLI logic. Takes the arguments (excluding argv[0]) and returns either
 /// the success line to print on stdout, or the error message to print on stderr
 /// (without the "error: " prefix — that is added by `main`).
-fn run(args: &[String]) -> Result<String, String> {
-  if args.len() != 1 {
-    return Err("usage: electron <input-file>".to_string());
+///
+/// An input file of `-` reads the YAML from stdin instead.
+/// `--basis NAME` may appear anywhere and overrides `model.basis`.
+/// `--dump-matrices DIR` writes the one-electron matrices instead of the
+/// summary line (see `dump_matrices`).
+/// `--timing` appends one `timing: <stage> <ms> ms` line per stage to
+/// `timings`, which `main` prints to stderr so stdout is unchanged.
+/// `--format json` replaces the `Parsed: ...` line with a JSON object holding
+/// the same facts plus charge, multiplicity and geometry kind; `--format text`
+/// is the default.
+/// `--check` only validates the input, reporting every problem found (see
+/// `check_input`).
+///
+/// `fetch <basis> <element>...` instead downloads basis files into the cache
+/// without reading any input (see `fetch_command`).
+fn run(args: &[String], timings: &mut Vec<String>) -> Result<String, String> {
+  run_in(args, &basis::default_cache_root(), timings)
+}
+
+/// `run` with basis files cached under `cache_root`.
+fn run_in(args: &[String], cache_root: &Path, timings: &mut Vec<String>) -> Result<String, String> {
+  run_with_stdin(args, cache_root, std::io::stdin().lock(), timings)
+}
+
+/// `run_in` with `stdin` standing in for the process's standard input when
+/// the input file is `-`. Dispatches on the first argument: `fetch` or an
+/// input file.
+fn run_with_stdin(
+  args: &[String],
+  cache_root: &Path,
+  stdin: impl Read,
+  timings: &mut Vec<String>,
+) -> Result<String, String> {
+  match args.first().map(String::as_str) {
+    Some("fetch") => fetch_command(&args[1..], basis::BSE_BASE_URL, cache_root),
+    _ => run_file(args, cache_root, stdin, timings),
   }
+}
 
-  let path = Path::new(&args[0]);
+/// Downloads `<basis> <element>...` from the BSE at `base_url` into
+/// `cache_root` and returns the cached paths, one per line. Stops at the first
+/// element that fails, with that `BseError`'s message.
+fn fetch_command(args: &[String], base_url: &str, cache_root: &Path) -> Result<String, String> {
+  const FETCH_USAGE: &str = "usage: electron fetch <basis> <element>...";
 
-  match parse_input(path) {
-    Ok(sim) => {
-      let driver = match sim.driver {
-        Driver::Energy   => "energy",
-        Driver::Gradient => "gradient",
-        Driver::Hessian  => "hessian",
-        Driver::Md       => "md",
-      };
-      let atoms = match &sim.molecule.geometry {
-        Geometry::Cartesian(c) => c.symbols.len(),
-        Geometry::ZMatrix(z)   => z.symbols.len(),
+  let [basis_name, elements @ ..] = args else {
+    return Err(FETCH_USAGE.to_string());
+  };
+  if elements.is_empty() {
+    return Err(FETCH_USAGE.to_string());
+  }
+  let mut paths = Vec::with_capacity(elements.len());
+  for element in elements {
+    let path = basis::fetch_basis_from(element, basis_name, base_url, cache_root)
+      .map_err(|e| e.to_string())?;
+    paths.push(path.display().to_string());
+  }
+  Ok(paths.join("\n"))
+}
+
+/// The single-input-file mode of `run`.
+fn run_file(
+  args: &[String],
+  cache_root: &Path,
+  mut stdin: impl Read,
+  timings: &mut Vec<String>,
+) -> Result<String, String> {
+  let mut basis_override: Option<&str> = None;
+  let mut dump_dir: Option<&str> = None;
+  let mut timing = false;
+  let mut json = false;
+  let mut check = false;
+  let mut positional: Vec<&str> = Vec::new();
+  let mut iter = args.iter();
+  while let Some(a) = iter.next() {
+    if a == "--timing" {
+      timing = true;
+    } else if a == "--check" {
+      check = true;
+    } else if a == "--basis" {
+      let name = iter.next().ok_or_else(|| USAGE.to_string())?;
+      basis_override = Some(name);
+    } else if a == "--format" {
+      match iter.next().map(String::as_str) {
+        Some("text") => json = false,
+        Some("json") => json = true,
+        Some(other) => return Err(format!("unknown format {:?} (expected text or json)", other)),
+        None => return Err(USAGE.to_string()),
+      }
+    } else if a == "--dump-matrices" {
+      let dir = iter.next().ok_or_else(|| USAGE.to_string())?;
+      dump_dir = Some(dir);
+    } else {
+      positional.push(a);
+    }
+  }
+  if positional.len() != 1 {
+    return Err(USAGE.to_string());
+  }
+
+  let from_stdin = positional[0] == "-";
+  let path = Path::new(positional[0]);
+  if check {
+    return if from_stdin {
+      check_input("<stdin>", read_stdin(&mut stdin))
+    } else {
+      check_input(
+        &path.display().to_string(),
+        std::fs::read_to_string(path).map_err(|e| InputError::IoError(e.to_string())),
+      )
+    };
+  }
+  let mut timer = Timer { log: if timing { Some(timings) } else { None } };
+
+  let start = Instant::now();
+  let parsed = if from_stdin {
+    read_stdin(&mut stdin).and_then(|yaml| parse_input_str(&yaml))
+  } else {
+    parse_input(path)
+  };
+  timer.record("parse", start);
+  match parsed {
+    Ok(mut sim) => {
+      if let Some(name) = basis_override {
+        sim.model.basis = name.to_string();
+      }
+      if let Some(dir) = dump_dir {
+        return dump_matrices(&sim, Path::new(dir), cache_root, &mut timer);
+      }
+      let drivers: Vec<&str> = sim
+        .drivers
+        .iter()
+        .map(|d| match d {
+          Driver::Energy    => "energy",
+          Driver::Gradient  => "gradient",
+          Driver::Hessian   => "hessian",
+          Driver::Md        => "md",
+          Driver::Frequency => "frequency",
+          Driver::Optimize  => "optimize",
+          Driver::Analysis  => "analysis",
+        })
+        .collect();
+      let (atoms, kind) = match &sim.molecule.geometry {
+        Geometry::Cartesian(c) => (c.symbols.len(), "cartesian"),
+        Geometry::ZMatrix(z)   => (z.is_dummy.iter().filter(|d| !**d).count(), "zmatrix"),
       };
+      if json {
+        return Ok(
+          serde_json::json!({
+            "driver": drivers,
+            "method": sim.model.method,
+            "basis": sim.model.basis,
+            "atoms": atoms,
+            "charge": sim.molecule.charge,
+            "multiplicity": sim.molecule.multiplicity,
+            "geometry": kind,
+          })
+          .to_string(),
+        );
+      }
       Ok(format!(
         "Parsed: driver={}, method={}, basis={}, atoms={}",
-        driver, sim.model.method, sim.model.basis, atoms
+        drivers.join(","), sim.model.method, sim.model.basis, atoms
       ))
     }
     Err(e) => Err(e.to_string()),
   }
 }
 
+/// Reads all of `stdin` as the YAML input.
+fn read_stdin(stdin: &mut impl Read) -> Result<String, InputError> {
+  let mut yaml = String::new();
+  stdin.read_to_string(&mut yaml).map_err(|e| InputError::IoError(e.to_string()))?;
+  Ok(yaml)
+}
+
+/// Validates the input read from `name` without running anything. Succeeds
+/// with an `OK` line, or fails listing every problem `validate_input_str`
+/// reports, one per line.
+fn check_input(name: &str, yaml: Result<String, InputError>) -> Result<String, String> {
+  let errors = match yaml {
+    Ok(yaml) => validate_input_str(&yaml),
+    Err(e) => vec![e],
+  };
+  if errors.is_empty() {
+    return Ok(format!("OK: {} is valid", name));
+  }
+  let lines: Vec<String> = errors.iter().map(|e| format!("  {}", e)).collect();
+  Err(format!(
+    "{} problem{} in {}:\n{}",
+    errors.len(),
+    if errors.len() == 1 { "" } else { "s" },
+    name,
+    lines.join("\n")
+  ))
+}
+
+/// Builds the AO basis for a single-point input and writes S, T and V as
+/// `S.csv`, `T.csv` and `V.csv` (one matrix row per line, full precision) in
+/// `dir`, creating it if needed. Returns the line reporting the dimension.
+fn dump_matrices(
+  sim: &SimulationInput,
+  dir: &Path,
+  cache_root: &Path,
+  timer: &mut Timer,
+) -> Result<String, String> {
+  if sim.drivers.contains(&Driver::Md) {
+    return Err("--dump-matrices needs a single-point driver, not md".to_string());
+  }
+  let converted;
+  let geometry = match &sim.molecule.geometry {
+    Geometry::Cartesian(c) => c,
+    Geometry::ZMatrix(z) => {
+      converted = zmatrix_to_cartesian(z).map_err(|e| e.to_string())?;
+      &converted
+    }
+  };
+
+  let start = Instant::now();
+  let basis = match &sim.model.basis_file {
+    Some(file) => init_basis_with_file(
+      geometry,
+      &sim.model.basis,
+      &sim.model.basis_overrides,
+      Path::new(file),
+      cache_root,
+    ),
+    None => init_basis_with_overrides(
+      geometry,
+      &sim.model.basis,
+      &sim.model.basis_overrides,
+      cache_root,
+    ),
+  }
+  .map_err(|e| e.to_string())?;
+  timer.record("basis", start);
+
+  let start = Instant::now();
+  let OneElectronIntegrals { s, t, v } = one_electron_integrals(&basis, geometry);
+  let matrices = [("S", s), ("T", t), ("V", v)];
+  timer.record("integrals", start);
+
+  std::fs::create_dir_all(dir)
+    .map_err(|e| format!("cannot create {}: {}", dir.display(), e))?;
+  for (name, m) in &matrices {
+    let file = dir.join(format!("{}.csv", name));
+    std::fs::write(&file, matrix_csv(m))
+      .map_err(|e| format!("cannot write {}: {}", file.display(), e))?;
+  }
+  let n = basis.n_basis;
+  Ok(format!("Wrote S, T, V ({}×{}) to {}", n, n, dir.display()))
+}
+
+/// Per-stage wall-clock log for `--timing`; records nothing when `log` is `None`.
+struct Timer<'a> {
+  log: Option<&'a mut Vec<String>>,
+}
+
+impl Timer<'_> {
+  /// Logs the time elapsed since `start` under `stage`.
+  fn record(&mut self, stage: &str, start: Instant) {
+    if let Some(log) = self.log.as_mut() {
+      log.push(format!("timing: {} {:.3} ms", stage, start.elapsed().as_secs_f64() * 1e3));
+    }
+  }
+}
+
+/// Comma-separated rows of `m`, each value in `{:e}` form so it round-trips.
+fn matrix_csv(m: &Mat<f64>) -> String {
+  let mut out = String::new();
+  for i in 0..m.nrows() {
+    let row: Vec<String> = (0..m.ncols()).map(|j| format!("{:e}", m[(i, j)])).collect();
+    out.push_str(&row.join(","));
+    out.push('\n');
+  }
+  out
+}
+
 fn main() {
   let args: Vec<String> = std::env::args().skip(1).collect();
-  match run(&args) {
+  let mut timings = Vec::new();
+  let result = run(&args, &mut timings);
+  for line in &timings {
+    eprintln!("{}", line);
+  }
+  match result {
     Ok(msg) => println!("{}", msg),
     Err(e) => {
       eprintln!("error: {}", e);
@@ -123,7 +399,7 @@ model:
   #[test]
   fn test_cartesian_energy_file() {
     let f = temp_file(ENERGY_H2);
-    let result = run(&arg(f.path().to_str().unwrap()));
+    let result = run(&arg(f.path().to_str().unwrap()), &mut Vec::new());
     assert_eq!(
       result.unwrap(),
       "Parsed: driver=energy, method=hf, basis=sto-3g, atoms=2"
@@ -134,7 +410,7 @@ model:
   #[test]
   fn test_md_file() {
     let f = temp_file(MD_WATER);
-    let result = run(&arg(f.path().to_str().unwrap()));
+    let result = run(&arg(f.path().to_str().unwrap()), &mut Vec::new());
     assert_eq!(
       result.unwrap(),
       "Parsed: driver=md, method=b3lyp, basis=sto-3g, atoms=3"
@@ -148,7 +424,7 @@ model:
     // then pass just the filename as a relative path.
     let filename = format!("__cli_test_{}.yaml", std::process::id());
     std::fs::write(&filename, ENERGY_H2).unwrap();
-    let result = run(&arg(&filename));
+    let result = run(&arg(&filename), &mut Vec::new());
     std::fs::remove_file(&filename).ok();
     assert!(result.is_ok(), "expected Ok, got: {:?}", result);
   }
@@ -158,7 +434,7 @@ model:
   fn test_absolute_path() {
     let f = temp_file(ENERGY_H2);
     let abs = f.path().canonicalize().unwrap();
-    let result = run(&arg(abs.to_str().unwrap()));
+    let result = run(&arg(abs.to_str().unwrap()), &mut Vec::new());
     assert!(result.is_ok(), "expected Ok, got: {:?}", result);
   }
 
@@ -166,24 +442,186 @@ model:
   #[test]
   fn test_zmatrix_atom_count() {
     let f = temp_file(ZMAT_4);
-    let msg = run(&arg(f.path().to_str().unwrap())).unwrap();
+    let msg = run(&arg(f.path().to_str().unwrap()), &mut Vec::new()).unwrap();
     assert!(msg.contains("atoms=4"), "output was: {msg}");
   }
 
+  /// Scenario: --basis overrides model.basis in the summary
+  #[test]
+  fn test_basis_override() {
+    let f = temp_file(ENERGY_H2);
+    let path = f.path().to_str().unwrap().to_string();
+    let args = ["--basis".to_string(), "6-31g".to_string(), path.clone()];
+    let result = run(&args, &mut Vec::new());
+    assert_eq!(
+      result.unwrap(),
+      "Parsed: driver=energy, method=hf, basis=6-31g, atoms=2"
+    );
+    // The flag may also follow the input file.
+    let result = run(&[path, "--basis".to_string(), "cc-pvdz".to_string()], &mut Vec::new());
+    assert!(result.unwrap().contains("basis=cc-pvdz"));
+  }
+
+  /// Scenario: --format json reports the water MD sample as a JSON object
+  #[test]
+  fn test_format_json_md_water() {
+    let f = temp_file(MD_WATER);
+    let args = ["--format".to_string(), "json".to_string(), f.path().to_str().unwrap().to_string()];
+    let out = run(&args, &mut Vec::new()).unwrap();
+    let v: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(
+      v,
+      serde_json::json!({
+        "driver": ["md"],
+        "method": "b3lyp",
+        "basis": "sto-3g",
+        "atoms": 3,
+        "charge": 0,
+        "multiplicity": 1,
+        "geometry": "cartesian",
+      })
+    );
+  }
+
+  /// Scenario: --format json reports a Z-matrix geometry kind
+  #[test]
+  fn test_format_json_zmatrix() {
+    let f = temp_file(ZMAT_4);
+    let args = [f.path().to_str().unwrap().to_string(), "--format".to_string(), "json".to_string()];
+    let v: serde_json::Value = serde_json::from_str(&run(&args, &mut Vec::new()).unwrap()).unwrap();
+    assert_eq!(v["geometry"], "zmatrix");
+    assert_eq!(v["atoms"], 4);
+  }
+
+  /// Scenario: --format json lists a driver sequence as an array
+  #[test]
+  fn test_format_json_driver_sequence() {
+    let f = temp_file(&ENERGY_H2.replace("driver: energy", "driver: [optimize, frequency]"));
+    let args = [f.path().to_str().unwrap().to_string(), "--format".to_string(), "json".to_string()];
+    let v: serde_json::Value = serde_json::from_str(&run(&args, &mut Vec::new()).unwrap()).unwrap();
+    assert_eq!(v["driver"], serde_json::json!(["optimize", "frequency"]));
+  }
+
+  /// Scenario: --format text keeps the summary line; other formats are rejected
+  #[test]
+  fn test_format_text_and_unknown() {
+    let f = temp_file(ENERGY_H2);
+    let path = f.path().to_str().unwrap().to_string();
+    let args = ["--format".to_string(), "text".to_string(), path.clone()];
+    assert_eq!(
+      run(&args, &mut Vec::new()).unwrap(),
+      "Parsed: driver=energy, method=hf, basis=sto-3g, atoms=2"
+    );
+    let err = run(&["--format".to_string(), "xml".to_string(), path], &mut Vec::new()).unwrap_err();
+    assert!(err.contains("xml"), "error was: {err}");
+    let err = run(&["--format".to_string()], &mut Vec::new()).unwrap_err();
+    assert_eq!(err, USAGE);
+  }
+
+  /// Scenario: --check lists every problem in the input and fails
+  #[test]
+  fn test_check_reports_all_errors() {
+    let yaml = ENERGY_H2.replace("method: hf", "method: \"\"") + "extra_key: 1\n";
+    let f = temp_file(&yaml);
+    let path = f.path().to_str().unwrap().to_string();
+    let err = run(&["--check".to_string(), path.clone()], &mut Vec::new()).unwrap_err();
+    let lines: Vec<&str> = err.lines().collect();
+    assert_eq!(lines[0], format!("2 problems in {}:", path));
+    assert_eq!(lines[1], "  unknown top-level field: \"extra_key\"");
+    assert_eq!(lines[2], "  invalid value for model.method: must not be empty");
+  }
+
+  /// Scenario: --check on a valid input succeeds without the summary line
+  #[test]
+  fn test_check_valid_input() {
+    let f = temp_file(ENERGY_H2);
+    let path = f.path().to_str().unwrap().to_string();
+    let out = run(&[path.clone(), "--check".to_string()], &mut Vec::new()).unwrap();
+    assert_eq!(out, format!("OK: {} is valid", path));
+  }
+
+  /// Scenario: --check on a missing file reports the I/O error
+  #[test]
+  fn test_check_missing_file() {
+    let args = ["--check".to_string(), "/nonexistent/electron/input.yaml".to_string()];
+    let err = run(&args, &mut Vec::new()).unwrap_err();
+    assert!(err.starts_with("1 problem in /nonexistent/electron/input.yaml:\n  I/O error"), "{err}");
+  }
+
+  /// Scenario: Parse an optimize input
+  #[test]
+  fn test_optimize_file() {
+    let f = temp_file(&ENERGY_H2.replace("driver: energy", "driver: optimize"));
+    let result = run(&arg(f.path().to_str().unwrap()), &mut Vec::new());
+    assert_eq!(
+      result.unwrap(),
+      "Parsed: driver=optimize, method=hf, basis=sto-3g, atoms=2"
+    );
+  }
+
+  /// Scenario: A driver sequence lists every driver in order
+  #[test]
+  fn test_driver_sequence_file() {
+    let f = temp_file(&ENERGY_H2.replace("driver: energy", "driver: [optimize, frequency]"));
+    let result = run(&arg(f.path().to_str().unwrap()), &mut Vec::new());
+    assert_eq!(
+      result.unwrap(),
+      "Parsed: driver=optimize,frequency, method=hf, basis=sto-3g, atoms=2"
+    );
+  }
+
+  /// Scenario: --timing logs a parse line and leaves the stdout line unchanged
+  #[test]
+  fn test_timing_flag() {
+    let f = temp_file(ENERGY_H2);
+    let path = f.path().to_str().unwrap().to_string();
+    let mut timings = Vec::new();
+    let timed = run(&["--timing".to_string(), path.clone()], &mut timings).unwrap();
+    assert_eq!(timed, run(&arg(&path), &mut Vec::new()).unwrap());
+    assert_eq!(timings.len(), 1);
+    assert!(timings[0].starts_with("timing: parse "), "{}", timings[0]);
+    assert!(timings[0].ends_with(" ms"));
+  }
+
+  /// Scenario: Without --timing nothing is logged
+  #[test]
+  fn test_no_timing_by_default() {
+    let f = temp_file(ENERGY_H2);
+    let mut timings = Vec::new();
+    run(&arg(f.path().to_str().unwrap()), &mut timings).unwrap();
+    assert!(timings.is_empty());
+  }
+
   // ── Argument errors ─────────────────────────────────────────────────────────
 
   /// Scenario: No argument given
   #[test]
   fn test_no_arguments() {
-    let result = run(&[]);
-    assert_eq!(result.unwrap_err(), "usage: electron <input-file>");
+    let result = run(&[], &mut Vec::new());
+    assert_eq!(result.unwrap_err(), USAGE);
+  }
+
+  /// Scenario: The usage message names every flag, stdin and the fetch form
+  #[test]
+  fn test_usage_lists_flags_and_fetch() {
+    let parts = ["--basis", "--dump-matrices", "--timing", "--format", "--check", "| -", "electron fetch"];
+    for part in parts {
+      assert!(USAGE.contains(part), "usage lacks {part}");
+    }
   }
 
   /// Scenario: More than one argument given
   #[test]
   fn test_too_many_arguments() {
-    let result = run(&["a.yaml".to_string(), "b.yaml".to_string()]);
-    assert_eq!(result.unwrap_err(), "usage: electron <input-file>");
+    let result = run(&["a.yaml".to_string(), "b.yaml".to_string()], &mut Vec::new());
+    assert_eq!(result.unwrap_err(), USAGE);
+  }
+
+  /// Scenario: --basis without a name
+  #[test]
+  fn test_basis_flag_missing_name() {
+    let result = run(&["a.yaml".to_string(), "--basis".to_string()], &mut Vec::new());
+    assert_eq!(result.unwrap_err(), USAGE);
   }
 
   // ── File and parse errors ───────────────────────────────────────────────────
@@ -191,7 +629,7 @@ model:
   /// Scenario: Input file does not exist
   #[test]
   fn test_missing_file() {
-    let result = run(&arg("/tmp/nonexistent_electron_cli_test.yaml"));
+    let result = run(&arg("/tmp/nonexistent_electron_cli_test.yaml"), &mut Vec::new());
     assert!(result.is_err());
   }
 
@@ -199,7 +637,7 @@ model:
   #[test]
   fn test_invalid_yaml() {
     let f = temp_file("driver: md\nmolecule: :\n  bad:");
-    let result = run(&arg(f.path().to_str().unwrap()));
+    let result = run(&arg(f.path().to_str().unwrap()), &mut Vec::new());
     assert!(result.is_err());
   }
 
@@ -209,7 +647,7 @@ model:
     let f = temp_file(
       "molecule:\n  symbols: [H]\n  geometry: [0.0, 0.0, 0.0]\nmodel:\n  method: hf\n  basis: sto-3g\n",
     );
-    let result = run(&arg(f.path().to_str().unwrap()));
+    let result = run(&arg(f.path().to_str().unwrap()), &mut Vec::new());
     assert!(result.is_err());
   }
 
@@ -217,9 +655,224 @@ model:
   #[test]
   fn test_invalid_driver_value() {
     let f = temp_file(
-      "driver: optimize\nmolecule:\n  symbols: [H]\n  geometry: [0.0, 0.0, 0.0]\nmodel:\n  method: hf\n  basis: sto-3g\n",
+      "driver: irc\nmolecule:\n  symbols: [H]\n  geometry: [0.0, 0.0, 0.0]\nmodel:\n  method: hf\n  basis: sto-3g\n",
     );
-    let result = run(&arg(f.path().to_str().unwrap()));
+    let result = run(&arg(f.path().to_str().unwrap()), &mut Vec::new());
     assert!(result.is_err());
   }
+
+  // ── Matrix dump ─────────────────────────────────────────────────────────────
+
+  const H_STO3G: &str = r#"{"elements":{"1":{"electron_shells":[
+    {"function_type":"gto","angular_momentum":[0],
+     "exponents":["3.42525091","0.62391373","0.16885540"],
+     "coefficients":[["0.15432897","0.53532814","0.44463454"]]}
+  ]}}}"#;
+
+  // A cache root already holding H/sto-3g, so no network access is needed.
+  fn seeded_cache() -> tempfile::TempDir {
+    let dir = tempfile::TempDir::new().unwrap();
+    let file = dir.path().join("sto-3g").join("H.json");
+    std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+    std::fs::write(file, H_STO3G).unwrap();
+    dir
+  }
+
+  fn read_csv(path: &Path) -> Vec<Vec<f64>> {
+    std::fs::read_to_string(path)
+      .unwrap()
+      .lines()
+      .map(|l| l.split(',').map(|v| v.parse().unwrap()).collect())
+      .collect()
+  }
+
+  /// Scenario: --dump-matrices writes S, T and V for H₂, each 2×2
+  #[test]
+  fn test_dump_matrices_h2() {
+    let cache = seeded_cache();
+    let out = tempfile::TempDir::new().unwrap();
+    let dir = out.path().join("mats");
+    let f = temp_file(ENERGY_H2);
+    let args = [
+      f.path().to_str().unwrap().to_string(),
+      "--dump-matrices".to_string(),
+      dir.to_str().unwrap().to_string(),
+    ];
+    let msg = run_in(&args, cache.path(), &mut Vec::new()).unwrap();
+    assert_eq!(msg, format!("Wrote S, T, V (2×2) to {}", dir.display()));
+    for name in ["S", "T", "V"] {
+      let m = read_csv(&dir.join(format!("{}.csv", name)));
+      assert_eq!(m.len(), 2, "{} rows", name);
+      assert!(m.iter().all(|row| row.len() == 2), "{} columns", name);
+      assert_eq!(m[0][1], m[1][0], "{} symmetric", name);
+    }
+    let s = read_csv(&dir.join("S.csv"));
+    assert!((s[0][0] - 1.0).abs() < 1e-6);
+    // Szabo & Ostlund: S₁₂ = 0.6593 for STO-3G H₂ at 1.4 Bohr.
+    assert!((s[0][1] - 0.6593).abs() < 1e-4);
+  }
+
+  /// Scenario: --timing with --dump-matrices also times the basis and integrals
+  #[test]
+  fn test_dump_matrices_timing_stages() {
+    let cache = seeded_cache();
+    let out = tempfile::TempDir::new().unwrap();
+    let f = temp_file(ENERGY_H2);
+    let args = [
+      "--timing".to_string(),
+      f.path().to_str().unwrap().to_string(),
+      "--dump-matrices".to_string(),
+      out.path().to_str().unwrap().to_string(),
+    ];
+    let mut timings = Vec::new();
+    run_in(&args, cache.path(), &mut timings).unwrap();
+    let stages: Vec<&str> = timings.iter().map(|t| t.split(' ').nth(1).unwrap()).collect();
+    assert_eq!(stages, vec!["parse", "basis", "integrals"]);
+  }
+
+  /// Scenario: model.basis_file supplies the basis with an empty cache
+  #[test]
+  fn test_dump_matrices_local_basis_file() {
+    let work = tempfile::TempDir::new().unwrap();
+    let basis_file = work.path().join("h.json");
+    std::fs::write(&basis_file, H_STO3G).unwrap();
+    let yaml = ENERGY_H2.replace(
+      "  basis: sto-3g\n",
+      &format!("  basis: sto-3g\n  basis_file: {}\n", basis_file.display()),
+    );
+    let f = temp_file(&yaml);
+    let dir = work.path().join("mats");
+    let args = [
+      f.path().to_str().unwrap().to_string(),
+      "--dump-matrices".to_string(),
+      dir.to_str().unwrap().to_string(),
+    ];
+    run_in(&args, &work.path().join("empty-cache"), &mut Vec::new()).unwrap();
+    let s = read_csv(&dir.join("S.csv"));
+    assert!((s[0][1] - 0.6593).abs() < 1e-4);
+  }
+
+  /// Scenario: --dump-matrices rejects an MD input
+  #[test]
+  fn test_dump_matrices_rejects_md() {
+    let f = temp_file(MD_WATER);
+    let out = tempfile::TempDir::new().unwrap();
+    let args = [
+      f.path().to_str().unwrap().to_string(),
+      "--dump-matrices".to_string(),
+      out.path().to_str().unwrap().to_string(),
+    ];
+    assert!(run_in(&args, out.path(), &mut Vec::new()).unwrap_err().contains("single-point"));
+  }
+
+  /// Scenario: --dump-matrices without a directory is a usage error
+  #[test]
+  fn test_dump_matrices_missing_dir() {
+    let result = run(&["a.yaml".to_string(), "--dump-matrices".to_string()], &mut Vec::new());
+    assert_eq!(result.unwrap_err(), USAGE);
+  }
+
+  // ── Stdin ───────────────────────────────────────────────────────────────────
+
+  /// Scenario: `-` reads the input from stdin and reports the same summary as the file
+  #[test]
+  fn test_stdin_matches_file() {
+    let f = temp_file(ENERGY_H2);
+    let from_file = run(&arg(f.path().to_str().unwrap()), &mut Vec::new()).unwrap();
+    let cache = tempfile::tempdir().unwrap();
+    let from_stdin =
+      run_with_stdin(&arg("-"), cache.path(), ENERGY_H2.as_bytes(), &mut Vec::new()).unwrap();
+    assert_eq!(from_stdin, from_file);
+  }
+
+  /// Scenario: Invalid YAML on stdin fails like a bad file
+  #[test]
+  fn test_stdin_invalid_input() {
+    let cache = tempfile::tempdir().unwrap();
+    let err = run_with_stdin(&arg("-"), cache.path(), "driver: [".as_bytes(), &mut Vec::new())
+      .unwrap_err();
+    let f = temp_file("driver: [");
+    assert_eq!(err, run(&arg(f.path().to_str().unwrap()), &mut Vec::new()).unwrap_err());
+  }
+
+  /// Scenario: --check validates stdin too
+  #[test]
+  fn test_stdin_check() {
+    let cache = tempfile::tempdir().unwrap();
+    let args = ["--check".to_string(), "-".to_string()];
+    let msg = run_with_stdin(&args, cache.path(), ENERGY_H2.as_bytes(), &mut Vec::new()).unwrap();
+    assert_eq!(msg, "OK: <stdin> is valid");
+  }
+
+  /// Scenario: `-` still counts as the single input argument
+  #[test]
+  fn test_stdin_with_extra_argument_is_usage_error() {
+    let cache = tempfile::tempdir().unwrap();
+    let args = ["-".to_string(), "other.yaml".to_string()];
+    let err = run_with_stdin(&args, cache.path(), ENERGY_H2.as_bytes(), &mut Vec::new());
+    assert_eq!(err.unwrap_err(), USAGE);
+  }
+
+  // ── fetch ───────────────────────────────────────────────────────────────────
+
+  const H_RESPONSE: &str = r#"{"elements":{"1":{"electron_shells":[]}}}"#;
+  const O_RESPONSE: &str = r#"{"elements":{"8":{"electron_shells":[]}}}"#;
+
+  fn fetch_args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|a| a.to_string()).collect()
+  }
+
+  /// Scenario: fetch downloads each element and prints the cached paths in order
+  #[test]
+  fn test_fetch_multiple_elements() {
+    let mut server = mockito::Server::new();
+    let _h = server
+      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=H")
+      .with_body(H_RESPONSE)
+      .create();
+    let _o = server
+      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=O")
+      .with_body(O_RESPONSE)
+      .create();
+    let cache = tempfile::tempdir().unwrap();
+    let out = fetch_command(&fetch_args(&["STO-3G", "h", "O"]), &server.url(), cache.path())
+      .unwrap();
+    let h = cache.path().join("sto-3g").join("H.json");
+    let o = cache.path().join("sto-3g").join("O.json");
+    assert_eq!(out, format!("{}\n{}", h.display(), o.display()));
+    assert_eq!(std::fs::read_to_string(h).unwrap(), H_RESPONSE);
+    assert_eq!(std::fs::read_to_string(o).unwrap(), O_RESPONSE);
+  }
+
+  /// Scenario: An unknown basis set fails with the BSE error message
+  #[test]
+  fn test_fetch_unknown_basis() {
+    let mut server = mockito::Server::new();
+    let _m = server
+      .mock("GET", "/api/basis/nope/format/qcschema?elements=H")
+      .with_status(404)
+      .create();
+    let cache = tempfile::tempdir().unwrap();
+    let err = fetch_command(&fetch_args(&["nope", "H", "O"]), &server.url(), cache.path())
+      .unwrap_err();
+    assert_eq!(
+      err,
+      format!(
+        "unknown basis set: \"nope\" (GET {}/api/basis/nope/format/qcschema?elements=H)",
+        server.url()
+      )
+    );
+    assert!(!cache.path().join("nope").exists());
+  }
+
+  /// Scenario: fetch needs a basis and at least one element
+  #[test]
+  fn test_fetch_usage() {
+    for args in [&["fetch"][..], &["fetch", "sto-3g"][..]] {
+      assert_eq!(
+        run(&fetch_args(args), &mut Vec::new()).unwrap_err(),
+        "usage: electron fetch <basis> <element>..."
+      );
+    }
+  }
 }
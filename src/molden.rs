@@ -0,0 +1,181 @@
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::basis::BasisSet;
+use crate::input::CartesianGeometry;
+
+/// Shell letters by angular momentum, as Molden reads them.
+const SHELL_LETTERS: &[char] = &['s', 'p', 'd', 'f', 'g'];
+
+// ── Error type ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, PartialEq)]
+pub enum MoldenError {
+  /// No `BasisSet` was supplied for an element present in the geometry.
+  MissingBasis(String),
+  /// Molden has no shell letter for this angular momentum.
+  UnsupportedAngularMomentum(u32),
+  Io(String),
+}
+
+impl std::fmt::Display for MoldenError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      MoldenError::MissingBasis(el) => write!(f, "no basis set for element {}", el),
+      MoldenError::UnsupportedAngularMomentum(l) =>
+        write!(f, "angular momentum {} has no Molden shell letter", l),
+      MoldenError::Io(s) => write!(f, "I/O error: {}", s),
+    }
+  }
+}
+
+// ── Public functions ──────────────────────────────────────────────────────────
+
+/// Writes `geometry` and its basis to `path` in Molden format; see
+/// [`molden_string`].
+pub fn write_molden(
+  path: &Path,
+  geometry: &CartesianGeometry,
+  basis: &[BasisSet],
+) -> Result<(), MoldenError> {
+  let content = molden_string(geometry, basis)?;
+  std::fs::write(path, content).map_err(|e| MoldenError::Io(e.to_string()))
+}
+
+/// Renders a Molden file with an `[Atoms] AU` section (symbol, 1-based index,
+/// atomic number and Bohr coordinates per atom) and a `[GTO]` section listing
+/// each atom's shells from the `BasisSet` for its element, with the
+/// unnormalized contraction coefficients Molden expects. Cartesian d and f
+/// functions are Molden's default, so no `[5D]` flags are written.
+pub fn molden_string(
+  geometry: &CartesianGeometry,
+  basis: &[BasisSet],
+) -> Result<String, MoldenError> {
+  let mut out = String::from("[Molden Format]\n[Atoms] AU\n");
+  let atomic_numbers = geometry.atomic_numbers();
+  for (i, sym) in geometry.symbols.iter().enumerate() {
+    writeln!(
+      out,
+      "{:<4} {:>4} {:>4} {:>20.12} {:>20.12} {:>20.12}",
+      sym, i + 1, atomic_numbers[i], geometry.x[i], geometry.y[i], geometry.z[i]
+    )
+    .expect("writing to a String cannot fail");
+  }
+
+  out.push_str("[GTO]\n");
+  for (i, sym) in geometry.symbols.iter().enumerate() {
+    let set = basis
+      .iter()
+      .find(|bs| bs.element.eq_ignore_ascii_case(sym))
+      .ok_or_else(|| MoldenError::MissingBasis(sym.clone()))?;
+    writeln!(out, "{:>4} 0", i + 1).expect("writing to a String cannot fail");
+    for shell in &set.shells {
+      let letter = SHELL_LETTERS
+        .get(shell.angular_momentum as usize)
+        .ok_or(MoldenError::UnsupportedAngularMomentum(shell.angular_momentum))?;
+      writeln!(out, " {} {:>4} 1.00", letter, shell.exponents.len())
+        .expect("writing to a String cannot fail");
+      for (a, c) in shell.exponents.iter().zip(&shell.coefficients) {
+        writeln!(out, "{:>22.14e} {:>22.14e}", a, c).expect("writing to a String cannot fail");
+      }
+    }
+    out.push('\n');
+  }
+  Ok(out)
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::basis::{parse_basis_str, ElectronShell};
+
+  const H_STO3G: &str = r#"{"elements":{"1":{"electron_shells":[
+    {"function_type":"gto","angular_momentum":[0],
+     "exponents":["3.42525091","0.62391373","0.16885540"],
+     "coefficients":[["0.15432897","0.53532814","0.44463454"]]}
+  ]}}}"#;
+
+  fn h2() -> CartesianGeometry {
+    CartesianGeometry {
+      symbols: vec!["H".to_string(), "H".to_string()],
+      labels: vec!["H".to_string(), "H".to_string()],
+      x: vec![0.0, 0.0],
+      y: vec![0.0, 0.0],
+      z: vec![0.0, 1.4],
+      is_ghost: vec![false; 2],
+    }
+  }
+
+  fn section<'a>(text: &'a str, header: &str) -> Vec<&'a str> {
+    text
+      .lines()
+      .skip_while(|l| !l.starts_with(header))
+      .skip(1)
+      .take_while(|l| !l.starts_with('['))
+      .collect()
+  }
+
+  // Scenario: An H2 STO-3G file lists both atoms and an s shell of three primitives each
+  #[test]
+  fn h2_sto3g_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("h2.molden");
+    let basis = vec![parse_basis_str(H_STO3G).unwrap()];
+    write_molden(&path, &h2(), &basis).unwrap();
+    let text = std::fs::read_to_string(&path).unwrap();
+
+    let atoms = section(&text, "[Atoms]");
+    assert_eq!(atoms.len(), 2);
+    let fields: Vec<&str> = atoms[1].split_whitespace().collect();
+    assert_eq!(&fields[..3], &["H", "2", "1"]);
+    assert_eq!(fields[5].parse::<f64>().unwrap(), 1.4);
+
+    let gto = section(&text, "[GTO]");
+    assert_eq!(gto.iter().filter(|l| l.split_whitespace().eq(["s", "3", "1.00"])).count(), 2);
+    let primitive: Vec<f64> = gto[2].split_whitespace().map(|v| v.parse().unwrap()).collect();
+    assert_eq!(primitive, vec![3.42525091, 0.15432897]);
+  }
+
+  // Scenario: Shells use Molden letters in file order
+  #[test]
+  fn shell_letters_follow_angular_momentum() {
+    let shells = (0..4)
+      .map(|l| ElectronShell {
+        angular_momentum: l,
+        exponents: vec![1.0],
+        coefficients: vec![1.0],
+        general_contraction_group: None,
+      })
+      .collect();
+    let basis = vec![BasisSet { element: "H".to_string(), atomic_number: 1, shells, ecp: None }];
+    let mut geom = h2();
+    geom.symbols.truncate(1);
+    let text = molden_string(&geom, &basis).unwrap();
+    let letters: Vec<&str> = section(&text, "[GTO]")
+      .iter()
+      .filter_map(|l| l.split_whitespace().next().filter(|w| w.chars().all(char::is_alphabetic)))
+      .collect();
+    assert_eq!(letters, vec!["s", "p", "d", "f"]);
+  }
+
+  // Scenario: An element without a basis set is an error
+  #[test]
+  fn missing_basis_is_error() {
+    assert_eq!(molden_string(&h2(), &[]), Err(MoldenError::MissingBasis("H".to_string())));
+  }
+
+  // Scenario: Angular momentum beyond g has no Molden letter
+  #[test]
+  fn unsupported_angular_momentum_is_error() {
+    let shells = vec![ElectronShell {
+      angular_momentum: 5,
+      exponents: vec![1.0],
+      coefficients: vec![1.0],
+      general_contraction_group: None,
+    }];
+    let basis = vec![BasisSet { element: "H".to_string(), atomic_number: 1, shells, ecp: None }];
+    assert_eq!(molden_string(&h2(), &basis), Err(MoldenError::UnsupportedAngularMomentum(5)));
+  }
+}
@@ -1,5 +1,13 @@
-use crate::basis::{BasisSet, LoadError};
+use crate::basis::{
+  odd_double_factorial, parse_local_basis, primitive_norm, BasisSet, BseError, LoadError, ParseError,
+  BSE_BASE_URL,
+};
 use crate::input::CartesianGeometry;
+use faer::Mat;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
 
 // ─── Public types ─────────────────────────────────────────────────────────────
 
@@ -33,6 +41,127 @@ pub struct AoBasis {
   // Per primitive (flat)
   pub exponents: Vec<f64>,
   pub coefficients: Vec<f64>,
+
+  /// Whether `coefficients` already include the radial primitive
+  /// normalization N(α, l) = (2α/π)^{3/4} (4α)^{l/2} / √((2l−1)!!) of an
+  /// axial (x^l) Cartesian Gaussian. `init_basis` always folds it in; the
+  /// integral routines then apply only the remaining per-component factor
+  /// √((2l−1)!! / ((2lx−1)!! (2ly−1)!! (2lz−1)!!)). When false the
+  /// coefficients are the raw BSE contraction coefficients and the full
+  /// normalization is applied at integral time.
+  pub norms_folded: bool,
+}
+
+impl AoBasis {
+  /// Indices of the basis functions centered on `atom`. Functions are stored
+  /// atom-major, so they form one contiguous range; it is empty for an atom
+  /// without functions or out of range.
+  pub fn functions_on_atom(&self, atom: usize) -> Range<usize> {
+    self.atom_index.partition_point(|&a| a < atom)..self.atom_index.partition_point(|&a| a <= atom)
+  }
+
+  /// Indices of the Cartesian components of `shell`, contiguous because
+  /// functions are stored shell-minor within each atom; empty if `shell` is
+  /// out of range.
+  pub fn functions_in_shell(&self, shell: usize) -> Range<usize> {
+    self.shell_index.partition_point(|&s| s < shell)..self.shell_index.partition_point(|&s| s <= shell)
+  }
+}
+
+/// The one-electron matrices of a geometry in an [`AoBasis`], as returned by
+/// [`one_electron_integrals`]: the inputs to `guess_hcore`.
+#[derive(Debug)]
+pub struct OneElectronIntegrals {
+  /// Overlap S, as [`overlap_matrix`].
+  pub s: Mat<f64>,
+  /// Kinetic energy T, as [`kinetic_matrix`].
+  pub t: Mat<f64>,
+  /// Nuclear attraction V, as [`nuclear_attraction_matrix`].
+  pub v: Mat<f64>,
+}
+
+/// Precomputed data for one pair of shells, as listed by [`shell_pairs`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShellPair {
+  /// Shell indices into the per-shell arrays of [`AoBasis`]; `shell_a <= shell_b`.
+  pub shell_a: usize,
+  pub shell_b: usize,
+  /// The primitive pairs that survive screening.
+  pub primitives: Vec<PrimitivePair>,
+}
+
+/// Gaussian product data of primitives `i` (on `shell_a`) and `j` (on
+/// `shell_b`), both indices into the flat primitive arrays of [`AoBasis`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrimitivePair {
+  pub i: usize,
+  pub j: usize,
+  /// Combined exponent p = α + β.
+  pub p: f64,
+  /// Gaussian product center P = (αA + βB) / p.
+  pub center: [f64; 3],
+  /// Overlap prefactor K_AB = e^{−αβ|A−B|²/p}.
+  pub prefactor: f64,
+}
+
+/// Opt-in in-memory memo of parsed basis sets, keyed by (element, basis
+/// name), for callers that build the basis of many geometries, such as a
+/// geometry optimization; see [`init_basis_cached`].
+///
+/// Each key is loaded (read from the disk cache under `cache_root`, fetching
+/// if needed, and parsed) at most once, even when several threads ask for it
+/// at the same time. Failed loads are not remembered.
+#[derive(Debug)]
+pub struct BasisCache {
+  cache_root: PathBuf,
+  entries: Mutex<HashMap<(String, String), BasisSlot>>,
+}
+
+/// One key of a [`BasisCache`]; `None` until its load succeeds.
+type BasisSlot = Arc<Mutex<Option<Arc<BasisSet>>>>;
+
+impl BasisCache {
+  /// An empty cache whose misses load from the disk cache under `cache_root`.
+  pub fn new(cache_root: &Path) -> Self {
+    BasisCache { cache_root: cache_root.to_path_buf(), entries: Mutex::new(HashMap::new()) }
+  }
+
+  /// The basis set of `element` in `basis_name`, loaded on first request.
+  pub fn load(&self, element: &str, basis_name: &str) -> Result<Arc<BasisSet>, InitError> {
+    self.load_with(element, basis_name, |element, name| {
+      crate::basis::load_basis_in(element, name, &self.cache_root).map_err(|e| {
+        InitError::BasisLoad { element: element.to_string(), source: e }
+      })
+    })
+  }
+
+  /// [`BasisCache::load`] with misses served by `load`.
+  fn load_with<F>(&self, element: &str, basis_name: &str, load: F) -> Result<Arc<BasisSet>, InitError>
+  where
+    F: Fn(&str, &str) -> Result<BasisSet, InitError>,
+  {
+    let slot = {
+      let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+      let key = (element.to_string(), basis_name.to_string());
+      Arc::clone(entries.entry(key).or_default())
+    };
+    // Holding only this key's lock lets different elements load concurrently.
+    let mut slot = slot.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(bs) = slot.as_ref() {
+      return Ok(Arc::clone(bs));
+    }
+    let bs = Arc::new(load(element, basis_name)?);
+    *slot = Some(Arc::clone(&bs));
+    Ok(bs)
+  }
+
+  /// Whether `element` in `basis_name` has already been loaded.
+  fn contains(&self, element: &str, basis_name: &str) -> bool {
+    let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+    entries
+      .get(&(element.to_string(), basis_name.to_string()))
+      .is_some_and(|slot| slot.lock().unwrap_or_else(|e| e.into_inner()).is_some())
+  }
 }
 
 /// Error type returned by `init_basis`.
@@ -40,6 +169,28 @@ pub struct AoBasis {
 pub enum InitError {
   /// `load_basis` failed for the named element.
   BasisLoad { element: String, source: LoadError },
+  /// The local basis file given to `init_basis_with_file` could not be read.
+  BasisFile { path: String, source: ParseError },
+  /// The BSE reports that `basis_name` has no functions for `elements`.
+  UnsupportedElements { basis_name: String, elements: Vec<String> },
+  /// The batch download of `basis_name` failed in a way the per-element
+  /// loads would only repeat (unknown basis, unusable response, cache I/O).
+  BasisFetch { basis_name: String, source: BseError },
+}
+
+impl std::fmt::Display for InitError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      InitError::BasisLoad { element, source } =>
+        write!(f, "failed to load basis for {}: {}", element, source),
+      InitError::BasisFile { path, source } =>
+        write!(f, "failed to read basis file {}: {}", path, source),
+      InitError::UnsupportedElements { basis_name, elements } =>
+        write!(f, "basis {} does not cover {}", basis_name, elements.join(", ")),
+      InitError::BasisFetch { basis_name, source } =>
+        write!(f, "failed to fetch basis {}: {}", basis_name, source),
+    }
+  }
 }
 
 // ─── Public functions ─────────────────────────────────────────────────────────
@@ -47,39 +198,434 @@ pub enum InitError {
 /// Builds the contracted Cartesian AO basis from a molecular geometry and
 /// a named basis set.
 ///
-/// Calls `load_basis` once per unique element symbol and returns a flat,
+/// Checks BSE coverage of every uncached element (failing with
+/// [`InitError::UnsupportedElements`]), fetches them in a single BSE request,
+/// then calls `load_basis` once per unique element symbol and returns a flat,
 /// structure-of-arrays representation ordered atom-major, shell-minor, with
 /// Cartesian components ordered by descending lx, then descending ly.
+/// Primitive normalization is folded into the coefficients (see
+/// [`AoBasis::norms_folded`]).
 pub fn init_basis(
   geometry: &CartesianGeometry,
   basis_name: &str,
 ) -> Result<AoBasis, InitError> {
-  init_basis_impl(geometry, |element| {
-    crate::basis::load_basis(element, basis_name).map_err(|e| InitError::BasisLoad {
-      element: element.to_string(),
-      source: e,
+  init_basis_in(geometry, basis_name, &crate::basis::default_cache_root())
+}
+
+/// [`init_basis`] with basis files cached under `cache_root` instead of the
+/// default root.
+pub fn init_basis_in(
+  geometry: &CartesianGeometry,
+  basis_name: &str,
+  cache_root: &Path,
+) -> Result<AoBasis, InitError> {
+  init_basis_with_overrides(geometry, basis_name, &BTreeMap::new(), cache_root)
+}
+
+/// [`init_basis_in`] with a mixed basis: elements keyed in `overrides`
+/// (title-case symbols, as in `model.basis_overrides`) are loaded as the
+/// mapped basis name, all others as `basis_name`. Coverage is checked and
+/// uncached elements fetched once per distinct basis name.
+pub fn init_basis_with_overrides(
+  geometry: &CartesianGeometry,
+  basis_name: &str,
+  overrides: &BTreeMap<String, String>,
+  cache_root: &Path,
+) -> Result<AoBasis, InitError> {
+  init_basis_from(geometry, basis_name, overrides, BSE_BASE_URL, cache_root)
+}
+
+/// [`init_basis_with_overrides`] with the elements present in the local basis
+/// file `basis_file` (QCSchema JSON or Gaussian94, possibly holding several
+/// elements) taken from that file; only elements it lacks are fetched, as
+/// their override or `basis_name`.
+pub fn init_basis_with_file(
+  geometry: &CartesianGeometry,
+  basis_name: &str,
+  overrides: &BTreeMap<String, String>,
+  basis_file: &Path,
+  cache_root: &Path,
+) -> Result<AoBasis, InitError> {
+  let local = parse_local_basis(basis_file).map_err(|e| InitError::BasisFile {
+    path: basis_file.display().to_string(),
+    source: e,
+  })?;
+  init_basis_impl(geometry, true, local_first(local, per_element_basis(
+    basis_name,
+    overrides,
+    |element, name| {
+      crate::basis::load_basis_in(element, name, cache_root).map_err(|e| {
+        InitError::BasisLoad { element: element.to_string(), source: e }
+      })
+    },
+  )))
+}
+
+/// [`init_basis_in`] with element basis sets taken from `cache`, so repeated
+/// calls (e.g. over the steps of an optimization) parse each element once.
+/// Coverage checks and prefetching are skipped for elements `cache` holds.
+pub fn init_basis_cached(
+  geometry: &CartesianGeometry,
+  basis_name: &str,
+  cache: &BasisCache,
+) -> Result<AoBasis, InitError> {
+  let uncached: Vec<String> = geometry
+    .unique_elements()
+    .into_iter()
+    .filter(|element| !cache.contains(element, basis_name))
+    .collect();
+  if !uncached.is_empty() {
+    prefetch_basis(&uncached, basis_name, BSE_BASE_URL, &cache.cache_root)?;
+  }
+  init_basis_impl(geometry, true, |element| Ok((*cache.load(element, basis_name)?).clone()))
+}
+
+/// Computes the n_basis × n_basis overlap matrix S_μν = ⟨φ_μ|φ_ν⟩ of the
+/// contracted Cartesian basis.
+///
+/// Each primitive carries the normalization of its own (lx, ly, lz) component,
+/// so a function whose contraction is normalized has S_μμ = 1 whatever its
+/// Cartesian component. Primitive overlaps use the Obara–Saika recurrence.
+pub fn overlap_matrix(basis: &AoBasis) -> Mat<f64> {
+  let n = basis.n_basis;
+  let mut s: Mat<f64> = Mat::zeros(n, n);
+  for mu in 0..n {
+    for nu in mu..n {
+      let value = contracted_overlap(basis, mu, nu);
+      s.write(mu, nu, value);
+      s.write(nu, mu, value);
+    }
+  }
+  s
+}
+
+/// Computes the n_basis × n_basis kinetic energy matrix T_μν = ⟨φ_μ|−½∇²|φ_ν⟩
+/// of the contracted Cartesian basis, normalized as in [`overlap_matrix`].
+///
+/// Each primitive integral is assembled from Obara–Saika overlaps with the ket
+/// angular momentum shifted by ±2; only the upper triangle is evaluated, so T
+/// is exactly symmetric.
+pub fn kinetic_matrix(basis: &AoBasis) -> Mat<f64> {
+  let n = basis.n_basis;
+  let mut t: Mat<f64> = Mat::zeros(n, n);
+  for mu in 0..n {
+    for nu in mu..n {
+      let value = contracted_kinetic(basis, mu, nu);
+      t.write(mu, nu, value);
+      t.write(nu, mu, value);
+    }
+  }
+  t
+}
+
+/// Computes the n_basis × n_basis nuclear attraction matrix
+/// V_μν = Σ_C ⟨φ_μ|−Z_C / |r − R_C||φ_ν⟩ over every atom of `geometry`, with
+/// Z_C the bare atomic number (0 for ghost centers).
+///
+/// Primitive integrals use McMurchie–Davidson Hermite expansions and the Boys
+/// function; only the upper triangle is evaluated, so V is exactly symmetric.
+pub fn nuclear_attraction_matrix(basis: &AoBasis, geometry: &CartesianGeometry) -> Mat<f64> {
+  let nuclei = nuclei(geometry);
+  let n = basis.n_basis;
+  let mut v: Mat<f64> = Mat::zeros(n, n);
+  for mu in 0..n {
+    for nu in mu..n {
+      let value = contracted_nuclear(basis, mu, nu, &nuclei);
+      v.write(mu, nu, value);
+      v.write(nu, mu, value);
+    }
+  }
+  v
+}
+
+/// Computes S, T and V in one pass over the basis function pairs, sharing the
+/// per-primitive-pair setup (Gaussian product centers and prefactors) between
+/// the three integrals. Each matrix equals the one its own builder returns.
+pub fn one_electron_integrals(basis: &AoBasis, geometry: &CartesianGeometry) -> OneElectronIntegrals {
+  let nuclei = nuclei(geometry);
+  let n = basis.n_basis;
+  let mut s: Mat<f64> = Mat::zeros(n, n);
+  let mut t: Mat<f64> = Mat::zeros(n, n);
+  let mut v: Mat<f64> = Mat::zeros(n, n);
+  for mu in 0..n {
+    for nu in mu..n {
+      let [s_value, t_value, v_value] = contracted_integrals(basis, mu, nu, |axes, a_l, b_l| {
+        [
+          primitive_overlap(axes, a_l, b_l),
+          primitive_kinetic(axes, a_l, b_l),
+          primitive_nuclear(axes, a_l, b_l, &nuclei),
+        ]
+      });
+      for (m, value) in [(&mut s, s_value), (&mut t, t_value), (&mut v, v_value)] {
+        m.write(mu, nu, value);
+        m.write(nu, mu, value);
+      }
+    }
+  }
+  OneElectronIntegrals { s, t, v }
+}
+
+/// Primitive pairs whose overlap bound falls below this are dropped by
+/// [`shell_pairs`].
+pub const SHELL_PAIR_THRESHOLD: f64 = 1e-14;
+
+/// Lists the shell pairs (a, b), a ≤ b, of `basis` with their primitive-pair
+/// data, screened at [`SHELL_PAIR_THRESHOLD`]; see
+/// [`shell_pairs_with_threshold`].
+pub fn shell_pairs(basis: &AoBasis) -> Vec<ShellPair> {
+  shell_pairs_with_threshold(basis, SHELL_PAIR_THRESHOLD)
+}
+
+/// [`shell_pairs`] with an explicit screening threshold.
+///
+/// A primitive pair is kept when its s-type overlap bound
+/// |c_i c_j| (π/p)^{3/2} K_AB reaches `threshold`; a shell pair with no
+/// surviving primitive pair is omitted altogether. The bound ignores the
+/// angular factors, so it is a screening heuristic rather than a strict
+/// Schwarz bound for l > 0.
+pub fn shell_pairs_with_threshold(basis: &AoBasis, threshold: f64) -> Vec<ShellPair> {
+  // Every shell contributes at least one function; its first carries the center.
+  let centers: Vec<[f64; 3]> = (0..basis.n_shells)
+    .map(|shell| {
+      let mu = basis.functions_in_shell(shell).start;
+      [basis.center_x[mu], basis.center_y[mu], basis.center_z[mu]]
     })
+    .collect();
+
+  let mut pairs = Vec::new();
+  for shell_a in 0..basis.n_shells {
+    for shell_b in shell_a..basis.n_shells {
+      let (a, b) = (centers[shell_a], centers[shell_b]);
+      let ab2: f64 = (0..3).map(|k| (a[k] - b[k]).powi(2)).sum();
+      let mut primitives = Vec::new();
+      for i in shell_primitives(basis, shell_a) {
+        for j in shell_primitives(basis, shell_b) {
+          let (alpha, beta) = (basis.exponents[i], basis.exponents[j]);
+          let p = alpha + beta;
+          let prefactor = (-alpha * beta / p * ab2).exp();
+          let bound = (basis.coefficients[i] * basis.coefficients[j]).abs()
+            * (std::f64::consts::PI / p).powf(1.5)
+            * prefactor;
+          if bound < threshold {
+            continue;
+          }
+          let center = [0, 1, 2].map(|k| (alpha * a[k] + beta * b[k]) / p);
+          primitives.push(PrimitivePair { i, j, p, center, prefactor });
+        }
+      }
+      if !primitives.is_empty() {
+        pairs.push(ShellPair { shell_a, shell_b, primitives });
+      }
+    }
+  }
+  pairs
+}
+
+// ─── Integral cache ───────────────────────────────────────────────────────────
+
+/// The one-electron matrices (S, T, V) of a geometry in a basis.
+pub type OneElectronMatrices = (Mat<f64>, Mat<f64>, Mat<f64>);
+
+/// Process-wide memo of [`one_electron_integrals_cached`], keyed by
+/// `(canonical_key(geometry), basis_name)`.
+static INTEGRAL_CACHE: LazyLock<Mutex<HashMap<(String, String), OneElectronMatrices>>> =
+  LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Exact, order-sensitive key for `geometry`: one `symbol x y z` line per
+/// atom with each coordinate written as its IEEE-754 bit pattern, so two
+/// geometries share a key only if every coordinate is bitwise identical
+/// (with −0.0 folded into 0.0). Labels are ignored; ghost centers are
+/// written as `Gh(X)`, since they change V.
+pub fn canonical_key(geometry: &CartesianGeometry) -> String {
+  let mut key = String::new();
+  for (i, sym) in geometry.symbols.iter().enumerate() {
+    let bits = [geometry.x[i], geometry.y[i], geometry.z[i]].map(|c| (c + 0.0).to_bits());
+    let sym = if geometry.is_ghost[i] { format!("Gh({})", sym) } else { sym.clone() };
+    key.push_str(&format!("{} {:016x} {:016x} {:016x}\n", sym, bits[0], bits[1], bits[2]));
+  }
+  key
+}
+
+/// S, T and V for `geometry` in `basis_name`, memoized across calls.
+///
+/// Only an exactly repeated geometry (see [`canonical_key`]) is a hit; a
+/// displaced one is computed afresh and added. Entries are never evicted, so
+/// each distinct geometry keeps 3·n_basis² f64s alive (about 2.4 MB at
+/// n_basis = 300) until [`clear_integral_cache`] — fine for a scan or
+/// optimization over a small molecule, not for long trajectories. The key
+/// does not include `cache_root`: a basis name is assumed to resolve to the
+/// same functions wherever it is loaded from. Failures are not cached.
+pub fn one_electron_integrals_cached(
+  geometry: &CartesianGeometry,
+  basis_name: &str,
+  cache_root: &Path,
+) -> Result<OneElectronMatrices, InitError> {
+  cached_integrals_with(geometry, basis_name, || {
+    let basis = init_basis_in(geometry, basis_name, cache_root)?;
+    let OneElectronIntegrals { s, t, v } = one_electron_integrals(&basis, geometry);
+    Ok((s, t, v))
   })
 }
 
+/// Drops every entry of the integral cache.
+pub fn clear_integral_cache() {
+  INTEGRAL_CACHE.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
 // ─── Private helpers ──────────────────────────────────────────────────────────
 
+/// Memoizing core of [`one_electron_integrals_cached`]: `compute` runs only
+/// on a cache miss. The lock is not held while computing.
+fn cached_integrals_with<F>(
+  geometry: &CartesianGeometry,
+  basis_name: &str,
+  compute: F,
+) -> Result<OneElectronMatrices, InitError>
+where
+  F: FnOnce() -> Result<OneElectronMatrices, InitError>,
+{
+  let key = (canonical_key(geometry), basis_name.to_string());
+  if let Some(hit) = INTEGRAL_CACHE.lock().unwrap_or_else(|e| e.into_inner()).get(&key) {
+    return Ok(hit.clone());
+  }
+  let matrices = compute()?;
+  INTEGRAL_CACHE
+    .lock()
+    .unwrap_or_else(|e| e.into_inner())
+    .insert(key, matrices.clone());
+  Ok(matrices)
+}
+
+/// Load function that answers from `local` when it has the element and defers
+/// to `fallback` otherwise.
+fn local_first<F>(local: Vec<BasisSet>, fallback: F) -> impl Fn(&str) -> Result<BasisSet, InitError>
+where
+  F: Fn(&str) -> Result<BasisSet, InitError>,
+{
+  move |element| match local.iter().find(|bs| bs.element == element) {
+    Some(bs) => Ok(bs.clone()),
+    None => fallback(element),
+  }
+}
+
+/// Unique elements of `geometry` grouped by the basis they are loaded as, in
+/// first-occurrence order of both basis names and elements.
+fn group_by_basis<'a>(
+  geometry: &CartesianGeometry,
+  basis_name: &'a str,
+  overrides: &'a BTreeMap<String, String>,
+) -> Vec<(&'a str, Vec<String>)> {
+  let mut groups: Vec<(&str, Vec<String>)> = Vec::new();
+  for element in geometry.unique_elements() {
+    let name = overrides.get(&element).map_or(basis_name, String::as_str);
+    match groups.iter_mut().find(|(n, _)| *n == name) {
+      Some((_, symbols)) => symbols.push(element),
+      None => groups.push((name, vec![element])),
+    }
+  }
+  groups
+}
+
+/// [`init_basis_with_overrides`] against the BSE at `base_url` (scheme and
+/// host, no trailing slash).
+fn init_basis_from(
+  geometry: &CartesianGeometry,
+  basis_name: &str,
+  overrides: &BTreeMap<String, String>,
+  base_url: &str,
+  cache_root: &Path,
+) -> Result<AoBasis, InitError> {
+  for (name, symbols) in group_by_basis(geometry, basis_name, overrides) {
+    prefetch_basis(&symbols, name, base_url, cache_root)?;
+  }
+  init_basis_impl(geometry, true, per_element_basis(basis_name, overrides, |element, name| {
+    crate::basis::load_basis_from(element, name, base_url, cache_root).map_err(|e| {
+      InitError::BasisLoad { element: element.to_string(), source: e }
+    })
+  }))
+}
+
+/// Checks that `basis_name` covers the uncached `symbols` and fetches them in
+/// one request to the BSE at `base_url`.
+fn prefetch_basis(
+  symbols: &[String],
+  basis_name: &str,
+  base_url: &str,
+  cache_root: &Path,
+) -> Result<(), InitError> {
+  let symbols: Vec<&str> = symbols.iter().map(String::as_str).collect();
+
+  // Fail fast, naming every unsupported element at once, before downloading
+  // anything. If the metadata is unreachable the per-element loads still
+  // report the first failure.
+  let uncached = crate::basis::cached_coverage(&symbols, basis_name, cache_root);
+  if !uncached.is_empty() {
+    let uncached: Vec<&str> = uncached.iter().map(String::as_str).collect();
+    if let Ok(missing) = crate::basis::check_basis_covers_from(&uncached, basis_name, base_url)
+      && !missing.is_empty()
+    {
+      return Err(InitError::UnsupportedElements {
+        basis_name: basis_name.to_string(),
+        elements: missing,
+      });
+    }
+  }
+
+  // A network failure or an element missing from the response is not fatal:
+  // the per-element loads retry and report which element is at fault.
+  match crate::basis::fetch_basis_multi_from(&symbols, basis_name, base_url, cache_root) {
+    Ok(_)
+    | Err(BseError::NetworkError(_) | BseError::Timeout(_) | BseError::ElementNotInBasisSet { .. }) => {
+      Ok(())
+    }
+    Err(e) => Err(InitError::BasisFetch { basis_name: basis_name.to_string(), source: e }),
+  }
+}
+
+/// Adapts `load(element, basis_name)` into a per-element loader that asks
+/// for each element's override, or `basis_name` when it has none.
+fn per_element_basis<'a, F>(
+  basis_name: &'a str,
+  overrides: &'a BTreeMap<String, String>,
+  load: F,
+) -> impl Fn(&str) -> Result<BasisSet, InitError> + Sync + 'a
+where
+  F: Fn(&str, &str) -> Result<BasisSet, InitError> + Sync + 'a,
+{
+  move |element| load(element, overrides.get(element).map_or(basis_name, String::as_str))
+}
+
 /// Testable core: the load function is injected so tests can bypass I/O.
+/// With `fold_norms` each stored coefficient is c·N(α, l) (see
+/// [`AoBasis::norms_folded`]); otherwise coefficients are copied verbatim.
+///
+/// `load_fn` runs once per unique element, each call on its own scoped
+/// thread, so slow downloads overlap. The result does not depend on which
+/// call finishes first; if several fail, the error of the element that
+/// occurs first in `geometry` is returned.
 fn init_basis_impl<F>(
   geometry: &CartesianGeometry,
+  fold_norms: bool,
   load_fn: F,
 ) -> Result<AoBasis, InitError>
 where
-  F: Fn(&str) -> Result<BasisSet, InitError>,
+  F: Fn(&str) -> Result<BasisSet, InitError> + Sync,
 {
-  // Load a BasisSet for each unique element symbol, in first-occurrence order.
-  let mut element_basis: std::collections::HashMap<String, BasisSet> =
-    std::collections::HashMap::new();
-  for symbol in &geometry.symbols {
-    if !element_basis.contains_key(symbol) {
-      let bs = load_fn(symbol)?;
-      element_basis.insert(symbol.clone(), bs);
-    }
+  let symbols = geometry.unique_elements();
+  let loaded: Vec<Result<BasisSet, InitError>> = std::thread::scope(|scope| {
+    let load_fn = &load_fn;
+    let handles: Vec<_> = symbols
+      .iter()
+      .map(|symbol| scope.spawn(move || load_fn(symbol)))
+      .collect();
+    handles
+      .into_iter()
+      .map(|h| h.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+      .collect()
+  });
+  let mut element_basis: HashMap<String, BasisSet> = HashMap::new();
+  for (symbol, bs) in symbols.into_iter().zip(loaded) {
+    element_basis.insert(symbol, bs?);
   }
 
   let mut n_basis = 0usize;
@@ -113,7 +659,14 @@ where
       prim_offset_vec.push(prim_offset);
       n_primitives_vec.push(n_prim);
       exponents_vec.extend_from_slice(&shell.exponents);
-      coefficients_vec.extend_from_slice(&shell.coefficients);
+      if fold_norms {
+        let l = shell.angular_momentum;
+        coefficients_vec.extend(
+          shell.exponents.iter().zip(&shell.coefficients).map(|(&a, &c)| c * primitive_norm(a, l)),
+        );
+      } else {
+        coefficients_vec.extend_from_slice(&shell.coefficients);
+      }
       prim_offset += n_prim;
 
       for (lx, ly, lz) in cartesian_components(shell.angular_momentum) {
@@ -145,6 +698,7 @@ where
     n_primitives: n_primitives_vec,
     exponents: exponents_vec,
     coefficients: coefficients_vec,
+    norms_folded: fold_norms,
   })
 }
 
@@ -173,36 +727,349 @@ fn cartesian_components(l: u32) -> Vec<(u32, u32, u32)> {
   result
 }
 
+/// `(Z, R)` for every atom of `geometry`, with Z = 0 for ghost centers.
+fn nuclei(geometry: &CartesianGeometry) -> Vec<(f64, [f64; 3])> {
+  geometry
+    .atomic_numbers()
+    .into_iter()
+    .enumerate()
+    .map(|(i, z)| (f64::from(z), [geometry.x[i], geometry.y[i], geometry.z[i]]))
+    .collect()
+}
+
+/// ⟨φ_μ|φ_ν⟩ summed over the primitive pairs of both contractions.
+fn contracted_overlap(basis: &AoBasis, mu: usize, nu: usize) -> f64 {
+  contracted_integral(basis, mu, nu, primitive_overlap)
+}
+
+/// ⟨φ_μ|−½∇²|φ_ν⟩ summed over the primitive pairs of both contractions.
+fn contracted_kinetic(basis: &AoBasis, mu: usize, nu: usize) -> f64 {
+  contracted_integral(basis, mu, nu, primitive_kinetic)
+}
+
+/// Σ_C −Z_C ⟨φ_μ|1/r_C|φ_ν⟩ summed over the primitive pairs of both
+/// contractions.
+fn contracted_nuclear(basis: &AoBasis, mu: usize, nu: usize, nuclei: &[(f64, [f64; 3])]) -> f64 {
+  contracted_integral(basis, mu, nu, |axes, a_l, b_l| primitive_nuclear(axes, a_l, b_l, nuclei))
+}
+
+/// Unnormalized primitive overlap ⟨g_a|g_b⟩.
+fn primitive_overlap(axes: &[PairAxis; 3], a_l: [u32; 3], b_l: [u32; 3]) -> f64 {
+  (0..3).map(|k| axes[k].overlap(a_l[k], b_l[k])).product()
+}
+
+/// Unnormalized primitive kinetic energy ⟨g_a|−½∇²|g_b⟩.
+fn primitive_kinetic(axes: &[PairAxis; 3], a_l: [u32; 3], b_l: [u32; 3]) -> f64 {
+  let s: Vec<f64> = (0..3).map(|k| axes[k].overlap(a_l[k], b_l[k])).collect();
+  (0..3)
+    .map(|k| axes[k].kinetic(a_l[k], b_l[k]) * s[(k + 1) % 3] * s[(k + 2) % 3])
+    .sum()
+}
+
+/// Unnormalized primitive nuclear attraction Σ_C −Z_C ⟨g_a|1/r_C|g_b⟩.
+fn primitive_nuclear(
+  axes: &[PairAxis; 3],
+  a_l: [u32; 3],
+  b_l: [u32; 3],
+  nuclei: &[(f64, [f64; 3])],
+) -> f64 {
+  let e: Vec<Vec<f64>> = (0..3).map(|k| axes[k].hermite(a_l[k], b_l[k])).collect();
+  let p = axes[0].p;
+  let l_total = (e[0].len() + e[1].len() + e[2].len() - 3) as u32;
+  let mut sum = 0.0;
+  for &(z, c) in nuclei {
+    let pc = [0, 1, 2].map(|k| axes[k].pk - c[k]);
+    let t_arg = p * (pc[0] * pc[0] + pc[1] * pc[1] + pc[2] * pc[2]);
+    let boys_values = boys(l_total, t_arg);
+    let mut attraction = 0.0;
+    for (t, ex) in e[0].iter().enumerate() {
+      for (u, ey) in e[1].iter().enumerate() {
+        for (w, ez) in e[2].iter().enumerate() {
+          attraction += ex * ey * ez * hermite_coulomb(0, t, u, w, p, pc, &boys_values);
+        }
+      }
+    }
+    sum -= z * attraction;
+  }
+  2.0 * std::f64::consts::PI / p * sum
+}
+
+/// Hermite Coulomb integral R^n_{tuv} for total exponent `p` and P − C = `pc`:
+///
+///   R^n_{000}     = (−2p)^n F_n(p |PC|²)
+///   R^n_{t+1,u,v} = t R^{n+1}_{t−1,u,v} + X_PC R^{n+1}_{t,u,v}
+///
+/// and likewise for u and v.
+fn hermite_coulomb(n: usize, t: usize, u: usize, v: usize, p: f64, pc: [f64; 3], boys_values: &[f64]) -> f64 {
+  if t > 0 {
+    let lower = if t > 1 { (t - 1) as f64 * hermite_coulomb(n + 1, t - 2, u, v, p, pc, boys_values) } else { 0.0 };
+    return lower + pc[0] * hermite_coulomb(n + 1, t - 1, u, v, p, pc, boys_values);
+  }
+  if u > 0 {
+    let lower = if u > 1 { (u - 1) as f64 * hermite_coulomb(n + 1, t, u - 2, v, p, pc, boys_values) } else { 0.0 };
+    return lower + pc[1] * hermite_coulomb(n + 1, t, u - 1, v, p, pc, boys_values);
+  }
+  if v > 0 {
+    let lower = if v > 1 { (v - 1) as f64 * hermite_coulomb(n + 1, t, u, v - 2, p, pc, boys_values) } else { 0.0 };
+    return lower + pc[2] * hermite_coulomb(n + 1, t, u, v - 1, p, pc, boys_values);
+  }
+  (-2.0 * p).powi(n as i32) * boys_values[n]
+}
+
+/// Boys function values F_0(t) … F_{n_max}(t).
+///
+/// F_{n_max} comes from its series for t < 30 and from the asymptotic form
+/// (2n−1)!! / 2^{n+1} · √(π / t^{2n+1}) beyond; lower orders follow by the
+/// stable downward recursion F_{n−1} = (2t F_n + e^{−t}) / (2n − 1).
+fn boys(n_max: u32, t: f64) -> Vec<f64> {
+  let n = n_max as f64;
+  let top = if t < 30.0 {
+    // F_n(t) = e^{−t} Σ_k (2t)^k / ((2n+1)(2n+3)…(2n+2k+1)).
+    let mut term = 1.0 / (2.0 * n + 1.0);
+    let mut sum = term;
+    let mut k = 1.0;
+    while term > 1e-17 * sum {
+      term *= 2.0 * t / (2.0 * n + 2.0 * k + 1.0);
+      sum += term;
+      k += 1.0;
+    }
+    (-t).exp() * sum
+  } else {
+    odd_double_factorial(n_max) / 2f64.powi(n_max as i32 + 1)
+      * (std::f64::consts::PI / t.powi(2 * n_max as i32 + 1)).sqrt()
+  };
+  let mut values = vec![0.0; n_max as usize + 1];
+  values[n_max as usize] = top;
+  let exp_t = (-t).exp();
+  for m in (1..=n_max as usize).rev() {
+    values[m - 1] = (2.0 * t * values[m] + exp_t) / (2 * m - 1) as f64;
+  }
+  values
+}
+
+/// Σ_ij c_i c_j N_i N_j ⟨g_i|Ô|g_j⟩ for basis functions `mu` and `nu`, where
+/// `primitive` evaluates the unnormalized primitive integral from the three
+/// per-axis pair data and the (lx, ly, lz) of each side.
+fn contracted_integral<F>(basis: &AoBasis, mu: usize, nu: usize, primitive: F) -> f64
+where
+  F: Fn(&[PairAxis; 3], [u32; 3], [u32; 3]) -> f64,
+{
+  let [value] = contracted_integrals(basis, mu, nu, |axes, a_l, b_l| [primitive(axes, a_l, b_l)]);
+  value
+}
+
+/// [`contracted_integral`] for `N` operators at once: every primitive pair's
+/// setup is built once and handed to `primitive`, which returns all `N`
+/// unnormalized integrals.
+fn contracted_integrals<F, const N: usize>(
+  basis: &AoBasis,
+  mu: usize,
+  nu: usize,
+  primitive: F,
+) -> [f64; N]
+where
+  F: Fn(&[PairAxis; 3], [u32; 3], [u32; 3]) -> [f64; N],
+{
+  let a_center = [basis.center_x[mu], basis.center_y[mu], basis.center_z[mu]];
+  let b_center = [basis.center_x[nu], basis.center_y[nu], basis.center_z[nu]];
+  let a_l = [basis.lx[mu], basis.ly[mu], basis.lz[mu]];
+  let b_l = [basis.lx[nu], basis.ly[nu], basis.lz[nu]];
+  let a_prims = primitive_range(basis, mu);
+  let b_prims = primitive_range(basis, nu);
+
+  let norm = |alpha: f64, l: [u32; 3]| {
+    if basis.norms_folded { component_norm_ratio(l) } else { cartesian_norm(alpha, l) }
+  };
+
+  let mut sum = [0.0; N];
+  for i in a_prims {
+    let (alpha, c_a) = (basis.exponents[i], basis.coefficients[i]);
+    let n_a = norm(alpha, a_l);
+    for j in b_prims.clone() {
+      let (beta, c_b) = (basis.exponents[j], basis.coefficients[j]);
+      let n_b = norm(beta, b_l);
+      let axes = [0, 1, 2].map(|k| PairAxis::new(alpha, beta, a_center[k], b_center[k]));
+      let weight = c_a * c_b * n_a * n_b;
+      for (total, value) in sum.iter_mut().zip(primitive(&axes, a_l, b_l)) {
+        *total += weight * value;
+      }
+    }
+  }
+  sum
+}
+
+/// One Cartesian direction of a primitive pair e^{−α(x−A)²}, e^{−β(x−B)²}.
+struct PairAxis {
+  beta: f64,
+  p: f64,
+  /// Coordinate of the Gaussian product center P.
+  pk: f64,
+  xpa: f64,
+  xpb: f64,
+  /// S_00 = √(π/p) e^{−αβ(A−B)²/p}.
+  s00: f64,
+}
+
+impl PairAxis {
+  fn new(alpha: f64, beta: f64, a: f64, b: f64) -> Self {
+    let p = alpha + beta;
+    let center = (alpha * a + beta * b) / p;
+    let ab = a - b;
+    PairAxis {
+      beta,
+      p,
+      pk: center,
+      xpa: center - a,
+      xpb: center - b,
+      s00: (std::f64::consts::PI / p).sqrt() * (-alpha * beta / p * ab * ab).exp(),
+    }
+  }
+
+  /// McMurchie–Davidson Hermite coefficients E^{ij}_t for t = 0..=i+j, from
+  /// E^{00}_0 = e^{−αβ(A−B)²/p} and
+  ///
+  ///   E^{i+1,j}_t = E^{ij}_{t−1} / 2p + X_PA E^{ij}_t + (t+1) E^{ij}_{t+1}
+  ///
+  /// (X_PB in place of X_PA to raise j).
+  fn hermite(&self, i: u32, j: u32) -> Vec<f64> {
+    let inv_2p = 0.5 / self.p;
+    let raise = |e: &[f64], x: f64| -> Vec<f64> {
+      (0..=e.len())
+        .map(|t| {
+          let below = if t > 0 { inv_2p * e[t - 1] } else { 0.0 };
+          let here = e.get(t).map_or(0.0, |v| x * v);
+          let above = e.get(t + 1).map_or(0.0, |v| (t + 1) as f64 * v);
+          below + here + above
+        })
+        .collect()
+    };
+    let mut e = vec![self.s00 * (self.p / std::f64::consts::PI).sqrt()];
+    for _ in 0..i {
+      e = raise(&e, self.xpa);
+    }
+    for _ in 0..j {
+      e = raise(&e, self.xpb);
+    }
+    e
+  }
+
+  /// S_ij along this axis.
+  fn overlap(&self, i: u32, j: u32) -> f64 {
+    self.s00 * overlap_1d(i, j, self.xpa, self.xpb, self.p)
+  }
+
+  /// T_ij = −½ ⟨i|d²/dx²|j⟩ along this axis, from overlaps with the ket power
+  /// shifted by ±2:
+  ///
+  ///   T_ij = β(2j+1) S_ij − 2β² S_{i,j+2} − ½ j(j−1) S_{i,j−2}
+  fn kinetic(&self, i: u32, j: u32) -> f64 {
+    let b = self.beta;
+    let mut t = b * (2 * j + 1) as f64 * self.overlap(i, j) - 2.0 * b * b * self.overlap(i, j + 2);
+    if j >= 2 {
+      t -= 0.5 * (j * (j - 1)) as f64 * self.overlap(i, j - 2);
+    }
+    t
+  }
+}
+
+/// Indices into the flat primitive arrays for basis function `mu`.
+fn primitive_range(basis: &AoBasis, mu: usize) -> Range<usize> {
+  shell_primitives(basis, basis.shell_index[mu])
+}
+
+/// Indices into the flat primitive arrays for `shell`.
+fn shell_primitives(basis: &AoBasis, shell: usize) -> Range<usize> {
+  let start = basis.prim_offset[shell];
+  start..start + basis.n_primitives[shell]
+}
+
+/// Normalization of x^lx y^ly z^lz e^{-αr²}: the axial norm for L = lx+ly+lz
+/// rescaled by √((2L−1)!! / ((2lx−1)!! (2ly−1)!! (2lz−1)!!)).
+fn cartesian_norm(alpha: f64, l: [u32; 3]) -> f64 {
+  primitive_norm(alpha, l[0] + l[1] + l[2]) * component_norm_ratio(l)
+}
+
+/// √((2L−1)!! / ((2lx−1)!! (2ly−1)!! (2lz−1)!!)): the Cartesian normalization
+/// relative to the axial component with the same L.
+fn component_norm_ratio(l: [u32; 3]) -> f64 {
+  let total = l[0] + l[1] + l[2];
+  (odd_double_factorial(total)
+    / (odd_double_factorial(l[0]) * odd_double_factorial(l[1]) * odd_double_factorial(l[2])))
+  .sqrt()
+}
+
+/// One-dimensional Obara–Saika overlap ratio S_ij / S_00 for powers `i`, `j`,
+/// with X_PA = `xpa`, X_PB = `xpb` and total exponent `p`:
+///
+///   S_{i+1,j} = X_PA S_ij + (i S_{i−1,j} + j S_{i,j−1}) / 2p
+///   S_{i,j+1} = X_PB S_ij + (i S_{i−1,j} + j S_{i,j−1}) / 2p
+fn overlap_1d(i: u32, j: u32, xpa: f64, xpb: f64, p: f64) -> f64 {
+  let (i, j) = (i as usize, j as usize);
+  let inv_2p = 0.5 / p;
+  // table[a][b] = S_ab / S_00.
+  let mut table = vec![vec![0.0; j + 1]; i + 1];
+  table[0][0] = 1.0;
+  for a in 0..=i {
+    for b in 0..=j {
+      if a == 0 && b == 0 {
+        continue;
+      }
+      table[a][b] = if a > 0 {
+        // Raise a from a−1.
+        let prev = a - 1;
+        let mut v = xpa * table[prev][b];
+        if prev > 0 { v += inv_2p * prev as f64 * table[prev - 1][b]; }
+        if b > 0 { v += inv_2p * b as f64 * table[prev][b - 1]; }
+        v
+      } else {
+        // Raise b from b−1 (a = 0).
+        let prev = b - 1;
+        let mut v = xpb * table[0][prev];
+        if prev > 0 { v += inv_2p * prev as f64 * table[0][prev - 1]; }
+        v
+      };
+    }
+  }
+  table[i][j]
+}
+
 // ─── Tests ────────────────────────────────────────────────────────────────────
 
 #[cfg(test)]
 mod tests {
   use super::*;
   use crate::basis::{BasisSet, BseError, ElectronShell, LoadError};
+  use crate::guess::guess_hcore;
   use crate::input::CartesianGeometry;
-  use std::sync::Arc;
+  use crate::util::mats_approx_eq;
   use std::sync::atomic::{AtomicUsize, Ordering};
 
   // ── Test helpers ────────────────────────────────────────────────────────────
 
   fn shell(l: u32, exponents: Vec<f64>, coefficients: Vec<f64>) -> ElectronShell {
-    ElectronShell { angular_momentum: l, exponents, coefficients }
+    ElectronShell { angular_momentum: l, exponents, coefficients, general_contraction_group: None }
   }
 
   fn uniform_shell(l: u32, n_prim: usize) -> ElectronShell {
     shell(l, vec![1.0; n_prim], vec![1.0; n_prim])
   }
 
+  fn unknown_basis(name: &str) -> BseError {
+    BseError::UnknownBasisSet { basis_name: name.to_string(), url: String::new() }
+  }
+
   fn make_basis(element: &str, shells: Vec<ElectronShell>) -> BasisSet {
-    BasisSet { element: element.to_string(), atomic_number: 1, shells }
+    BasisSet { element: element.to_string(), atomic_number: 1, shells, ecp: None }
   }
 
   fn geometry(symbols: Vec<&str>, xs: Vec<f64>, ys: Vec<f64>, zs: Vec<f64>) -> CartesianGeometry {
     CartesianGeometry {
-      symbols: symbols.into_iter().map(|s| s.to_string()).collect(),
+      symbols: symbols.iter().map(|s| s.to_string()).collect(),
+      labels: symbols.iter().map(|s| s.to_string()).collect(),
       x: xs,
       y: ys,
       z: zs,
+      is_ghost: vec![false; symbols.len()],
     }
   }
 
@@ -222,7 +1089,7 @@ mod tests {
   #[test]
   fn empty_molecule_returns_empty_basis() {
     let geom = geometry(vec![], vec![], vec![], vec![]);
-    let result = init_basis_impl(&geom, |e| panic!("load_fn called for {}", e));
+    let result = init_basis_impl(&geom, false, |e| panic!("load_fn called for {}", e));
     let b = result.expect("should succeed");
     assert_eq!(b.n_basis, 0);
     assert_eq!(b.n_shells, 0);
@@ -245,7 +1112,7 @@ mod tests {
   fn single_h_one_s_shell_gives_one_basis_function() {
     let geom = single_atom("H", 0.0, 0.0, 0.0);
     let bs = make_basis("H", vec![uniform_shell(0, 3)]);
-    let b = init_basis_impl(&geom, fixed_load(bs)).expect("should succeed");
+    let b = init_basis_impl(&geom, false, fixed_load(bs)).expect("should succeed");
     assert_eq!(b.n_basis, 1);
     assert_eq!(b.n_shells, 1);
   }
@@ -255,7 +1122,7 @@ mod tests {
   fn single_c_one_s_one_p_gives_four_basis_functions() {
     let geom = single_atom("C", 0.0, 0.0, 0.0);
     let bs = make_basis("C", vec![uniform_shell(0, 3), uniform_shell(1, 3)]);
-    let b = init_basis_impl(&geom, fixed_load(bs)).expect("should succeed");
+    let b = init_basis_impl(&geom, false, fixed_load(bs)).expect("should succeed");
     assert_eq!(b.n_basis, 4);
     assert_eq!(b.n_shells, 2);
   }
@@ -265,7 +1132,7 @@ mod tests {
   fn single_d_shell_gives_six_basis_functions() {
     let geom = single_atom("X", 0.0, 0.0, 0.0);
     let bs = make_basis("X", vec![uniform_shell(2, 2)]);
-    let b = init_basis_impl(&geom, fixed_load(bs)).expect("should succeed");
+    let b = init_basis_impl(&geom, false, fixed_load(bs)).expect("should succeed");
     assert_eq!(b.n_basis, 6);
     assert_eq!(b.n_shells, 1);
   }
@@ -282,7 +1149,7 @@ mod tests {
     );
     let o_basis = make_basis("O", vec![uniform_shell(0, 3), uniform_shell(0, 3), uniform_shell(1, 3)]);
     let h_basis = make_basis("H", vec![uniform_shell(0, 3)]);
-    let b = init_basis_impl(&geom, |e| {
+    let b = init_basis_impl(&geom, false, |e| {
       let bs = if e == "O" { o_basis.clone() } else { h_basis.clone() };
       Ok(bs)
     }).expect("should succeed");
@@ -302,7 +1169,7 @@ mod tests {
       vec![0.0, 0.0],
     );
     let bs = make_basis("H", vec![uniform_shell(0, 3)]);
-    let b = init_basis_impl(&geom, fixed_load(bs)).expect("should succeed");
+    let b = init_basis_impl(&geom, false, fixed_load(bs)).expect("should succeed");
     assert_eq!(b.n_basis, 2);
     assert!((b.center_x[0] - 0.0).abs() < 1e-12);
     assert!((b.center_x[1] - 1.0).abs() < 1e-12);
@@ -324,7 +1191,7 @@ mod tests {
       uniform_shell(1, 3),
     ]);
     let h_basis = make_basis("H", vec![uniform_shell(0, 3)]);
-    let b = init_basis_impl(&geom, |e| {
+    let b = init_basis_impl(&geom, false, |e| {
       let bs = if e == "O" { o_basis.clone() } else { h_basis.clone() };
       Ok(bs)
     }).expect("should succeed");
@@ -338,6 +1205,39 @@ mod tests {
     assert_eq!(b.atom_index[6], 2);
   }
 
+  // ── Function ranges ──────────────────────────────────────────────────────────
+
+  // Water with O = s, s, p (5 functions) and H = s (1 function each).
+  fn water_basis() -> AoBasis {
+    let geom = geometry(vec!["O", "H", "H"], vec![0.0; 3], vec![0.0, 1.4, -1.4], vec![0.0, 1.1, 1.1]);
+    init_basis_impl(&geom, false, |e| {
+      Ok(if e == "O" {
+        make_basis("O", vec![uniform_shell(0, 3), uniform_shell(0, 3), uniform_shell(1, 3)])
+      } else {
+        make_basis("H", vec![uniform_shell(0, 3)])
+      })
+    }).unwrap()
+  }
+
+  /// Scenario: O owns functions 0–4 and each H one function after it
+  #[test]
+  fn functions_on_atom_water() {
+    let b = water_basis();
+    assert_eq!(b.functions_on_atom(0), 0..5);
+    assert_eq!(b.functions_on_atom(1), 5..6);
+    assert_eq!(b.functions_on_atom(2), 6..7);
+    assert!(b.functions_on_atom(3).is_empty());
+  }
+
+  /// Scenario: The O p shell spans functions 2–4; every other shell is one function
+  #[test]
+  fn functions_in_shell_water() {
+    let b = water_basis();
+    let ranges: Vec<Range<usize>> = (0..b.n_shells).map(|s| b.functions_in_shell(s)).collect();
+    assert_eq!(ranges, vec![0..1, 1..2, 2..5, 5..6, 6..7]);
+    assert!(b.functions_in_shell(b.n_shells).is_empty());
+  }
+
   // ── Shell ordering within an atom ────────────────────────────────────────────
 
   /// Scenario: Shells for one atom appear in basis-file order
@@ -350,7 +1250,7 @@ mod tests {
       uniform_shell(1, 3), // shell 1: 3 functions
       uniform_shell(0, 3), // shell 2: 1 function
     ]);
-    let b = init_basis_impl(&geom, fixed_load(bs)).expect("should succeed");
+    let b = init_basis_impl(&geom, false, fixed_load(bs)).expect("should succeed");
     assert_eq!(b.n_basis, 5);
     assert_eq!(b.shell_index[0], 0);
     assert_eq!(b.shell_index[1], 1);
@@ -366,7 +1266,7 @@ mod tests {
   fn s_shell_emits_one_component() {
     let geom = single_atom("H", 0.0, 0.0, 0.0);
     let bs = make_basis("H", vec![uniform_shell(0, 1)]);
-    let b = init_basis_impl(&geom, fixed_load(bs)).expect("should succeed");
+    let b = init_basis_impl(&geom, false, fixed_load(bs)).expect("should succeed");
     assert_eq!(b.lx[0], 0);
     assert_eq!(b.ly[0], 0);
     assert_eq!(b.lz[0], 0);
@@ -377,7 +1277,7 @@ mod tests {
   fn p_shell_emits_components_in_correct_order() {
     let geom = single_atom("H", 0.0, 0.0, 0.0);
     let bs = make_basis("H", vec![uniform_shell(1, 1)]);
-    let b = init_basis_impl(&geom, fixed_load(bs)).expect("should succeed");
+    let b = init_basis_impl(&geom, false, fixed_load(bs)).expect("should succeed");
     assert_eq!(b.n_basis, 3);
     assert_eq!((b.lx[0], b.ly[0], b.lz[0]), (1, 0, 0));
     assert_eq!((b.lx[1], b.ly[1], b.lz[1]), (0, 1, 0));
@@ -389,7 +1289,7 @@ mod tests {
   fn d_shell_emits_six_components_in_correct_order() {
     let geom = single_atom("H", 0.0, 0.0, 0.0);
     let bs = make_basis("H", vec![uniform_shell(2, 1)]);
-    let b = init_basis_impl(&geom, fixed_load(bs)).expect("should succeed");
+    let b = init_basis_impl(&geom, false, fixed_load(bs)).expect("should succeed");
     assert_eq!(b.n_basis, 6);
     let expected = [(2,0,0),(1,1,0),(1,0,1),(0,2,0),(0,1,1),(0,0,2)];
     for (i, (ex, ey, ez)) in expected.iter().enumerate() {
@@ -407,7 +1307,7 @@ mod tests {
     let exps = vec![3.425, 0.624, 0.169];
     let coeffs = vec![0.154, 0.535, 0.445];
     let bs = make_basis("H", vec![shell(0, exps.clone(), coeffs.clone())]);
-    let b = init_basis_impl(&geom, fixed_load(bs)).expect("should succeed");
+    let b = init_basis_impl(&geom, false, fixed_load(bs)).expect("should succeed");
     assert_eq!(b.n_primitives[0], 3);
     assert_eq!(b.prim_offset[0], 0);
     for i in 0..3 {
@@ -424,7 +1324,7 @@ mod tests {
       uniform_shell(0, 3), // shell 0: 3 primitives
       uniform_shell(0, 2), // shell 1: 2 primitives
     ]);
-    let b = init_basis_impl(&geom, fixed_load(bs)).expect("should succeed");
+    let b = init_basis_impl(&geom, false, fixed_load(bs)).expect("should succeed");
     assert_eq!(b.prim_offset[0], 0);
     assert_eq!(b.prim_offset[1], 3);
   }
@@ -434,7 +1334,7 @@ mod tests {
   fn all_cartesian_functions_of_shell_share_shell_index() {
     let geom = single_atom("H", 0.0, 0.0, 0.0);
     let bs = make_basis("H", vec![uniform_shell(1, 1)]);
-    let b = init_basis_impl(&geom, fixed_load(bs)).expect("should succeed");
+    let b = init_basis_impl(&geom, false, fixed_load(bs)).expect("should succeed");
     assert_eq!(b.n_basis, 3);
     assert_eq!(b.shell_index[0], 0);
     assert_eq!(b.shell_index[1], 0);
@@ -448,7 +1348,7 @@ mod tests {
   fn center_coordinates_taken_from_geometry() {
     let geom = single_atom("H", 1.5, 2.5, 3.5);
     let bs = make_basis("H", vec![uniform_shell(0, 1)]);
-    let b = init_basis_impl(&geom, fixed_load(bs)).expect("should succeed");
+    let b = init_basis_impl(&geom, false, fixed_load(bs)).expect("should succeed");
     assert!((b.center_x[0] - 1.5).abs() < 1e-12);
     assert!((b.center_y[0] - 2.5).abs() < 1e-12);
     assert!((b.center_z[0] - 3.5).abs() < 1e-12);
@@ -459,7 +1359,7 @@ mod tests {
   fn all_functions_for_same_atom_share_coordinates() {
     let geom = single_atom("X", 1.0, 0.0, 0.0);
     let bs = make_basis("X", vec![uniform_shell(1, 1)]); // p-shell: 3 functions
-    let b = init_basis_impl(&geom, fixed_load(bs)).expect("should succeed");
+    let b = init_basis_impl(&geom, false, fixed_load(bs)).expect("should succeed");
     assert_eq!(b.n_basis, 3);
     for i in 0..3 {
       assert!((b.center_x[i] - 1.0).abs() < 1e-12, "center_x[{i}] wrong");
@@ -484,7 +1384,7 @@ mod tests {
     let call_count = Arc::new(AtomicUsize::new(0));
     let count_clone = call_count.clone();
     let bs_clone = h_basis.clone();
-    let b = init_basis_impl(&geom, move |_| {
+    let b = init_basis_impl(&geom, false, move |_| {
       count_clone.fetch_add(1, Ordering::SeqCst);
       Ok(bs_clone.clone())
     }).expect("should succeed");
@@ -499,16 +1399,44 @@ mod tests {
     }
   }
 
+  // ── Parallel loading ─────────────────────────────────────────────────────────
+
+  /// Scenario: The three element loads run at once (each waits for the
+  /// other two), and the basis keeps geometry order whichever finishes first
+  #[test]
+  fn unique_elements_load_concurrently() {
+    let geom = geometry(vec!["O", "H", "C", "H"], vec![0.0; 4], vec![0.0; 4], vec![0.0, 1.0, 2.0, 3.0]);
+    let all_started = std::sync::Barrier::new(3);
+    let b = init_basis_impl(&geom, false, |e| {
+      // Serial loading would block here forever.
+      all_started.wait();
+      // Later elements finish first.
+      let (delay_ms, l) = match e {
+        "O" => (40, 1),
+        "H" => (20, 0),
+        _ => (0, 2),
+      };
+      std::thread::sleep(std::time::Duration::from_millis(delay_ms));
+      Ok(make_basis(e, vec![uniform_shell(l, 1)]))
+    })
+    .unwrap();
+    // O p (3), H s (1), C d (6), H s (1), in geometry order.
+    assert_eq!(b.n_basis, 11);
+    assert_eq!(b.atom_index, vec![0, 0, 0, 1, 2, 2, 2, 2, 2, 2, 3]);
+    assert_eq!(b.lx[..3], [1, 0, 0]);
+    assert_eq!(b.lx[4..10], [2, 1, 1, 0, 0, 0]);
+  }
+
   // ── Error handling ───────────────────────────────────────────────────────────
 
   /// Scenario: InitError::BasisLoad is returned when load_basis fails for an element
   #[test]
   fn basis_load_error_returned_on_failure() {
     let geom = single_atom("H", 0.0, 0.0, 0.0);
-    let result = init_basis_impl(&geom, |element| {
+    let result = init_basis_impl(&geom, false, |element| {
       Err(InitError::BasisLoad {
         element: element.to_string(),
-        source: LoadError::Fetch(BseError::UnknownBasisSet("unknown-basis".to_string())),
+        source: LoadError::Fetch(unknown_basis("unknown-basis")),
       })
     });
     assert!(
@@ -527,13 +1455,13 @@ mod tests {
       vec![0.0, 0.0],
     );
     let h_basis = make_basis("H", vec![uniform_shell(0, 3)]);
-    let result = init_basis_impl(&geom, move |element| {
+    let result = init_basis_impl(&geom, false, move |element| {
       if element == "H" {
         Ok(h_basis.clone())
       } else {
         Err(InitError::BasisLoad {
           element: element.to_string(),
-          source: LoadError::Fetch(BseError::UnknownBasisSet("sto-3g".to_string())),
+          source: LoadError::Fetch(unknown_basis("sto-3g")),
         })
       }
     });
@@ -542,4 +1470,784 @@ mod tests {
       "expected BasisLoad error identifying element C"
     );
   }
+
+  // ── Per-element basis overrides ──────────────────────────────────────────────
+
+  fn fe_h2() -> CartesianGeometry {
+    geometry(
+      vec!["H", "Fe", "H"],
+      vec![0.0, 1.5, 3.0],
+      vec![0.0, 0.0, 0.0],
+      vec![0.0, 0.0, 0.0],
+    )
+  }
+
+  /// Scenario: Overridden elements are loaded with their own basis name
+  #[test]
+  fn overrides_request_per_element_basis() {
+    let overrides: BTreeMap<String, String> =
+      [("Fe".to_string(), "def2-tzvp".to_string())].into_iter().collect();
+    let requests = Mutex::new(Vec::new());
+    let b = init_basis_impl(&fe_h2(), false, per_element_basis("def2-svp", &overrides, |el, name| {
+      requests.lock().unwrap().push((el.to_string(), name.to_string()));
+      Ok(make_basis(el, vec![uniform_shell(0, 1)]))
+    }))
+    .unwrap();
+    assert_eq!(b.n_shells, 3);
+    // Loads run concurrently, so compare the requests in a fixed order.
+    let mut requests = requests.into_inner().unwrap();
+    requests.sort();
+    assert_eq!(
+      requests,
+      vec![
+        ("Fe".to_string(), "def2-tzvp".to_string()),
+        ("H".to_string(), "def2-svp".to_string()),
+      ]
+    );
+  }
+
+  /// Scenario: Elements are grouped for prefetching by the basis they use
+  #[test]
+  fn group_by_basis_follows_overrides() {
+    let overrides: BTreeMap<String, String> =
+      [("Fe".to_string(), "def2-tzvp".to_string())].into_iter().collect();
+    assert_eq!(
+      group_by_basis(&fe_h2(), "def2-svp", &overrides),
+      vec![("def2-svp", vec!["H".to_string()]), ("def2-tzvp", vec!["Fe".to_string()])]
+    );
+    assert_eq!(
+      group_by_basis(&fe_h2(), "sto-3g", &BTreeMap::new()),
+      vec![("sto-3g", vec!["H".to_string(), "Fe".to_string()])]
+    );
+  }
+
+  /// Scenario: Cached per-element basis files are used under their override name
+  #[test]
+  fn overrides_load_from_cache() {
+    let dir = tempfile::tempdir().unwrap();
+    let s_shell = r#"{"function_type":"gto","angular_momentum":[0],"exponents":["1.0"],"coefficients":[["1.0"]]}"#;
+    for (name, shells) in [("basis-a", 1), ("basis-b", 2)] {
+      let json = format!(
+        r#"{{"elements":{{"1":{{"electron_shells":[{}]}}}}}}"#,
+        vec![s_shell; shells].join(",")
+      );
+      std::fs::create_dir_all(dir.path().join(name)).unwrap();
+      std::fs::write(dir.path().join(name).join("H.json"), json).unwrap();
+    }
+    let geom = single_atom("H", 0.0, 0.0, 0.0);
+    let overrides: BTreeMap<String, String> =
+      [("H".to_string(), "basis-b".to_string())].into_iter().collect();
+    let b = init_basis_with_overrides(&geom, "basis-a", &overrides, dir.path()).unwrap();
+    assert_eq!(b.n_shells, 2);
+    assert_eq!(init_basis_in(&geom, "basis-a", dir.path()).unwrap().n_shells, 1);
+  }
+
+  // ── In-memory basis cache ────────────────────────────────────────────────────
+
+  /// Scenario: Two cached builds of water load each element's basis once
+  #[test]
+  fn basis_cache_loads_each_element_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = BasisCache::new(dir.path());
+    let loads = Mutex::new(Vec::new());
+    let load = |element: &str, _: &str| {
+      loads.lock().unwrap().push(element.to_string());
+      Ok(make_basis(element, vec![shell(0, vec![1.0], vec![1.0])]))
+    };
+    let geom = geometry(vec!["O", "H", "H"], vec![0.0; 3], vec![0.0, 1.4, -1.4], vec![0.0, 1.1, 1.1]);
+    for _ in 0..2 {
+      let b = init_basis_impl(&geom, true, |e| Ok((*cache.load_with(e, "sto-3g", load)?).clone())).unwrap();
+      assert_eq!(b.n_basis, 3);
+    }
+    let mut loads = loads.into_inner().unwrap();
+    loads.sort();
+    assert_eq!(loads, vec!["H", "O"]);
+    assert!(cache.contains("O", "sto-3g") && !cache.contains("O", "6-31g"));
+  }
+
+  /// Scenario: init_basis_cached reads the disk cache once, then serves
+  /// later builds from memory even after the files are gone
+  #[test]
+  fn init_basis_cached_reuses_parsed_sets() {
+    let dir = tempfile::tempdir().unwrap();
+    let json = r#"{"elements":{"1":{"electron_shells":[{"function_type":"gto","angular_momentum":[0],"exponents":["1.0"],"coefficients":[["1.0"]]}]}}}"#;
+    std::fs::create_dir_all(dir.path().join("basis-a")).unwrap();
+    std::fs::write(dir.path().join("basis-a").join("H.json"), json).unwrap();
+    let cache = BasisCache::new(dir.path());
+    let geom = geometry(vec!["H", "H"], vec![0.0, 0.0], vec![0.0, 0.0], vec![0.0, 1.4]);
+    assert_eq!(init_basis_cached(&geom, "basis-a", &cache).unwrap().n_basis, 2);
+
+    std::fs::remove_dir_all(dir.path().join("basis-a")).unwrap();
+    let again = init_basis_cached(&geom, "basis-a", &cache).unwrap();
+    assert_eq!(again.n_basis, 2);
+    assert!(Arc::ptr_eq(&cache.load("H", "basis-a").unwrap(), &cache.load("H", "basis-a").unwrap()));
+  }
+
+  /// Scenario: A failed load is not cached
+  #[test]
+  fn basis_cache_retries_failed_loads() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = BasisCache::new(dir.path());
+    let fail = |element: &str, _: &str| Err(InitError::BasisLoad {
+      element: element.to_string(),
+      source: LoadError::Fetch(unknown_basis("x")),
+    });
+    assert!(cache.load_with("H", "x", fail).is_err());
+    assert!(!cache.contains("H", "x"));
+    assert!(cache.load_with("H", "x", |e, _| Ok(make_basis(e, vec![]))).is_ok());
+  }
+
+  /// Scenario: UnsupportedElements names the basis and every missing element
+  #[test]
+  fn unsupported_elements_display_lists_all() {
+    let err = InitError::UnsupportedElements {
+      basis_name: "sto-3g".to_string(),
+      elements: vec!["Au".to_string(), "Pt".to_string()],
+    };
+    assert_eq!(err.to_string(), "basis sto-3g does not cover Au, Pt");
+  }
+
+  // ── Overlap matrix ───────────────────────────────────────────────────────────
+
+  fn assert_close(a: f64, b: f64, tol: f64) {
+    assert!((a - b).abs() < tol, "{a} vs {b}");
+  }
+
+  /// Scenario: An empty basis gives a 0 × 0 overlap matrix
+  #[test]
+  fn overlap_empty_basis() {
+    let geom = geometry(vec![], vec![], vec![], vec![]);
+    let b = init_basis_impl(&geom, true, |e| panic!("load_fn called for {}", e)).unwrap();
+    let s = overlap_matrix(&b);
+    assert_eq!((s.nrows(), s.ncols()), (0, 0));
+  }
+
+  /// Scenario: Two identical s-functions on the same center overlap to 1.0
+  #[test]
+  fn overlap_two_s_same_center() {
+    let geom = geometry(vec!["H", "H"], vec![0.0, 0.0], vec![0.0, 0.0], vec![0.0, 0.0]);
+    let bs = make_basis("H", vec![shell(0, vec![0.8], vec![1.0])]);
+    let b = init_basis_impl(&geom, true, fixed_load(bs)).unwrap();
+    let s = overlap_matrix(&b);
+    for i in 0..2 {
+      for j in 0..2 {
+        assert_close(s[(i, j)], 1.0, 1e-14);
+      }
+    }
+  }
+
+  /// Scenario: Two normalized s-primitives separated by R along z overlap by
+  /// (2√(αβ)/(α+β))^{3/2} exp(−αβR²/(α+β))
+  #[test]
+  fn overlap_two_s_separated_along_z() {
+    let (alpha, beta, r) = (0.5, 1.2, 1.4);
+    let geom = geometry(vec!["H", "He"], vec![0.0, 0.0], vec![0.0, 0.0], vec![0.0, r]);
+    let b = init_basis_impl(&geom, true, |e| {
+      let exp = if e == "H" { alpha } else { beta };
+      Ok(make_basis(e, vec![shell(0, vec![exp], vec![1.0])]))
+    }).unwrap();
+    let s = overlap_matrix(&b);
+    let p = alpha + beta;
+    let expected = (2.0 * (alpha * beta).sqrt() / p).powf(1.5) * (-alpha * beta * r * r / p).exp();
+    assert_close(s[(0, 1)], expected, 1e-14);
+    assert_close(s[(1, 0)], expected, 1e-14);
+    assert_close(s[(0, 0)], 1.0, 1e-14);
+    assert_close(s[(1, 1)], 1.0, 1e-14);
+  }
+
+  /// Scenario: Every Cartesian d component of a normalized primitive has unit
+  /// self-overlap, including the xy-type ones
+  #[test]
+  fn overlap_d_components_normalized() {
+    let geom = single_atom("X", 0.0, 0.0, 0.0);
+    let bs = make_basis("X", vec![shell(2, vec![0.9], vec![1.0])]);
+    let b = init_basis_impl(&geom, true, fixed_load(bs)).unwrap();
+    let s = overlap_matrix(&b);
+    for i in 0..6 {
+      assert_close(s[(i, i)], 1.0, 1e-13);
+    }
+    // ⟨x²|y²⟩ = 1/3 for normalized Cartesian d functions on one center.
+    assert_close(s[(0, 3)], 1.0 / 3.0, 1e-13);
+    // ⟨x²|xy⟩ vanishes by parity.
+    assert_close(s[(0, 1)], 0.0, 1e-14);
+  }
+
+  /// Scenario: s on A and p_z on B overlap antisymmetrically in the separation
+  #[test]
+  fn overlap_s_pz_sign_and_value() {
+    let (alpha, beta, r) = (1.0, 1.0, 1.0);
+    let geom = geometry(vec!["H", "C"], vec![0.0, 0.0], vec![0.0, 0.0], vec![0.0, r]);
+    let b = init_basis_impl(&geom, true, |e| {
+      let l = if e == "H" { 0 } else { 1 };
+      let exp = if e == "H" { alpha } else { beta };
+      Ok(make_basis(e, vec![shell(l, vec![exp], vec![1.0])]))
+    }).unwrap();
+    let s = overlap_matrix(&b);
+    // Basis order: s(H), px, py, pz (C).
+    let p = alpha + beta;
+    let ss = (2.0 * (alpha * beta).sqrt() / p).powf(1.5) * (-alpha * beta * r * r / p).exp();
+    // X_PB = −α R / p, and N_p / N_s = 2√β for the p primitive.
+    let expected = ss * 2.0 * beta.sqrt() * (-alpha * r / p);
+    assert_close(s[(0, 3)], expected, 1e-14);
+    assert_close(s[(0, 1)], 0.0, 1e-14);
+    assert_close(s[(0, 2)], 0.0, 1e-14);
+  }
+
+  /// Scenario: Contracted functions use the stored coefficients
+  #[test]
+  fn overlap_contracted_sto3g_h_is_normalized() {
+    let geom = single_atom("H", 0.0, 0.0, 0.0);
+    let bs = make_basis("H", vec![shell(
+      0,
+      vec![3.42525091, 0.62391373, 0.16885540],
+      vec![0.15432897, 0.53532814, 0.44463454],
+    )]);
+    let b = init_basis_impl(&geom, true, fixed_load(bs)).unwrap();
+    assert_close(overlap_matrix(&b)[(0, 0)], 1.0, 1e-6);
+  }
+
+  // ── Kinetic energy matrix ────────────────────────────────────────────────────
+
+  fn sto3g_h() -> BasisSet {
+    make_basis("H", vec![shell(
+      0,
+      vec![3.42525091, 0.62391373, 0.16885540],
+      vec![0.15432897, 0.53532814, 0.44463454],
+    )])
+  }
+
+  /// Scenario: A ghost hydrogen carries its 1s function but no nuclear charge
+  #[test]
+  fn ghost_atom_has_basis_but_no_charge() {
+    let mut geom = geometry(vec!["H", "H"], vec![0.0, 0.0], vec![0.0, 0.0], vec![0.0, 1.4]);
+    geom.is_ghost = vec![true, false];
+    let b = init_basis_impl(&geom, true, fixed_load(sto3g_h())).unwrap();
+    assert_eq!(b.n_basis, 2);
+    assert_eq!(b.atom_index, vec![0, 1]);
+
+    // V only sees the real nucleus at z = 1.4.
+    let v = nuclear_attraction_matrix(&b, &geom);
+    let v_real = nuclear_attraction_matrix(&b, &single_atom("H", 0.0, 0.0, 1.4));
+    assert!(mats_approx_eq(&v, &v_real, 1e-14));
+  }
+
+  /// Scenario: STO-3G hydrogen 1s kinetic self-term is 0.7600 Hartree
+  /// (Szabo & Ostlund, Table 3.5)
+  #[test]
+  fn kinetic_sto3g_h_self_term() {
+    let geom = single_atom("H", 0.0, 0.0, 0.0);
+    let b = init_basis_impl(&geom, true, fixed_load(sto3g_h())).unwrap();
+    assert_close(kinetic_matrix(&b)[(0, 0)], 0.7600, 1e-4);
+  }
+
+  /// Scenario: STO-3G H₂ at R = 1.4 Bohr has T₁₂ = 0.2365 Hartree
+  #[test]
+  fn kinetic_sto3g_h2_off_diagonal() {
+    let geom = geometry(vec!["H", "H"], vec![0.0, 0.0], vec![0.0, 0.0], vec![0.0, 1.4]);
+    let b = init_basis_impl(&geom, true, fixed_load(sto3g_h())).unwrap();
+    assert_close(kinetic_matrix(&b)[(0, 1)], 0.2365, 1e-4);
+  }
+
+  /// Scenario: A single normalized s-primitive has T = 3α/2
+  #[test]
+  fn kinetic_s_primitive_analytic() {
+    let geom = single_atom("H", 0.0, 0.0, 0.0);
+    let bs = make_basis("H", vec![shell(0, vec![0.7], vec![1.0])]);
+    let b = init_basis_impl(&geom, true, fixed_load(bs)).unwrap();
+    assert_close(kinetic_matrix(&b)[(0, 0)], 1.5 * 0.7, 1e-14);
+  }
+
+  /// Scenario: A normalized p-primitive has T = 5α/2 for every component
+  #[test]
+  fn kinetic_p_primitive_analytic() {
+    let geom = single_atom("C", 0.0, 0.0, 0.0);
+    let bs = make_basis("C", vec![shell(1, vec![0.9], vec![1.0])]);
+    let b = init_basis_impl(&geom, true, fixed_load(bs)).unwrap();
+    let t = kinetic_matrix(&b);
+    for i in 0..3 {
+      assert_close(t[(i, i)], 2.5 * 0.9, 1e-13);
+    }
+  }
+
+  /// Scenario: T is symmetric for a mixed s/p carbon basis on two centers, and
+  /// the recurrence gives the same value in either bra/ket order
+  #[test]
+  fn kinetic_mixed_sp_carbon_symmetric() {
+    let geom = geometry(vec!["C", "C"], vec![0.0, 0.3], vec![0.0, -0.2], vec![0.0, 2.5]);
+    let bs = make_basis("C", vec![
+      shell(0, vec![71.6168370, 13.0450960, 3.5305122], vec![0.15432897, 0.53532814, 0.44463454]),
+      shell(0, vec![2.9412494, 0.6834831, 0.2222899], vec![-0.09996723, 0.39951283, 0.70011547]),
+      shell(1, vec![2.9412494, 0.6834831, 0.2222899], vec![0.15591627, 0.60768372, 0.39195739]),
+    ]);
+    let b = init_basis_impl(&geom, true, fixed_load(bs)).unwrap();
+    let t = kinetic_matrix(&b);
+    assert_eq!((t.nrows(), t.ncols()), (10, 10));
+    for i in 0..10 {
+      for j in 0..10 {
+        assert_eq!(t[(i, j)], t[(j, i)]);
+        assert_close(contracted_kinetic(&b, j, i), t[(i, j)], 1e-12);
+      }
+    }
+  }
+
+  // ── Coefficient normalization ────────────────────────────────────────────────
+
+  /// Scenario: Folding multiplies each coefficient by N(α, l) of its shell
+  #[test]
+  fn folded_coefficients_include_primitive_norm() {
+    let geom = single_atom("C", 0.0, 0.0, 0.0);
+    let bs = make_basis("C", vec![shell(0, vec![2.0], vec![0.5]), shell(1, vec![0.8, 0.3], vec![0.4, 0.7])]);
+    let b = init_basis_impl(&geom, true, fixed_load(bs)).unwrap();
+    assert!(b.norms_folded);
+    assert_close(b.coefficients[0], 0.5 * primitive_norm(2.0, 0), 1e-14);
+    assert_close(b.coefficients[1], 0.4 * primitive_norm(0.8, 1), 1e-14);
+    assert_close(b.coefficients[2], 0.7 * primitive_norm(0.3, 1), 1e-14);
+  }
+
+  /// Scenario: A single s-primitive with coefficient 1 is self-normalized
+  /// after folding
+  #[test]
+  fn folded_single_s_primitive_is_normalized() {
+    let geom = single_atom("H", 0.0, 0.0, 0.0);
+    let bs = make_basis("H", vec![shell(0, vec![1.3], vec![1.0])]);
+    let b = init_basis_impl(&geom, true, fixed_load(bs)).unwrap();
+    assert_close(overlap_matrix(&b)[(0, 0)], 1.0, 1e-14);
+  }
+
+  /// Scenario: Raw and folded coefficients give the same integrals
+  #[test]
+  fn folded_and_raw_give_same_integrals() {
+    let geom = geometry(vec!["X", "X"], vec![0.0, 0.4], vec![0.0, 0.0], vec![0.0, 1.7]);
+    let bs = make_basis("X", vec![
+      shell(0, vec![1.1, 0.3], vec![0.6, 0.5]),
+      shell(2, vec![0.9, 0.25], vec![0.7, 0.4]),
+    ]);
+    let raw = init_basis_impl(&geom, false, fixed_load(bs.clone())).unwrap();
+    let folded = init_basis_impl(&geom, true, fixed_load(bs)).unwrap();
+    assert!(!raw.norms_folded);
+    let (s_raw, s_folded) = (overlap_matrix(&raw), overlap_matrix(&folded));
+    let (t_raw, t_folded) = (kinetic_matrix(&raw), kinetic_matrix(&folded));
+    assert!(mats_approx_eq(&s_raw, &s_folded, 1e-13));
+    assert!(mats_approx_eq(&t_raw, &t_folded, 1e-13));
+  }
+
+  // ── Nuclear attraction matrix ────────────────────────────────────────────────
+
+  /// Scenario: F_0(0) = 1 and F_n(0) = 1/(2n+1)
+  #[test]
+  fn boys_at_zero() {
+    let f = boys(3, 0.0);
+    for (n, v) in f.iter().enumerate() {
+      assert_close(*v, 1.0 / (2 * n + 1) as f64, 1e-15);
+    }
+  }
+
+  /// Scenario: F_0(t) = ½ √(π/t) erf(√t) on both sides of the series cutoff
+  #[test]
+  fn boys_zero_order_matches_erf_form() {
+    // erf(√t) ≈ 1 for t ≥ 30, so compare there directly.
+    assert_close(boys(0, 40.0)[0], 0.5 * (std::f64::consts::PI / 40.0).sqrt(), 1e-14);
+    // Continuity across t = 30.
+    let below = boys(2, 30.0 - 1e-9);
+    let above = boys(2, 30.0);
+    for n in 0..3 {
+      assert_close(below[n], above[n], 1e-12);
+    }
+  }
+
+  /// Scenario: A unit-charge nucleus at the center of a normalized s-primitive
+  /// gives V = −2√(2α/π)
+  #[test]
+  fn nuclear_s_primitive_on_center() {
+    let alpha = 0.9;
+    let geom = single_atom("H", 0.0, 0.0, 0.0);
+    let bs = make_basis("H", vec![shell(0, vec![alpha], vec![1.0])]);
+    let b = init_basis_impl(&geom, true, fixed_load(bs)).unwrap();
+    let v = nuclear_attraction_matrix(&b, &geom);
+    assert_close(v[(0, 0)], -2.0 * (2.0 * alpha / std::f64::consts::PI).sqrt(), 1e-13);
+  }
+
+  /// Scenario: V scales with the nuclear charge
+  #[test]
+  fn nuclear_scales_with_charge() {
+    let bs = make_basis("X", vec![shell(0, vec![0.9], vec![1.0])]);
+    let h = single_atom("H", 0.0, 0.0, 0.0);
+    let li = single_atom("Li", 0.0, 0.0, 0.0);
+    let b = init_basis_impl(&h, true, fixed_load(bs)).unwrap();
+    let v_h = nuclear_attraction_matrix(&b, &h)[(0, 0)];
+    let v_li = nuclear_attraction_matrix(&b, &li)[(0, 0)];
+    assert_close(v_li, 3.0 * v_h, 1e-13);
+  }
+
+  /// Scenario: V is symmetric and the p-on-s element flips sign under
+  /// reflection of the nucleus
+  #[test]
+  fn nuclear_mixed_sp_symmetric() {
+    let geom = geometry(vec!["C", "H"], vec![0.0, 0.0], vec![0.0, 0.2], vec![0.0, 2.0]);
+    let b = init_basis_impl(&geom, true, |e| {
+      Ok(if e == "C" {
+        make_basis("C", vec![shell(0, vec![1.2, 0.4], vec![0.5, 0.6]), shell(1, vec![0.8], vec![1.0])])
+      } else {
+        make_basis("H", vec![shell(0, vec![0.7], vec![1.0])])
+      })
+    }).unwrap();
+    let v = nuclear_attraction_matrix(&b, &geom);
+    assert_eq!((v.nrows(), v.ncols()), (5, 5));
+    for i in 0..5 {
+      for j in 0..5 {
+        assert_eq!(v[(i, j)], v[(j, i)]);
+        assert_close(contracted_nuclear(&b, j, i, &[(6.0, [0.0; 3]), (1.0, [0.0, 0.2, 2.0])]), v[(i, j)], 1e-12);
+      }
+    }
+    // Mirroring H through the xz plane flips ⟨p_y(C)|V|s(C)⟩; only the H
+    // nucleus contributes to it, since the C nucleus sits on both functions.
+    let mirrored = geometry(vec!["C", "H"], vec![0.0, 0.0], vec![0.0, -0.2], vec![0.0, 2.0]);
+    let v_mirrored = nuclear_attraction_matrix(&b, &mirrored);
+    assert!(v[(2, 0)].abs() > 1e-6);
+    assert_close(v_mirrored[(2, 0)], -v[(2, 0)], 1e-14);
+  }
+
+  /// Scenario: STO-3G H₂ at R = 1.4 Bohr matches Szabo & Ostlund's V¹ + V²
+  /// (V₁₁ = V₂₂ = −1.2266 − 0.6538, V₁₂ = 2 × −0.5974)
+  #[test]
+  fn nuclear_sto3g_h2_reference_values() {
+    let geom = geometry(vec!["H", "H"], vec![0.0, 0.0], vec![0.0, 0.0], vec![0.0, 1.4]);
+    let b = init_basis_impl(&geom, true, fixed_load(sto3g_h())).unwrap();
+    let v = nuclear_attraction_matrix(&b, &geom);
+    let expected = [[-1.8804, -1.1948], [-1.1948, -1.8804]];
+    for i in 0..2 {
+      for j in 0..2 {
+        assert_close(v[(i, j)], expected[i][j], 1e-4);
+      }
+    }
+  }
+
+  // ── One-electron integral bundle ─────────────────────────────────────────────
+
+  /// Scenario: The bundle matches the individual S, T and V builders
+  #[test]
+  fn bundle_matches_individual_builders() {
+    let geom = geometry(vec!["C", "H"], vec![0.0, 0.3], vec![0.0, 0.2], vec![0.0, 2.0]);
+    let b = init_basis_impl(&geom, true, |e| {
+      Ok(if e == "C" {
+        make_basis("C", vec![shell(0, vec![1.2, 0.4], vec![0.5, 0.6]), shell(2, vec![0.8], vec![1.0])])
+      } else {
+        sto3g_h()
+      })
+    }).unwrap();
+    let ints = one_electron_integrals(&b, &geom);
+    let pairs = [
+      (&ints.s, overlap_matrix(&b)),
+      (&ints.t, kinetic_matrix(&b)),
+      (&ints.v, nuclear_attraction_matrix(&b, &geom)),
+    ];
+    for (bundled, single) in pairs {
+      assert_eq!((bundled.nrows(), bundled.ncols()), (b.n_basis, b.n_basis));
+      assert!(mats_approx_eq(bundled, &single, 1e-14));
+    }
+  }
+
+  /// Scenario: init_basis → one_electron_integrals → guess_hcore gives an
+  /// S-orthonormal C for H₂
+  #[test]
+  fn bundle_feeds_guess_hcore_for_h2() {
+    let geom = geometry(vec!["H", "H"], vec![0.0, 0.0], vec![0.0, 0.0], vec![0.0, 1.4]);
+    let b = init_basis_impl(&geom, true, fixed_load(sto3g_h())).unwrap();
+    let OneElectronIntegrals { s, t, v } = one_electron_integrals(&b, &geom);
+    let c = guess_hcore(&s, &t, &v, 1, 1).unwrap();
+    let ctsc = c.transpose() * &s * &c;
+    assert!(mats_approx_eq(&ctsc, &Mat::identity(2, 2), 1e-12));
+  }
+
+  // ── Shell pairs ──────────────────────────────────────────────────────────────
+
+  fn tight_s() -> BasisSet {
+    make_basis("H", vec![shell(0, vec![10.0], vec![1.0])])
+  }
+
+  /// Scenario: Tight Gaussians 10 Bohr apart are screened out, while each
+  /// on-center pair is kept
+  #[test]
+  fn shell_pairs_screen_distant_tight_gaussians() {
+    let geom = geometry(vec!["H", "H"], vec![0.0, 0.0], vec![0.0, 0.0], vec![0.0, 10.0]);
+    let b = init_basis_impl(&geom, true, fixed_load(tight_s())).unwrap();
+    let pairs = shell_pairs(&b);
+    let kept: Vec<(usize, usize)> = pairs.iter().map(|sp| (sp.shell_a, sp.shell_b)).collect();
+    assert_eq!(kept, vec![(0, 0), (1, 1)]);
+  }
+
+  /// Scenario: An on-center pair keeps p = 2α, P = A and K = 1
+  #[test]
+  fn shell_pairs_on_center_data() {
+    let geom = single_atom("H", 0.5, -1.0, 2.0);
+    let b = init_basis_impl(&geom, true, fixed_load(tight_s())).unwrap();
+    let pairs = shell_pairs(&b);
+    assert_eq!(pairs.len(), 1);
+    assert_eq!(
+      pairs[0].primitives,
+      vec![PrimitivePair { i: 0, j: 0, p: 20.0, center: [0.5, -1.0, 2.0], prefactor: 1.0 }]
+    );
+  }
+
+  /// Scenario: With nothing screened, every pair a ≤ b and every primitive
+  /// pair is listed
+  #[test]
+  fn shell_pairs_unscreened_lists_upper_triangle() {
+    let geom = geometry(vec!["H", "H"], vec![0.0, 0.0], vec![0.0, 0.0], vec![0.0, 1.4]);
+    let b = init_basis_impl(&geom, true, fixed_load(sto3g_h())).unwrap();
+    let pairs = shell_pairs_with_threshold(&b, 0.0);
+    assert_eq!(pairs.len(), 3);
+    assert!(pairs.iter().all(|sp| sp.shell_a <= sp.shell_b && sp.primitives.len() == 9));
+    let cross = &pairs[1];
+    assert_eq!((cross.shell_a, cross.shell_b), (0, 1));
+    let first = &cross.primitives[0];
+    assert_close(first.center[2], 0.7, 1e-15);
+    assert_close(first.prefactor, (-3.42525091 / 2.0 * 1.96f64).exp(), 1e-15);
+  }
+
+  // ── Integral cache ───────────────────────────────────────────────────────────
+
+  // The cache is process-wide; tests that clear it or count misses must not
+  // interleave.
+  static CACHE_LOCK: Mutex<()> = Mutex::new(());
+
+  // STO-3G integrals for `geom`, counting each evaluation in `calls`.
+  fn counted_compute(geom: &CartesianGeometry, calls: &AtomicUsize) -> Result<OneElectronMatrices, InitError> {
+    calls.fetch_add(1, Ordering::SeqCst);
+    let b = init_basis_impl(geom, true, fixed_load(sto3g_h()))?;
+    Ok((overlap_matrix(&b), kinetic_matrix(&b), nuclear_attraction_matrix(&b, geom)))
+  }
+
+  /// Scenario: Two calls on an identical geometry compute the integrals once
+  #[test]
+  fn cache_identical_geometry_computes_once() {
+    let _guard = CACHE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    clear_integral_cache();
+    let geom = geometry(vec!["H", "H"], vec![0.0, 0.0], vec![0.0, 0.0], vec![0.0, 1.4]);
+    let calls = AtomicUsize::new(0);
+    let first = cached_integrals_with(&geom, "sto-3g", || counted_compute(&geom, &calls)).unwrap();
+    let second = cached_integrals_with(&geom, "sto-3g", || counted_compute(&geom, &calls)).unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(first.0, second.0);
+    assert_eq!(first.1, second.1);
+    assert_eq!(first.2, second.2);
+    assert_close(second.0[(0, 1)], 0.6593, 1e-4);
+  }
+
+  /// Scenario: A displaced geometry or a different basis name is a miss
+  #[test]
+  fn cache_distinguishes_geometry_and_basis() {
+    let _guard = CACHE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    clear_integral_cache();
+    let a = geometry(vec!["H", "H"], vec![0.0, 0.0], vec![0.0, 0.0], vec![0.0, 1.4]);
+    let b = geometry(vec!["H", "H"], vec![0.0, 0.0], vec![0.0, 0.0], vec![0.0, 1.4 + 1e-12]);
+    let calls = AtomicUsize::new(0);
+    cached_integrals_with(&a, "sto-3g", || counted_compute(&a, &calls)).unwrap();
+    cached_integrals_with(&b, "sto-3g", || counted_compute(&b, &calls)).unwrap();
+    cached_integrals_with(&a, "6-31g", || counted_compute(&a, &calls)).unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+  }
+
+  /// Scenario: clear_integral_cache forces a recompute
+  #[test]
+  fn cache_clear_forces_recompute() {
+    let _guard = CACHE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    clear_integral_cache();
+    let geom = single_atom("H", 0.0, 0.0, 0.0);
+    let calls = AtomicUsize::new(0);
+    cached_integrals_with(&geom, "sto-3g", || counted_compute(&geom, &calls)).unwrap();
+    clear_integral_cache();
+    cached_integrals_with(&geom, "sto-3g", || counted_compute(&geom, &calls)).unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+  }
+
+  /// Scenario: A failed computation is not cached
+  #[test]
+  fn cache_does_not_store_errors() {
+    let _guard = CACHE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    clear_integral_cache();
+    let geom = single_atom("He", 0.0, 0.0, 0.0);
+    let err = cached_integrals_with(&geom, "sto-3g", || {
+      Err(InitError::BasisLoad {
+        element: "He".to_string(),
+        source: LoadError::Fetch(unknown_basis("sto-3g")),
+      })
+    });
+    assert!(err.is_err());
+    let calls = AtomicUsize::new(0);
+    cached_integrals_with(&geom, "sto-3g", || counted_compute(&geom, &calls)).unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+  }
+
+  /// Scenario: A ghost center and a real atom at the same place have different keys
+  #[test]
+  fn canonical_key_distinguishes_ghosts() {
+    let real = single_atom("O", 0.0, 0.0, 0.0);
+    let mut ghost = real.clone();
+    ghost.is_ghost = vec![true];
+    assert_ne!(canonical_key(&real), canonical_key(&ghost));
+  }
+
+  /// Scenario: canonical_key ignores labels and the sign of zero
+  #[test]
+  fn canonical_key_ignores_labels_and_negative_zero() {
+    let a = single_atom("O", 0.0, 1.0, 2.0);
+    let mut b = single_atom("O", -0.0, 1.0, 2.0);
+    b.labels = vec!["O1".to_string()];
+    assert_eq!(canonical_key(&a), canonical_key(&b));
+    assert_ne!(canonical_key(&a), canonical_key(&single_atom("N", 0.0, 1.0, 2.0)));
+  }
+
+  // ── Local basis file ─────────────────────────────────────────────────────────
+
+  const H_AND_C_JSON: &str = r#"{"elements":{
+    "6":{"electron_shells":[
+      {"function_type":"gto","angular_momentum":[0],"exponents":["2.0","0.5"],"coefficients":[["0.4","0.7"]]},
+      {"function_type":"gto","angular_momentum":[1],"exponents":["0.8"],"coefficients":[["1.0"]]}]},
+    "1":{"electron_shells":[
+      {"function_type":"gto","angular_momentum":[0],"exponents":["3.42525091","0.62391373","0.16885540"],
+       "coefficients":[["0.15432897","0.53532814","0.44463454"]]}]}
+  }}"#;
+
+  fn write_basis_file(dir: &tempfile::TempDir, json: &str) -> std::path::PathBuf {
+    let path = dir.path().join("local.json");
+    std::fs::write(&path, json).unwrap();
+    path
+  }
+
+  /// Scenario: A two-element local file supplies the right shells per symbol
+  /// without touching the cache
+  #[test]
+  fn basis_file_selects_element_per_symbol() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let file = write_basis_file(&dir, H_AND_C_JSON);
+    let geom = geometry(vec!["H", "C", "H"], vec![0.0; 3], vec![0.0; 3], vec![-2.0, 0.0, 2.0]);
+    let empty_cache = dir.path().join("no-cache");
+    let b = init_basis_with_file(&geom, "sto-3g", &BTreeMap::new(), &file, &empty_cache).unwrap();
+    // H (1) + C (1 s + 3 p) + H (1).
+    assert_eq!(b.n_basis, 6);
+    assert_eq!(b.n_shells, 4);
+    assert_eq!(b.n_primitives, vec![3, 2, 1, 3]);
+    assert_eq!(b.exponents[3..5], [2.0, 0.5]);
+    assert_eq!(b.atom_index, vec![0, 1, 1, 1, 1, 2]);
+    assert_close(overlap_matrix(&b)[(0, 0)], 1.0, 1e-6);
+    assert!(!empty_cache.exists());
+  }
+
+  /// Scenario: A Gaussian94 basis file works like the JSON one
+  #[test]
+  fn basis_file_accepts_gaussian94() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let file = write_basis_file(&dir, "****\nH 0\nS 3 1.00\n 3.42525091 0.15432897\n\
+      0.62391373 0.53532814\n 0.16885540 0.44463454\n****\n");
+    let geom = geometry(vec!["H", "H"], vec![0.0, 0.0], vec![0.0, 0.0], vec![0.0, 1.4]);
+    let no_cache = dir.path().join("no-cache");
+    let b = init_basis_with_file(&geom, "sto-3g", &BTreeMap::new(), &file, &no_cache).unwrap();
+    assert_close(overlap_matrix(&b)[(0, 1)], 0.6593, 1e-4);
+  }
+
+  /// Scenario: Elements missing from the local file fall back to the loader
+  #[test]
+  fn basis_file_falls_back_for_missing_element() {
+    let local = vec![sto3g_h()];
+    let fallback_calls = Arc::new(AtomicUsize::new(0));
+    let calls = Arc::clone(&fallback_calls);
+    let load = local_first(local, move |e| {
+      calls.fetch_add(1, Ordering::SeqCst);
+      assert_eq!(e, "O");
+      Ok(make_basis("O", vec![uniform_shell(0, 1), uniform_shell(1, 1)]))
+    });
+    let geom = geometry(vec!["O", "H", "H"], vec![0.0; 3], vec![0.0, 1.4, -1.4], vec![0.0, 1.1, 1.1]);
+    let b = init_basis_impl(&geom, false, load).unwrap();
+    assert_eq!(b.n_basis, 6);
+    assert_eq!(fallback_calls.load(Ordering::SeqCst), 1);
+  }
+
+  /// Scenario: An unreadable local file is a BasisFile error naming the path
+  #[test]
+  fn basis_file_parse_error() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let file = write_basis_file(&dir, "{not json");
+    let geom = single_atom("H", 0.0, 0.0, 0.0);
+    let err = init_basis_with_file(&geom, "sto-3g", &BTreeMap::new(), &file, dir.path()).unwrap_err();
+    assert!(matches!(&err, InitError::BasisFile { source: ParseError::InvalidJson(_), .. }));
+    assert!(err.to_string().contains("local.json"));
+  }
+
+  // ── BSE prefetching ──────────────────────────────────────────────────────────
+
+  const STO3G_H_C_METADATA: &str =
+    r#"{"sto-3g":{"latest_version":"1","versions":{"1":{"elements":["1","6"]}}}}"#;
+
+  /// Scenario: Uncached elements are downloaded in one request and the
+  /// per-element loads are then served from the cache
+  #[test]
+  fn init_basis_prefetches_in_one_request() {
+    let mut server = mockito::Server::new();
+    let _metadata = server.mock("GET", "/api/metadata").with_body(STO3G_H_C_METADATA).create();
+    let batch = server
+      .mock("GET", "/api/basis/sto-3g/format/qcschema?elements=H,C")
+      .with_body(H_AND_C_JSON)
+      .expect(1)
+      .create();
+    let single = server
+      .mock("GET", mockito::Matcher::Regex("elements=(H|C)$".to_string()))
+      .expect(0)
+      .create();
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let geom = geometry(vec!["H", "C", "H"], vec![0.0; 3], vec![0.0; 3], vec![-2.0, 0.0, 2.0]);
+    let b = init_basis_from(&geom, "sto-3g", &BTreeMap::new(), &server.url(), dir.path()).unwrap();
+    assert_eq!(b.n_basis, 6);
+    batch.assert();
+    single.assert();
+  }
+
+  /// Scenario: An unknown basis in the batch request is reported, not retried
+  /// per element
+  #[test]
+  fn init_basis_reports_batch_fetch_failure() {
+    let mut server = mockito::Server::new();
+    let _metadata = server.mock("GET", "/api/metadata").with_status(404).create();
+    let batch = server
+      .mock("GET", "/api/basis/nope/format/qcschema?elements=H")
+      .with_status(404)
+      .expect(1)
+      .create();
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let geom = single_atom("H", 0.0, 0.0, 0.0);
+    let err = init_basis_from(&geom, "nope", &BTreeMap::new(), &server.url(), dir.path()).unwrap_err();
+    assert!(matches!(
+      err,
+      InitError::BasisFetch { source: BseError::UnknownBasisSet { .. }, .. }
+    ));
+    batch.assert();
+  }
+
+  /// Scenario: An element the metadata does not list fails before any basis
+  /// is downloaded
+  #[test]
+  fn init_basis_rejects_uncovered_element_before_downloading() {
+    let mut server = mockito::Server::new();
+    let _metadata = server
+      .mock("GET", "/api/metadata")
+      .with_body(r#"{"sto-3g":{"latest_version":"1","versions":{"1":{"elements":["1"]}}}}"#)
+      .create();
+    let downloads = server
+      .mock("GET", mockito::Matcher::Regex("^/api/basis/".to_string()))
+      .expect(0)
+      .create();
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let geom = geometry(vec!["H", "C"], vec![0.0; 2], vec![0.0; 2], vec![0.0, 2.0]);
+    let err = init_basis_from(&geom, "sto-3g", &BTreeMap::new(), &server.url(), dir.path())
+      .unwrap_err();
+    assert!(matches!(
+      &err,
+      InitError::UnsupportedElements { basis_name, elements }
+        if basis_name == "sto-3g" && elements == &["C"]
+    ));
+    downloads.assert();
+  }
 }
@@ -0,0 +1,56 @@
+use faer::Mat;
+
+// ── Public functions ──────────────────────────────────────────────────────────
+
+/// True when `a` and `b` have the same shape and every element differs by at
+/// most `tol`. Any NaN element makes the matrices unequal. Useful for
+/// checking computed integrals or guesses against reference matrices.
+pub fn mats_approx_eq(a: &Mat<f64>, b: &Mat<f64>, tol: f64) -> bool {
+  if a.nrows() != b.nrows() || a.ncols() != b.ncols() {
+    return false;
+  }
+  (0..a.nrows()).all(|i| (0..a.ncols()).all(|j| (a[(i, j)] - b[(i, j)]).abs() <= tol))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn sample() -> Mat<f64> {
+    Mat::from_fn(2, 3, |i, j| (i * 3 + j) as f64 * 0.5)
+  }
+
+  #[test]
+  fn equal_matrices_compare_equal() {
+    assert!(mats_approx_eq(&sample(), &sample(), 0.0));
+  }
+
+  #[test]
+  fn differences_within_tolerance() {
+    let shifted = Mat::from_fn(2, 3, |i, j| sample()[(i, j)] + 1e-9);
+    assert!(mats_approx_eq(&sample(), &shifted, 1e-8));
+    assert!(!mats_approx_eq(&sample(), &shifted, 1e-10));
+  }
+
+  #[test]
+  fn shape_mismatch_is_unequal() {
+    let square: Mat<f64> = Mat::zeros(2, 2);
+    let wide: Mat<f64> = Mat::zeros(2, 3);
+    assert!(!mats_approx_eq(&square, &wide, 1.0));
+    assert!(!mats_approx_eq(&wide, &wide.transpose().to_owned(), 1.0));
+  }
+
+  #[test]
+  fn nan_is_never_equal() {
+    let nan = Mat::from_fn(1, 1, |_, _| f64::NAN);
+    assert!(!mats_approx_eq(&nan, &nan, 1.0));
+  }
+
+  #[test]
+  fn empty_matrices_compare_equal() {
+    let a: Mat<f64> = Mat::zeros(0, 0);
+    assert!(mats_approx_eq(&a, &a, 0.0));
+  }
+}